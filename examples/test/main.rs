@@ -2,12 +2,11 @@
 // This is free software distributed under the terms specified in
 // the file LICENSE at the top-level directory of this distribution.
 
-use text_wrapper::Text;
+use async_parse_wiki_text::WikiText as Text;
 
 extern crate async_parse_wiki_text;
 
 mod test;
-mod test_cases;
 
 #[tokio::main]
 async fn main() {
@@ -37,7 +36,7 @@ async fn main() {
                     println!(
                         "{:#?}",
                         async_parse_wiki_text::Configuration::default()
-                            .parse(Text::new(&wiki_text.replace("\\t", "\t").replace("\\n", "\n"))).await
+                            .parse(Text::new(wiki_text.replace("\\t", "\t").replace("\\n", "\n"))).await
                     );
                     return;
                 }
@@ -5,7 +5,7 @@
 #[path = "test_cases.rs"]
 mod test_cases;
 use test_cases::TEST_CASES;
-use text_wrapper::Text;
+use async_parse_wiki_text::WikiText as Text;
 
 pub async fn run_test(configuration: &async_parse_wiki_text::Configuration) {
     let mut output = concat!(
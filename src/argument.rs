@@ -0,0 +1,225 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use crate::template_name::plain_text;
+use crate::{
+    Node, Parameter, Positioned, RelatedSpan, RelatedSpanKind, Warning, WarningContext,
+    WarningMessage, WikiText,
+};
+
+/// Argument of a template, with its name resolved the same way as Mediawiki.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Argument<'a> {
+    /// The byte position in the wiki text where the value ends, not counting whitespace at the end of the value.
+    pub end: usize,
+
+    /// The value, without leading and trailing comments and whitespace if the argument is named.
+    pub nodes: &'a [Node],
+
+    /// The parameter of the template the argument is given by.
+    pub parameter: &'a Parameter,
+
+    /// The position of the argument among the arguments without a name, starting from 1, or `None` if the argument is named.
+    pub position: Option<usize>,
+
+    /// The byte position in the wiki text where the value starts, not counting whitespace at the start of the value.
+    pub start: usize,
+}
+
+impl<'a> Argument<'a> {
+    /// Returns the name of the argument. An argument without a name is named by its position. Surrounding whitespace and comments are removed from an explicit name, and `None` is returned if the name contains anything but text.
+    #[must_use]
+    pub fn name(&self) -> Option<String> {
+        match (&self.parameter.name, self.position) {
+            (_, Some(position)) => Some(position.to_string()),
+            (Some(name), None) => plain_text(name).map(|name| name.trim().to_owned()),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the wiki text of the value. Like in Mediawiki, the value of a named argument is trimmed, while the value of a positional argument keeps its surrounding whitespace.
+    ///
+    /// The wiki text must be the wiki text the argument was parsed from.
+    #[must_use]
+    pub fn value<'b>(&self, wiki_text: &'b WikiText) -> &'b str {
+        let wiki_text = wiki_text.as_ref();
+        if self.position.is_none() {
+            return &wiki_text[self.start..self.end];
+        }
+        let start = wiki_text[..self.start].trim_end().len();
+        let end = wiki_text.len() - wiki_text[self.end..].trim_start().len();
+        &wiki_text[start..end]
+    }
+}
+
+impl Node {
+    /// Returns the argument given to the template with the given name, or `None` if there is no such argument or the node is not a template.
+    ///
+    /// If the same name is given more than once, the last argument is returned, the same way as Mediawiki. That includes an explicitly named argument such as `2=` overriding a positional argument.
+    #[must_use]
+    pub fn arg(&self, name: &str) -> Option<Argument<'_>> {
+        self.arguments()
+            .into_iter()
+            .rev()
+            .find(|argument| argument.name().as_deref() == Some(name))
+    }
+
+    /// Returns the arguments given to the template in order, or nothing if the node is not a template.
+    #[must_use]
+    pub fn arguments(&self) -> Vec<Argument<'_>> {
+        let Node::Template { parameters, .. } = self else {
+            return vec![];
+        };
+        let mut position = 0;
+        parameters
+            .iter()
+            .map(|parameter| {
+                let named = parameter.name.is_some();
+                let position = if named {
+                    None
+                } else {
+                    position += 1;
+                    Some(position)
+                };
+                let (start, end, nodes) = if named {
+                    trim(&parameter.value, parameter.end)
+                } else {
+                    match (parameter.value.first(), parameter.value.last()) {
+                        (Some(first), Some(last)) => (first.start(), last.end(), &parameter.value[..]),
+                        _ => (parameter.end, parameter.end, &parameter.value[..]),
+                    }
+                };
+                Argument {
+                    end,
+                    nodes,
+                    parameter,
+                    position,
+                    start,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns a warning for every argument given to the template that overrides an earlier argument with the same name, like the tracking category for duplicate arguments in Mediawiki.
+    #[must_use]
+    pub fn duplicate_arguments(&self) -> Vec<Warning> {
+        let mut names = std::collections::HashMap::new();
+        self.arguments()
+            .into_iter()
+            .filter_map(|argument| {
+                let name = argument.name()?;
+                let parameter = argument.parameter;
+                let (start, end) = *names
+                    .entry(name.clone())
+                    .or_insert((parameter.start, parameter.end));
+                if start == parameter.start {
+                    return None;
+                }
+                Some(Warning {
+                    context: Some(WarningContext::TemplateParameter { name }),
+                    end: parameter.end,
+                    fix: None,
+                    message: WarningMessage::DuplicateTemplateParameter,
+                    related: vec![RelatedSpan {
+                        end,
+                        kind: RelatedSpanKind::FirstDefinition,
+                        start,
+                    }],
+                    start: parameter.start,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the positional argument given to the template at the given position, starting from 1, or an explicitly named argument with the number as its name if it comes later.
+    #[must_use]
+    pub fn positional(&self, position: usize) -> Option<Argument<'_>> {
+        self.arg(&position.to_string())
+    }
+}
+
+fn is_whitespace(node: &Node) -> bool {
+    match node {
+        Node::Comment { .. } | Node::ParagraphBreak { .. } => true,
+        Node::Text { value, .. } => value.as_ref().trim().is_empty(),
+        _ => false,
+    }
+}
+
+fn trim(nodes: &[Node], default_position: usize) -> (usize, usize, &[Node]) {
+    let first = nodes.iter().position(|node| !is_whitespace(node));
+    let last = nodes.iter().rposition(|node| !is_whitespace(node));
+    let (Some(first), Some(last)) = (first, last) else {
+        return (default_position, default_position, &[]);
+    };
+    let nodes = &nodes[first..=last];
+    let start = match &nodes[0] {
+        Node::Text { end, value, .. } => end - value.as_ref().trim_start().len(),
+        node => node.start(),
+    };
+    let end = match &nodes[nodes.len() - 1] {
+        Node::Text { start, value, .. } => start + value.as_ref().trim_end().len(),
+        node => node.end(),
+    };
+    (start, end, nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, Node, WarningMessage, WikiText};
+
+    const TEXT: &str = "{{x| a |date = 2020 <!-- c --> | b |2=explicit| c |da<!-- -->te= 2021\n}}";
+
+    async fn template() -> (Node, WikiText) {
+        let wiki_text = WikiText::new(TEXT);
+        let mut output = Configuration::default().parse(wiki_text.clone()).await;
+        (output.nodes.remove(0), wiki_text)
+    }
+
+    #[tokio::test]
+    async fn positional() {
+        let (template, wiki_text) = template().await;
+        assert_eq!(template.positional(1).unwrap().value(&wiki_text), " a ");
+        assert_eq!(template.positional(2).unwrap().value(&wiki_text), "explicit");
+        assert_eq!(template.positional(3).unwrap().value(&wiki_text), " c ");
+        assert_eq!(template.positional(4), None);
+    }
+
+    #[tokio::test]
+    async fn named() {
+        let (template, wiki_text) = template().await;
+        assert_eq!(template.arguments()[1].value(&wiki_text), "2020");
+        assert_eq!(template.arg("date").unwrap().value(&wiki_text), "2021");
+        assert_eq!(template.arg("missing"), None);
+    }
+
+    #[tokio::test]
+    async fn names() {
+        let (template, _) = template().await;
+        assert_eq!(
+            template
+                .arguments()
+                .iter()
+                .map(|argument| argument.name().unwrap())
+                .collect::<Vec<_>>(),
+            ["1", "date", "2", "2", "3", "date"]
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicates() {
+        let (template, _) = template().await;
+        assert_eq!(
+            template
+                .duplicate_arguments()
+                .iter()
+                .map(|warning| (warning.start, warning.message))
+                .collect::<Vec<_>>(),
+            [
+                (36, WarningMessage::DuplicateTemplateParameter),
+                (51, WarningMessage::DuplicateTemplateParameter),
+            ]
+        );
+    }
+}
@@ -8,32 +8,27 @@ use crate::state::State;
 use crate::Node;
 
 pub async fn parse_bold_italic(state: &mut State) {
-    let scan_position = state.scan_position;
-    state.flush(scan_position).await;
     let start_position = state.scan_position;
     state.scan_position += 2;
     while state.get_byte(state.scan_position).await == Some(b'\'') {
         state.scan_position += 1;
     }
     let length = state.scan_position - start_position;
-    if length < 3 {
-        state.flushed_position = state.scan_position;
-        state.nodes.push(Node::Italic {
-            end: state.flushed_position,
-            start: start_position,
-        });
-    } else if length < 5 {
-        state.flushed_position = start_position + 3;
-        state.nodes.push(Node::Bold {
-            end: state.flushed_position,
-            start: start_position,
-        });
-    } else {
-        state.flushed_position = start_position + 5;
-        state.nodes.push(Node::BoldItalic {
-            end: state.flushed_position,
-            start: start_position,
-        });
-    }
+    // As in Mediawiki, the first of four apostrophes and all but the last five of more than five apostrophes are text.
+    let markup_length = match length {
+        2 => 2,
+        3 | 4 => 3,
+        _ => 5,
+    };
+    let markup_start_position = state.scan_position - markup_length;
+    state.flush(markup_start_position).await;
+    state.flushed_position = state.scan_position;
+    let end = state.scan_position;
+    let start = markup_start_position;
+    state.push_node(match markup_length {
+        2 => Node::Italic { end, start },
+        3 => Node::Bold { end, start },
+        _ => Node::BoldItalic { end, start },
+    });
     yield_now().await;
 }
@@ -0,0 +1,175 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use futures_util::{Stream, StreamExt};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use crate::{parse, Configuration, Output, WikiText};
+
+/// Limits on the resources used for parsing a page.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ParseLimits {
+    /// The time after which parsing a page is given up, or `None` for no limit.
+    pub max_duration: Option<Duration>,
+
+    /// The length in bytes of the longest wiki text to parse, or `None` for no limit.
+    pub max_length: Option<usize>,
+
+    /// The time after which a page is reported as slow even though it's parsed, or `None` to not report slow pages.
+    pub slow_threshold: Option<Duration>,
+}
+
+/// Statistics about parsing a page.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PageStats {
+    /// The time spent parsing the page, until it was parsed or parsing was given up.
+    pub duration: Duration,
+
+    /// Whether parsing the page took longer than the slow threshold of the limits, or was given up because it took too long.
+    pub slow: bool,
+
+    /// The number of warnings from the parser. Zero if parsing was given up.
+    pub warnings: usize,
+}
+
+/// Reason why parsing a page was given up.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseAborted {
+    /// Parsing took longer than the maximum duration of the limits.
+    TimedOut {
+        /// The time spent before parsing was given up.
+        elapsed: Duration,
+    },
+
+    /// The wiki text is longer than the maximum length of the limits.
+    TooLong {
+        /// The length in bytes of the wiki text.
+        length: usize,
+    },
+}
+
+impl fmt::Display for ParseAborted {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseAborted::TimedOut { elapsed } => {
+                write!(formatter, "Parsing was given up after {:?}.", elapsed)
+            }
+            ParseAborted::TooLong { length } => write!(
+                formatter,
+                "The wiki text of {} bytes is longer than the limit.",
+                length
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseAborted {}
+
+impl Configuration {
+    /// Parses wiki text into structured data, giving up if the wiki text is too long or parsing takes too long.
+    pub async fn parse_with_limits(&self, wiki_text: WikiText, limits: &ParseLimits) -> Result<Output, ParseAborted> {
+        let length = wiki_text.len();
+        if limits.max_length.is_some_and(|max_length| length > max_length) {
+            return Err(ParseAborted::TooLong { length });
+        }
+        let start = Instant::now();
+        let deadline = limits.max_duration.map(|max_duration| start + max_duration);
+        parse::parse_until(self, wiki_text, deadline)
+            .await
+            .ok_or_else(|| ParseAborted::TimedOut {
+                elapsed: start.elapsed(),
+            })
+    }
+}
+
+/// Parses many pages concurrently, each page in its own task, and yields the result for each page together with its identifier and statistics.
+///
+/// At most `concurrency` pages are parsed at the same time, and no more pages are taken from the input until the results of earlier pages have been consumed. When `ordered` is `true`, the results are yielded in the order of the input, otherwise each result is yielded as soon as it's ready, so that a slow page doesn't hold back the pages after it. Pages that are too long or take too long to parse according to the limits are given up and yielded with an error.
+///
+/// Must be called within a Tokio runtime. Panics if parsing a page panics.
+pub fn parse_many<I, S>(
+    configuration: Arc<Configuration>,
+    pages: S,
+    concurrency: usize,
+    ordered: bool,
+    limits: ParseLimits,
+) -> impl Stream<Item = (I, Result<Output, ParseAborted>, PageStats)>
+where
+    I: Send + 'static,
+    S: Stream<Item = (I, WikiText)>,
+{
+    let concurrency = concurrency.max(1);
+    let tasks = pages.map(move |(id, wiki_text)| {
+        let configuration = configuration.clone();
+        let task = tokio::spawn(async move {
+            let start = Instant::now();
+            let result = configuration.parse_with_limits(wiki_text, &limits).await;
+            let duration = start.elapsed();
+            let stats = PageStats {
+                duration,
+                slow: matches!(result, Err(ParseAborted::TimedOut { .. }))
+                    || limits.slow_threshold.is_some_and(|threshold| duration > threshold),
+                warnings: result.as_ref().map_or(0, |output| output.warnings.len()),
+            };
+            (id, result, stats)
+        });
+        async move {
+            match task.await {
+                Err(error) => std::panic::resume_unwind(error.into_panic()),
+                Ok(result) => result,
+            }
+        }
+    });
+    if ordered {
+        tasks.buffered(concurrency).left_stream()
+    } else {
+        tasks.buffer_unordered(concurrency).right_stream()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::{stream, StreamExt};
+    use std::{sync::Arc, time::Duration};
+    use crate::{parse_many, Configuration, ParseAborted, ParseLimits, WikiText};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn it_works() {
+        let pages = vec![
+            (1, WikiText::new("''a''")),
+            (2, WikiText::new("a\n\n\nb")),
+            (3, WikiText::new("x".repeat(200_001))),
+        ];
+        let limits = ParseLimits {
+            max_duration: Some(Duration::from_secs(60)),
+            max_length: Some(200_000),
+            slow_threshold: None,
+        };
+        let configuration = Arc::new(Configuration::default());
+        let results = parse_many(configuration.clone(), stream::iter(pages), 2, true, limits)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            results
+                .iter()
+                .map(|(id, result, stats)| (*id, result.is_ok(), stats.warnings))
+                .collect::<Vec<_>>(),
+            [(1, true, 0), (2, true, 1), (3, false, 0)]
+        );
+        assert_eq!(results[2].1.as_ref().unwrap_err(), &ParseAborted::TooLong { length: 200_001 });
+        assert!(!results[2].2.slow);
+        let limits = ParseLimits {
+            max_duration: Some(Duration::ZERO),
+            ..limits
+        };
+        let results = parse_many(configuration, stream::iter([(4, WikiText::new("''a''"))]), 2, false, limits)
+            .collect::<Vec<_>>()
+            .await;
+        assert!(matches!(results[0].1, Err(ParseAborted::TimedOut { .. })));
+        assert!(results[0].2.slow);
+    }
+}
@@ -3,23 +3,135 @@
 // the file LICENSE at the top-level directory of this distribution.
 
 use crate::state::State;
-use crate::{Configuration, Node};
+use crate::{Configuration, Node, Warning, WarningContext, WarningMessage};
 
 pub async fn parse_character_entity(state: &mut State, configuration: &Configuration) {
-    if let Ok((match_length, character)) = configuration
-        .character_entities
-        .find(&state.wiki_text.as_ref()[state.scan_position + 1..])
-    {
-        let start_position = state.scan_position;
-        state.flush(start_position).await;
-        state.flushed_position = match_length + start_position + 1;
-        state.scan_position = state.flushed_position;
-        state.nodes.push(Node::CharacterEntity {
-            character,
-            end: state.scan_position,
-            start: start_position,
-        });
-    } else {
-        state.scan_position += 1;
+    let start_position = state.scan_position;
+    let text = &state.wiki_text.as_ref()[start_position + 1..];
+    let (match_length, character, second_character) = match configuration.character_entities.find(text) {
+        Ok((match_length, (character, second_character))) => (match_length, character, second_character),
+        Err(_) => match parse_numeric_character_reference(text) {
+            Some((match_length, Some(character))) => (match_length, character, None),
+            result => {
+                let warning = match result {
+                    Some((match_length, None)) => Some((match_length, WarningMessage::InvalidCharacterReference, None)),
+                    _ => entity_name_length(text).map(|match_length| {
+                        let context = WarningContext::CharacterEntity {
+                            name: text[..match_length - 1].to_owned(),
+                        };
+                        (match_length, WarningMessage::UnrecognizedCharacterEntity, Some(context))
+                    }),
+                };
+                if let Some((match_length, message, context)) = warning {
+                    state.warnings.push(Warning {
+                        context,
+                        end: start_position + 1 + match_length,
+                        fix: None,
+                        message,
+                        related: vec![],
+                        start: start_position,
+                    });
+                }
+                state.scan_position += 1;
+                return;
+            }
+        },
+    };
+    state.flush(start_position).await;
+    state.flushed_position = match_length + start_position + 1;
+    state.scan_position = state.flushed_position;
+    state.push_node(Node::CharacterEntity {
+        character,
+        end: state.scan_position,
+        second_character,
+        start: start_position,
+    });
+}
+
+/// Parses a decimal or hexadecimal numeric character reference following `&`, returning the length of the reference and the character it refers to, or `None` for the character if the code point is not allowed in the same way as in Mediawiki.
+pub fn parse_numeric_character_reference(text: &str) -> Option<(usize, Option<char>)> {
+    let text = text.strip_prefix('#')?;
+    let (digits, radix, prefix_length) = match text.strip_prefix(['x', 'X']) {
+        None => (text, 10, 1),
+        Some(digits) => (digits, 16, 2),
+    };
+    let digits_length = digits
+        .find(|character: char| !character.is_digit(radix))
+        .filter(|&length| length > 0 && digits.as_bytes()[length] == b';')?;
+    let character = u32::from_str_radix(&digits[..digits_length], radix)
+        .ok()
+        .filter(|&code_point| {
+            matches!(code_point, 0x9 | 0xa | 0xd | 0x20..=0xd7ff | 0xe000..=0xfffd | 0x10000..=0x10ffff)
+        })
+        .and_then(char::from_u32);
+    Some((prefix_length + digits_length + 1, character))
+}
+
+fn entity_name_length(text: &str) -> Option<usize> {
+    text.find(|character: char| !character.is_ascii_alphanumeric())
+        .filter(|&length| length > 0 && text.as_bytes()[length] == b';')
+        .map(|length| length + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, ConfigurationBuilder, Node, WarningMessage, WikiText};
+
+    #[tokio::test]
+    async fn it_works() {
+        let output = Configuration::default()
+            .parse(WikiText::new("&#8212;&#x2014;&eacute;&#0;&bogus;"))
+            .await;
+        assert!(matches!(
+            output.nodes.as_slice(),
+            [
+                Node::CharacterEntity { character: '\u{2014}', end: 7, .. },
+                Node::CharacterEntity { character: '\u{2014}', end: 15, .. },
+                Node::CharacterEntity { character: 'é', second_character: None, .. },
+                Node::Text { start: 23, .. },
+            ]
+        ));
+        assert_eq!(
+            output
+                .warnings
+                .iter()
+                .map(|warning| (warning.start, warning.end, warning.message))
+                .collect::<Vec<_>>(),
+            [
+                (23, 27, WarningMessage::InvalidCharacterReference),
+                (27, 34, WarningMessage::UnrecognizedCharacterEntity),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn html5_entities() {
+        let wiki_text = "&fjlig;&Lt;&eacute;";
+        let output = Configuration::default().parse(WikiText::new(wiki_text)).await;
+        assert!(matches!(
+            output.nodes.as_slice(),
+            [Node::Text { end: 11, .. }, Node::CharacterEntity { character: 'é', .. }]
+        ));
+        let output = ConfigurationBuilder::new()
+            .html5_entities(true)
+            .build()
+            .unwrap()
+            .parse(WikiText::new(wiki_text))
+            .await;
+        assert!(matches!(
+            output.nodes.as_slice(),
+            [
+                Node::CharacterEntity { character: 'f', second_character: Some('j'), .. },
+                Node::CharacterEntity { character: '\u{226a}', second_character: None, .. },
+                Node::CharacterEntity { character: 'é', .. },
+            ]
+        ));
+    }
+
+    #[tokio::test]
+    async fn non_ascii_name() {
+        let output = Configuration::default().parse(WikiText::new("&café; &naïve;")).await;
+        assert!(output.warnings.is_empty());
+        assert!(matches!(output.nodes.as_slice(), [Node::Text { end: 16, .. }]));
     }
 }
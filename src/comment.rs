@@ -32,7 +32,7 @@ pub async fn parse_comment(state: &mut State) {
     }
     state.flushed_position = position;
     state.scan_position = position;
-    state.nodes.push(Node::Comment {
+    state.push_node(Node::Comment {
         end: state.scan_position,
         start: start_position,
     });
@@ -59,7 +59,7 @@ async fn parse_end_tag(
             _ => tag_name_end_position += 1,
         }
     } + 1;
-    let tag_name = Text::new(&state.wiki_text.as_ref()[tag_name_start_position..tag_name_end_position].to_ascii_lowercase());
+    let tag_name = Text::new(state.wiki_text.as_ref()[tag_name_start_position..tag_name_end_position].to_ascii_lowercase());
     let mut matched_node_index = None;
     for (open_node_index, open_node) in state.stack.iter().enumerate().rev() {
         if let OpenNodeType::Tag { name, .. } = &open_node.type_ {
@@ -73,21 +73,27 @@ async fn parse_end_tag(
         None => false,
         Some(open_node_index) => {
             if open_node_index < state.stack.len() - 1 {
+                state.stack.truncate(open_node_index + 2);
+                let open_node = state.stack.pop().unwrap();
                 state.warnings.push(Warning {
+                    context: state.open_node_context(&open_node),
                     end: tag_end_position,
+                    fix: None,
                     message: WarningMessage::MissingEndTagRewinding,
+                    related: vec![state.open_node_start_span(&open_node)],
                     start: tag_start_position,
                 });
-                state.stack.truncate(open_node_index + 2);
-                let open_node = state.stack.pop().unwrap();
-                state.rewind(open_node.nodes, open_node.start);
+                state.rewind(open_node);
             } else {
                 state.warnings.push(Warning {
+                    context: None,
                     end: tag_end_position,
+                    fix: None,
                     message: WarningMessage::EndTagInComment,
+                    related: vec![],
                     start: tag_start_position,
                 });
-                state.nodes.push(Node::Comment {
+                state.push_node(Node::Comment {
                     end: tag_start_position,
                     start: comment_start_position,
                 });
@@ -95,7 +101,7 @@ async fn parse_end_tag(
                 state.flushed_position = tag_end_position;
                 state.scan_position = state.flushed_position;
                 let nodes = std::mem::replace(&mut state.nodes, open_node.nodes);
-                state.nodes.push(Node::Tag {
+                state.push_node(Node::Tag {
                     end: state.scan_position,
                     name: tag_name,
                     nodes,
@@ -3,86 +3,229 @@
 // the file LICENSE at the top-level directory of this distribution.
 
 use std::collections::{HashMap, HashSet};
-use crate::{Configuration, Output, parse, default, Trie, TagClass, html_entities, WikiText, Text};
+use std::fmt;
+use crate::{Configuration, Output, parse, default, TrieBuilder, TagClass, InterwikiClass, LinkAffix, html5_entities, html_entities, SiteConfig, SiteNamespace, WikiText, Text};
 
 /// Site specific configuration of a wiki.
 ///
 /// This is generated using the program [`fetch_site_configuration`](https://github.com/portstrom/fetch_site_configuration).
 pub struct ConfigurationSource<'a> {
-    /// Aliases of the category namespace.
+    /// Aliases of the category namespace, added to the aliases of the namespace with identifier 14 in `namespaces`. Kept from before `namespaces` was added; new configurations should give the aliases in `namespaces`.
     pub category_namespaces: &'a [&'a str],
 
     /// Tag names of extension tags.
     pub extension_tags: &'a [&'a str],
 
-    /// Aliases of the file namespace.
+    /// Aliases of the file namespace, added to the aliases of the namespace with identifier 6 in `namespaces`. Kept from before `namespaces` was added; new configurations should give the aliases in `namespaces`.
     pub file_namespaces: &'a [&'a str],
 
-    /// Characters that can appear in link trails.
+    /// Whether all named character entities of HTML5 are recognized, rather than only those of HTML 4.
+    pub html5_entities: bool,
+
+    /// Prefixes of links to other wikis, not including language prefixes.
+    pub interwiki_prefixes: &'a [&'a str],
+
+    /// Prefixes of links to the same page in wikis in other languages.
+    pub language_prefixes: &'a [&'a str],
+
+    /// Characters that can appear in link prefixes, in the format of Mediawiki's `linkprefixcharset`, such as `a-zA-Z\x{80}-\x{10ffff}`, or empty if the wiki has no link prefixes.
+    pub link_prefix: &'a str,
+
+    /// Rule for link trails, either a regular expression in the format of Mediawiki's `linktrail`, such as `/^([a-z]+)(.*)$/sD`, or the characters that can appear in link trails.
     pub link_trail: &'a str,
 
     /// Magic words that can appear between `__` and `__`.
     pub magic_words: &'a [&'a str],
 
+    /// Namespaces of the wiki.
+    pub namespaces: &'a [NamespaceSource<'a>],
+
+    /// Names of parser functions that are called without `#`, such as `lc` or `DEFAULTSORT`, matched case insensitively. A template name that has one of these names before `:` is a call of the parser function.
+    pub parser_functions: &'a [&'a str],
+
     /// Protocols that can be used for external links.
     pub protocols: &'a [&'a str],
 
     /// Magic words that can be used for redirects.
     pub redirect_magic_words: &'a [&'a str],
+
+    /// Names of variables, such as `PAGENAME`, matched case sensitively. A template name that is one of these names is the variable.
+    pub variables: &'a [&'a str],
+}
+
+/// Error from building a configuration from a site specific configuration.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigurationError {
+    /// The characters that can appear in link prefixes are not in the format of Mediawiki's `linkprefixcharset`, or use a part of the format that is not supported.
+    InvalidLinkPrefix,
+
+    /// The rule for link trails is not in the format of Mediawiki's `linktrail`, or uses a part of the format that is not supported.
+    InvalidLinkTrail,
+}
+
+/// Site specific configuration of a namespace.
+pub struct NamespaceSource<'a> {
+    /// Additional names of the namespace, besides its name and canonical name.
+    pub aliases: &'a [&'a str],
+
+    /// The name of the namespace that is the same in all wikis.
+    pub canonical_name: &'a str,
+
+    /// Whether the first letter of page names in the namespace is case sensitive.
+    pub case_sensitive: bool,
+
+    /// The numeric identifier of the namespace.
+    pub id: i32,
+
+    /// The name of the namespace in the language of the wiki.
+    pub name: &'a str,
+}
+
+/// Namespace of a page, identified by its numeric identifier.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Namespace {
+    /// The numeric identifier of the namespace.
+    pub id: i32,
+}
+
+impl Namespace {
+    /// The namespace for linking directly to files. Parsed as links, not images.
+    pub const MEDIA: Namespace = Namespace { id: -2 };
+
+    /// The namespace of special pages.
+    pub const SPECIAL: Namespace = Namespace { id: -1 };
+
+    /// The main namespace.
+    pub const MAIN: Namespace = Namespace { id: 0 };
+
+    /// The namespace of talk pages of the main namespace.
+    pub const TALK: Namespace = Namespace { id: 1 };
+
+    /// The namespace of user pages.
+    pub const USER: Namespace = Namespace { id: 2 };
+
+    /// The namespace of user talk pages.
+    pub const USER_TALK: Namespace = Namespace { id: 3 };
+
+    /// The namespace of pages about the wiki itself.
+    pub const PROJECT: Namespace = Namespace { id: 4 };
+
+    /// The namespace of talk pages about the wiki itself.
+    pub const PROJECT_TALK: Namespace = Namespace { id: 5 };
+
+    /// The namespace of file description pages. Links to it are parsed as images.
+    pub const FILE: Namespace = Namespace { id: 6 };
+
+    /// The namespace of talk pages of files.
+    pub const FILE_TALK: Namespace = Namespace { id: 7 };
+
+    /// The namespace of interface messages.
+    pub const MEDIAWIKI: Namespace = Namespace { id: 8 };
+
+    /// The namespace of talk pages of interface messages.
+    pub const MEDIAWIKI_TALK: Namespace = Namespace { id: 9 };
+
+    /// The namespace of templates.
+    pub const TEMPLATE: Namespace = Namespace { id: 10 };
+
+    /// The namespace of talk pages of templates.
+    pub const TEMPLATE_TALK: Namespace = Namespace { id: 11 };
+
+    /// The namespace of help pages.
+    pub const HELP: Namespace = Namespace { id: 12 };
+
+    /// The namespace of talk pages of help pages.
+    pub const HELP_TALK: Namespace = Namespace { id: 13 };
+
+    /// The namespace of categories. Links to it are parsed as categories.
+    pub const CATEGORY: Namespace = Namespace { id: 14 };
+
+    /// The namespace of talk pages of categories.
+    pub const CATEGORY_TALK: Namespace = Namespace { id: 15 };
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub enum Namespace {
-    Category,
-    File,
+impl fmt::Display for ConfigurationError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            ConfigurationError::InvalidLinkPrefix => "The link prefix characters are invalid.",
+            ConfigurationError::InvalidLinkTrail => "The link trail rule is invalid.",
+        })
+    }
 }
 
+impl std::error::Error for ConfigurationError {}
+
 impl Configuration {
     /// Allocates and returns a new configuration based on the given site specific configuration.
+    ///
+    /// Panics if the link prefix or link trail is invalid. Use `from_site_config` to get an error instead.
     #[must_use]
     pub fn new(source: &ConfigurationSource) -> Self {
-        let mut configuration = Configuration {
-            character_entities: Trie::new(),
-            link_trail_character_set: HashSet::new(),
-            magic_words: Trie::new(),
-            namespaces: Trie::new(),
-            protocols: Trie::new(),
-            redirect_magic_words: Trie::new(),
-            tag_name_map: HashMap::new(),
-        };
-        for (name, character) in html_entities::HTML_ENTITIES {
-            configuration
-                .character_entities
-                .add_case_sensitive_term(&format!("{};", name), *character);
+        match Configuration::from_site_config(SiteConfig::from(source)) {
+            Err(error) => panic!("{}", error),
+            Ok(configuration) => configuration,
+        }
+    }
+
+    /// Allocates and returns a new configuration based on the given site specific configuration, taking ownership of it.
+    ///
+    /// Returns an error if the link prefix or link trail is invalid.
+    pub fn from_site_config(source: SiteConfig) -> Result<Self, ConfigurationError> {
+        let link_prefix = LinkAffix::prefix(&source.link_prefix).ok_or(ConfigurationError::InvalidLinkPrefix)?;
+        let link_trail = LinkAffix::trail(&source.link_trail).ok_or(ConfigurationError::InvalidLinkTrail)?;
+        let mut character_entities = TrieBuilder::new();
+        let mut interwiki_prefixes = TrieBuilder::new();
+        let mut magic_words = TrieBuilder::new();
+        let mut namespaces = TrieBuilder::new();
+        let mut protocols = TrieBuilder::new();
+        let mut redirect_magic_words = TrieBuilder::new();
+        let mut tag_name_map = HashMap::new();
+        if source.html5_entities {
+            for (name, characters) in html5_entities::HTML5_ENTITIES {
+                let mut characters = characters.chars();
+                character_entities.add_case_sensitive_term(
+                    &format!("{};", name),
+                    (characters.next().unwrap(), characters.next()),
+                );
+            }
+        } else {
+            for (name, character) in html_entities::HTML_ENTITIES {
+                character_entities.add_case_sensitive_term(&format!("{};", name), (*character, None));
+            }
         }
-        for character in source.link_trail.chars() {
-            configuration.link_trail_character_set.insert(character);
+        for protocol in &source.protocols {
+            protocols.add_term(protocol, ());
         }
-        for protocol in source.protocols {
-            configuration.protocols.add_term(protocol, ());
+        for magic_word in &source.magic_words {
+            magic_words.add_term(magic_word, ());
         }
-        for magic_word in source.magic_words {
-            configuration.magic_words.add_term(magic_word, ());
+        for namespace in &source.namespaces {
+            for name in [&namespace.name, &namespace.canonical_name]
+                .into_iter()
+                .chain(&namespace.aliases)
+            {
+                if !name.is_empty() {
+                    namespaces.add_term(&format!("{}:", name), Namespace { id: namespace.id });
+                    if name.contains(' ') {
+                        namespaces.add_term(
+                            &format!("{}:", name.replace(' ', "_")),
+                            Namespace { id: namespace.id },
+                        );
+                    }
+                }
+            }
         }
-        for namespace in source.category_namespaces {
-            configuration
-                .namespaces
-                .add_term(&format!("{}:", namespace), Namespace::Category);
+        for prefix in &source.language_prefixes {
+            interwiki_prefixes.add_term(&format!("{}:", prefix), InterwikiClass::Language);
         }
-        for namespace in source.file_namespaces {
-            configuration
-                .namespaces
-                .add_term(&format!("{}:", namespace), Namespace::File);
+        for prefix in &source.interwiki_prefixes {
+            interwiki_prefixes.add_term(&format!("{}:", prefix), InterwikiClass::Interwiki);
         }
-        for redirect_magic_word in source.redirect_magic_words {
-            configuration
-                .redirect_magic_words
-                .add_term(redirect_magic_word, ());
+        for redirect_magic_word in &source.redirect_magic_words {
+            redirect_magic_words.add_term(redirect_magic_word, ());
         }
-        for tag_name in source.extension_tags {
-            configuration
-                .tag_name_map
-                .insert(Text::new(tag_name), TagClass::ExtensionTag);
+        for tag_name in &source.extension_tags {
+            tag_name_map.insert(Text::new(tag_name), TagClass::ExtensionTag);
         }
         for tag_name in [
             "abbr",
@@ -144,16 +287,58 @@ impl Configuration {
             "wbr",
         ].iter()
         {
-            configuration
-                .tag_name_map
-                .insert(Text::new(tag_name), TagClass::Tag);
+            tag_name_map.insert(Text::new(tag_name), TagClass::Tag);
         }
-        configuration
+        Ok(Configuration {
+            character_entities: character_entities.build(),
+            interwiki_prefixes: interwiki_prefixes.build(),
+            link_prefix,
+            link_trail,
+            magic_words: magic_words.build(),
+            namespace_definitions: namespace_definitions(&source),
+            namespaces: namespaces.build(),
+            parser_functions: parser_functions(&source),
+            protocols: protocols.build(),
+            redirect_magic_words: redirect_magic_words.build(),
+            tag_name_map,
+            variables: variables(&source),
+            site_config: source,
+        })
+    }
+
+    /// Returns the canonical name of the given namespace, if the namespace is configured.
+    #[must_use]
+    pub fn canonical_namespace_name(&self, namespace: Namespace) -> Option<&str> {
+        self.namespace_definitions
+            .get(&namespace)
+            .map(|definition| &definition.canonical_name as _)
+    }
+
+    /// Returns the site specific configuration the configuration was created from.
+    #[must_use]
+    pub fn to_source(&self) -> SiteConfig {
+        self.site_config.clone()
+    }
+
+    /// Returns whether the first letter of page names in the given namespace is case sensitive.
+    #[must_use]
+    pub fn is_case_sensitive(&self, namespace: Namespace) -> bool {
+        self.namespace_definitions
+            .get(&namespace)
+            .is_some_and(|definition| definition.case_sensitive)
+    }
+
+    /// Returns the name of the given namespace in the language of the wiki, if the namespace is configured.
+    #[must_use]
+    pub fn namespace_name(&self, namespace: Namespace) -> Option<&str> {
+        self.namespace_definitions
+            .get(&namespace)
+            .map(|definition| &definition.name as _)
     }
 
     /// Parses wiki text into structured data.
     #[must_use]
-    pub async fn parse<'a>(&self, wiki_text: WikiText) -> Output {
+    pub async fn parse(&self, wiki_text: WikiText) -> Output {
         parse::parse(self, wiki_text).await
     }
 }
@@ -164,3 +349,19 @@ impl Default for Configuration {
         default::create_configuration()
     }
 }
+
+pub fn namespace_definitions(source: &SiteConfig) -> HashMap<Namespace, SiteNamespace> {
+    source
+        .namespaces
+        .iter()
+        .map(|namespace| (Namespace { id: namespace.id }, namespace.clone()))
+        .collect()
+}
+
+pub fn parser_functions(source: &SiteConfig) -> HashSet<String> {
+    source.parser_functions.iter().map(|name| name.to_lowercase()).collect()
+}
+
+pub fn variables(source: &SiteConfig) -> HashSet<String> {
+    source.variables.iter().cloned().collect()
+}
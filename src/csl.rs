@@ -0,0 +1,381 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use crate::references::{citation_template, for_each_child};
+use crate::{CitationTemplate, Configuration, Node, Output, WikiText};
+
+const MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// Mapping from the parameters of citation templates to the variables of CSL-JSON records.
+///
+/// The default mapping covers the Citation Style 1 templates of English Wikipedia. A mapping for the citation templates of another wiki can be built or loaded from a file.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct CitationMapping {
+    /// Template parameters holding dates, and the CSL date variables they map to.
+    pub dates: Vec<(String, String)>,
+
+    /// Template parameters holding lists of names, and how they map to CSL name variables.
+    pub names: Vec<NameMapping>,
+
+    /// Kinds of citation templates, as given by `TemplateName::kind`, and the CSL types they map to.
+    pub templates: Vec<(String, String)>,
+
+    /// Template parameters holding text, and the CSL variables they map to. When several parameters map to the same variable, the first one present is used.
+    pub variables: Vec<(String, String)>,
+}
+
+/// Mapping from the numbered parameters of a citation template to a CSL name variable.
+///
+/// A numbered parameter is the base name followed by a number starting from 1, such as `last1`. The base name alone stands for the number 1.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct NameMapping {
+    /// Base names of the parameters holding family names.
+    pub family: Vec<String>,
+
+    /// Base names of the parameters holding given names.
+    pub given: Vec<String>,
+
+    /// Base names of the parameters holding full names.
+    pub literal: Vec<String>,
+
+    /// The CSL name variable, such as `author`.
+    pub variable: String,
+}
+
+impl CitationMapping {
+    /// Returns the CSL-JSON record for the given template, or `None` if the template is not a citation template in the mapping.
+    #[must_use]
+    pub fn record(&self, template: &CitationTemplate) -> Option<Value> {
+        let type_ = &self
+            .templates
+            .iter()
+            .find(|(name, _)| *name == template.name)?
+            .1;
+        let parameter = |name: &str| {
+            template
+                .parameters
+                .iter()
+                .rev()
+                .find(|(parameter_name, value)| parameter_name == name && !value.is_empty())
+                .map(|(_, value)| plain_text(value))
+        };
+        let mut record = Map::new();
+        record.insert("id".to_owned(), format!("cite-{}", template.start).into());
+        record.insert("type".to_owned(), type_.as_str().into());
+        for (parameter_name, variable) in &self.variables {
+            if !record.contains_key(variable) {
+                if let Some(value) = parameter(parameter_name) {
+                    record.insert(variable.clone(), value.into());
+                }
+            }
+        }
+        for (parameter_name, variable) in &self.dates {
+            if !record.contains_key(variable) {
+                if let Some(value) = parameter(parameter_name) {
+                    record.insert(variable.clone(), date(&value));
+                }
+            }
+        }
+        for mapping in &self.names {
+            let mut names = vec![];
+            for number in 1.. {
+                let numbered = |base_names: &[String]| {
+                    base_names.iter().find_map(|base_name| {
+                        parameter(&format!("{}{}", base_name, number))
+                            .or_else(|| if number == 1 { parameter(base_name) } else { None })
+                    })
+                };
+                let name = match (numbered(&mapping.family), numbered(&mapping.given)) {
+                    (Some(family), Some(given)) => json!({ "family": family, "given": given }),
+                    (Some(family), None) => json!({ "family": family }),
+                    (None, _) => match numbered(&mapping.literal) {
+                        None => break,
+                        Some(literal) => json!({ "literal": literal }),
+                    },
+                };
+                names.push(name);
+            }
+            if !names.is_empty() {
+                record.insert(mapping.variable.clone(), names.into());
+            }
+        }
+        Some(record.into())
+    }
+}
+
+impl Default for CitationMapping {
+    /// Returns the mapping for the Citation Style 1 templates of English Wikipedia.
+    fn default() -> Self {
+        let pairs = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(name, variable)| ((*name).to_owned(), (*variable).to_owned()))
+                .collect()
+        };
+        let strings = |strings: &[&str]| strings.iter().map(|string| (*string).to_owned()).collect();
+        CitationMapping {
+            dates: pairs(&[
+                ("date", "issued"),
+                ("year", "issued"),
+                ("access-date", "accessed"),
+                ("accessdate", "accessed"),
+                ("orig-date", "original-date"),
+            ]),
+            names: vec![
+                NameMapping {
+                    family: strings(&["last", "surname", "author-last"]),
+                    given: strings(&["first", "given", "author-first"]),
+                    literal: strings(&["author"]),
+                    variable: "author".to_owned(),
+                },
+                NameMapping {
+                    family: strings(&["editor-last", "editor-surname"]),
+                    given: strings(&["editor-first", "editor-given"]),
+                    literal: strings(&["editor"]),
+                    variable: "editor".to_owned(),
+                },
+            ],
+            templates: pairs(&[
+                ("citation", "document"),
+                ("cite book", "book"),
+                ("cite conference", "paper-conference"),
+                ("cite encyclopedia", "entry-encyclopedia"),
+                ("cite journal", "article-journal"),
+                ("cite magazine", "article-magazine"),
+                ("cite news", "article-newspaper"),
+                ("cite newspaper", "article-newspaper"),
+                ("cite paper", "article-journal"),
+                ("cite periodical", "article-magazine"),
+                ("cite report", "report"),
+                ("cite thesis", "thesis"),
+                ("cite url", "webpage"),
+                ("cite web", "webpage"),
+                ("cite website", "webpage"),
+            ]),
+            variables: pairs(&[
+                ("title", "title"),
+                ("script-title", "title"),
+                ("url", "URL"),
+                ("doi", "DOI"),
+                ("isbn", "ISBN"),
+                ("issn", "ISSN"),
+                ("pmid", "PMID"),
+                ("pmc", "PMCID"),
+                ("journal", "container-title"),
+                ("newspaper", "container-title"),
+                ("magazine", "container-title"),
+                ("encyclopedia", "container-title"),
+                ("website", "container-title"),
+                ("work", "container-title"),
+                ("periodical", "container-title"),
+                ("publisher", "publisher"),
+                ("location", "publisher-place"),
+                ("place", "publisher-place"),
+                ("volume", "volume"),
+                ("issue", "issue"),
+                ("number", "issue"),
+                ("pages", "page"),
+                ("page", "page"),
+                ("edition", "edition"),
+                ("series", "collection-title"),
+                ("language", "language"),
+            ]),
+        }
+    }
+}
+
+impl Output {
+    /// Returns a CSL-JSON record for every citation template in the wiki text, in order.
+    ///
+    /// Use the wiki text and configuration this output was parsed from, since parameter values are read from the wiki text by their positions.
+    #[must_use]
+    pub fn csl_records(&self, configuration: &Configuration, wiki_text: &WikiText, mapping: &CitationMapping) -> Vec<Value> {
+        let mut records = vec![];
+        collect_records(&self.nodes, configuration, wiki_text, mapping, &mut records);
+        records
+    }
+}
+
+fn collect_records(
+    nodes: &[Node],
+    configuration: &Configuration,
+    wiki_text: &WikiText,
+    mapping: &CitationMapping,
+    records: &mut Vec<Value>,
+) {
+    for node in nodes {
+        if let Some(record) =
+            citation_template(configuration, wiki_text, node).and_then(|template| mapping.record(&template))
+        {
+            records.push(record);
+        }
+        for_each_child(node, |nodes| collect_records(nodes, configuration, wiki_text, mapping, records));
+    }
+}
+
+fn date(text: &str) -> Value {
+    let mut year = None;
+    let mut month = None;
+    let mut day = None;
+    for part in text.split([' ', ',', '-', '/']) {
+        if part.is_empty() {
+            continue;
+        }
+        let parsed = match part.parse::<u32>() {
+            Ok(number) if part.len() == 4 && year.is_none() => year.replace(number).is_none(),
+            Ok(number) if year.is_some() && month.is_some() && day.is_none() && (1..=31).contains(&number) => {
+                day.replace(number).is_none()
+            }
+            Ok(number) if year.is_some() && month.is_none() && (1..=12).contains(&number) => {
+                month.replace(number).is_none()
+            }
+            Ok(number) if year.is_none() && day.is_none() && (1..=31).contains(&number) => {
+                day.replace(number).is_none()
+            }
+            Ok(_) => false,
+            Err(_) => {
+                let part = part.trim_end_matches('.').to_lowercase();
+                match MONTHS.iter().position(|name| part.len() >= 3 && name.starts_with(&part)) {
+                    Some(index) if month.is_none() => month.replace(index as u32 + 1).is_none(),
+                    _ => false,
+                }
+            }
+        };
+        if !parsed {
+            return json!({ "raw": text });
+        }
+    }
+    match (year, month, day) {
+        (Some(year), Some(month), Some(day)) => json!({ "date-parts": [[year, month, day]] }),
+        (Some(year), Some(month), None) => json!({ "date-parts": [[year, month]] }),
+        (Some(year), None, None) => json!({ "date-parts": [[year]] }),
+        _ => json!({ "raw": text }),
+    }
+}
+
+fn plain_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut remainder = text;
+    while let Some(position) = remainder.find("[[") {
+        result += &remainder[..position];
+        remainder = &remainder[position + 2..];
+        match remainder.find("]]") {
+            None => result += "[[",
+            Some(end) => {
+                let link = &remainder[..end];
+                result += link.rsplit('|').next().unwrap();
+                remainder = &remainder[end + 2..];
+            }
+        }
+    }
+    result += remainder;
+    result.replace("'''", "").replace("''", "")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use super::{date, plain_text};
+    use crate::{CitationMapping, Configuration, WikiText};
+
+    async fn records(text: &str) -> Vec<Value> {
+        let wiki_text = WikiText::new(text);
+        let output = Configuration::default().parse(wiki_text.clone()).await;
+        output.csl_records(&Configuration::default(), &wiki_text, &CitationMapping::default())
+    }
+
+    #[tokio::test]
+    async fn journal_article() {
+        let records = records(concat!(
+            "Text.<ref>{{cite journal |last1=Curie |first1=Marie |last2=Curie |first2=Pierre",
+            " |title=On a new ''radioactive'' substance |journal=[[Comptes rendus]] |date=18 July 1898",
+            " |doi=10.1000/182 |pages=175–178}}</ref>",
+        ))
+        .await;
+        assert_eq!(
+            records,
+            [json!({
+                "id": "cite-10",
+                "type": "article-journal",
+                "title": "On a new radioactive substance",
+                "DOI": "10.1000/182",
+                "container-title": "Comptes rendus",
+                "page": "175–178",
+                "issued": { "date-parts": [[1898, 7, 18]] },
+                "author": [
+                    { "family": "Curie", "given": "Marie" },
+                    { "family": "Curie", "given": "Pierre" },
+                ],
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn web_page_with_literal_author() {
+        let records =
+            records("{{Cite_web|author=Example Org|url=https://example.com|access-date=2023-05-01|website=Example}}").await;
+        assert_eq!(
+            records,
+            [json!({
+                "id": "cite-0",
+                "type": "webpage",
+                "URL": "https://example.com",
+                "container-title": "Example",
+                "accessed": { "date-parts": [[2023, 5, 1]] },
+                "author": [{ "literal": "Example Org" }],
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_template_ignored() {
+        assert!(records("{{cite unknown|title=Ignored}} {{foo|title=Ignored}}").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn first_present_parameter_wins() {
+        let records = records("{{cite book|page=5|pages=}} {{cite book|page=5|pages=1–3}}").await;
+        assert_eq!(
+            records,
+            [
+                json!({ "id": "cite-0", "type": "book", "page": "5" }),
+                json!({ "id": "cite-28", "type": "book", "page": "1–3" }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn dates() {
+        assert_eq!(date("18 July 1898"), json!({ "date-parts": [[1898, 7, 18]] }));
+        assert_eq!(date("July 18, 1898"), json!({ "date-parts": [[1898, 7, 18]] }));
+        assert_eq!(date("2023-05-01"), json!({ "date-parts": [[2023, 5, 1]] }));
+        assert_eq!(date("Sept. 2020"), json!({ "date-parts": [[2020, 9]] }));
+        assert_eq!(date("2020"), json!({ "date-parts": [[2020]] }));
+        assert_eq!(date("c. 1900"), json!({ "raw": "c. 1900" }));
+        assert_eq!(date("18 July"), json!({ "raw": "18 July" }));
+    }
+
+    #[tokio::test]
+    async fn plain_text_strips_links_and_formatting() {
+        assert_eq!(plain_text("[[Foo|bar]] and [[Baz]] ''x'' '''y'''"), "bar and Baz x y");
+        assert_eq!(plain_text("[[Foo"), "[[Foo");
+    }
+}
@@ -2,11 +2,11 @@
 // This is free software distributed under the terms specified in
 // the file LICENSE at the top-level directory of this distribution.
 
-use crate::{Configuration, ConfigurationSource};
+use crate::{Configuration, ConfigurationSource, NamespaceSource};
 
 pub fn create_configuration() -> Configuration {
     Configuration::new(&ConfigurationSource {
-        category_namespaces: &["category"],
+        category_namespaces: &[],
         extension_tags: &[
             "categorytree",
             "ce",
@@ -33,7 +33,32 @@ pub fn create_configuration() -> Configuration {
             "templatedata",
             "timeline",
         ],
-        file_namespaces: &["file", "image"],
+        file_namespaces: &[],
+        html5_entities: false,
+        interwiki_prefixes: &[
+            "commons",
+            "mediawikiwiki",
+            "meta",
+            "mw",
+            "w",
+            "wikibooks",
+            "wikidata",
+            "wikinews",
+            "wikipedia",
+            "wikiquote",
+            "wikisource",
+            "wikispecies",
+            "wikiversity",
+            "wikivoyage",
+            "wikt",
+            "wiktionary",
+        ],
+        language_prefixes: &[
+            "ar", "bg", "ca", "cs", "da", "de", "el", "en", "eo", "es", "et", "eu", "fa", "fi", "fr",
+            "he", "hi", "hr", "hu", "id", "it", "ja", "ko", "lt", "ms", "nl", "no", "pl", "pt", "ro",
+            "ru", "sk", "sl", "sr", "sv", "th", "tr", "uk", "vi", "zh",
+        ],
+        link_prefix: "",
         link_trail: "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
         magic_words: &[
             "DISAMBIG",
@@ -55,6 +80,247 @@ pub fn create_configuration() -> Configuration {
             "STATICREDIRECT",
             "TOC",
         ],
+        namespaces: &[
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Media",
+                case_sensitive: false,
+                id: -2,
+                name: "Media",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Special",
+                case_sensitive: false,
+                id: -1,
+                name: "Special",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "",
+                case_sensitive: false,
+                id: 0,
+                name: "",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Talk",
+                case_sensitive: false,
+                id: 1,
+                name: "Talk",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "User",
+                case_sensitive: false,
+                id: 2,
+                name: "User",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "User talk",
+                case_sensitive: false,
+                id: 3,
+                name: "User talk",
+            },
+            NamespaceSource {
+                aliases: &["WP"],
+                canonical_name: "Project",
+                case_sensitive: false,
+                id: 4,
+                name: "Wikipedia",
+            },
+            NamespaceSource {
+                aliases: &["WT"],
+                canonical_name: "Project talk",
+                case_sensitive: false,
+                id: 5,
+                name: "Wikipedia talk",
+            },
+            NamespaceSource {
+                aliases: &["Image"],
+                canonical_name: "File",
+                case_sensitive: false,
+                id: 6,
+                name: "File",
+            },
+            NamespaceSource {
+                aliases: &["Image talk"],
+                canonical_name: "File talk",
+                case_sensitive: false,
+                id: 7,
+                name: "File talk",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "MediaWiki",
+                case_sensitive: false,
+                id: 8,
+                name: "MediaWiki",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "MediaWiki talk",
+                case_sensitive: false,
+                id: 9,
+                name: "MediaWiki talk",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Template",
+                case_sensitive: false,
+                id: 10,
+                name: "Template",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Template talk",
+                case_sensitive: false,
+                id: 11,
+                name: "Template talk",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Help",
+                case_sensitive: false,
+                id: 12,
+                name: "Help",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Help talk",
+                case_sensitive: false,
+                id: 13,
+                name: "Help talk",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Category",
+                case_sensitive: false,
+                id: 14,
+                name: "Category",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Category talk",
+                case_sensitive: false,
+                id: 15,
+                name: "Category talk",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Portal",
+                case_sensitive: false,
+                id: 100,
+                name: "Portal",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Portal talk",
+                case_sensitive: false,
+                id: 101,
+                name: "Portal talk",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Draft",
+                case_sensitive: false,
+                id: 118,
+                name: "Draft",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Draft talk",
+                case_sensitive: false,
+                id: 119,
+                name: "Draft talk",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Module",
+                case_sensitive: false,
+                id: 828,
+                name: "Module",
+            },
+            NamespaceSource {
+                aliases: &[],
+                canonical_name: "Module talk",
+                case_sensitive: false,
+                id: 829,
+                name: "Module talk",
+            },
+        ],
+        parser_functions: &[
+            "anchorencode",
+            "BASEPAGENAME",
+            "BASEPAGENAMEE",
+            "canonicalurl",
+            "canonicalurle",
+            "DEFAULTCATEGORYSORT",
+            "DEFAULTSORT",
+            "DEFAULTSORTKEY",
+            "DISPLAYTITLE",
+            "filepath",
+            "formatnum",
+            "FULLPAGENAME",
+            "FULLPAGENAMEE",
+            "fullurl",
+            "fullurle",
+            "gender",
+            "grammar",
+            "int",
+            "lc",
+            "lcfirst",
+            "localurl",
+            "localurle",
+            "NAMESPACE",
+            "NAMESPACEE",
+            "NAMESPACENUMBER",
+            "ns",
+            "nse",
+            "NUMBERINGROUP",
+            "NUMBEROFADMINS",
+            "NUMBEROFARTICLES",
+            "NUMBEROFEDITS",
+            "NUMBEROFFILES",
+            "NUMBEROFPAGES",
+            "NUMBEROFUSERS",
+            "padleft",
+            "padright",
+            "PAGEID",
+            "PAGENAME",
+            "PAGENAMEE",
+            "PAGESINCATEGORY",
+            "PAGESINNAMESPACE",
+            "PAGESIZE",
+            "plural",
+            "PROTECTIONEXPIRY",
+            "PROTECTIONLEVEL",
+            "REVISIONDAY",
+            "REVISIONID",
+            "REVISIONMONTH",
+            "REVISIONTIMESTAMP",
+            "REVISIONUSER",
+            "REVISIONYEAR",
+            "ROOTPAGENAME",
+            "ROOTPAGENAMEE",
+            "special",
+            "speciale",
+            "SUBJECTPAGENAME",
+            "SUBJECTPAGENAMEE",
+            "SUBJECTSPACE",
+            "SUBJECTSPACEE",
+            "SUBPAGENAME",
+            "SUBPAGENAMEE",
+            "tag",
+            "TALKPAGENAME",
+            "TALKPAGENAMEE",
+            "TALKSPACE",
+            "TALKSPACEE",
+            "uc",
+            "ucfirst",
+            "urlencode",
+        ],
         protocols: &[
             "//",
             "bitcoin:",
@@ -86,5 +352,81 @@ pub fn create_configuration() -> Configuration {
             "xmpp:",
         ],
         redirect_magic_words: &["REDIRECT"],
+        variables: &[
+            "!",
+            "=",
+            "BASEPAGENAME",
+            "BASEPAGENAMEE",
+            "CONTENTLANGUAGE",
+            "CURRENTDAY",
+            "CURRENTDAY2",
+            "CURRENTDAYNAME",
+            "CURRENTDOW",
+            "CURRENTHOUR",
+            "CURRENTMONTH",
+            "CURRENTMONTH1",
+            "CURRENTMONTHABBREV",
+            "CURRENTMONTHNAME",
+            "CURRENTTIME",
+            "CURRENTTIMESTAMP",
+            "CURRENTVERSION",
+            "CURRENTWEEK",
+            "CURRENTYEAR",
+            "DIRMARK",
+            "FULLPAGENAME",
+            "FULLPAGENAMEE",
+            "LOCALDAY",
+            "LOCALDAY2",
+            "LOCALDAYNAME",
+            "LOCALDOW",
+            "LOCALHOUR",
+            "LOCALMONTH",
+            "LOCALMONTH1",
+            "LOCALMONTHABBREV",
+            "LOCALMONTHNAME",
+            "LOCALTIME",
+            "LOCALTIMESTAMP",
+            "LOCALWEEK",
+            "LOCALYEAR",
+            "NAMESPACE",
+            "NAMESPACEE",
+            "NAMESPACENUMBER",
+            "NUMBEROFACTIVEUSERS",
+            "NUMBEROFADMINS",
+            "NUMBEROFARTICLES",
+            "NUMBEROFEDITS",
+            "NUMBEROFFILES",
+            "NUMBEROFPAGES",
+            "NUMBEROFUSERS",
+            "PAGEID",
+            "PAGENAME",
+            "PAGENAMEE",
+            "REVISIONDAY",
+            "REVISIONDAY2",
+            "REVISIONID",
+            "REVISIONMONTH",
+            "REVISIONMONTH1",
+            "REVISIONSIZE",
+            "REVISIONTIMESTAMP",
+            "REVISIONUSER",
+            "REVISIONYEAR",
+            "ROOTPAGENAME",
+            "ROOTPAGENAMEE",
+            "SCRIPTPATH",
+            "SERVER",
+            "SERVERNAME",
+            "SITENAME",
+            "STYLEPATH",
+            "SUBJECTPAGENAME",
+            "SUBJECTPAGENAMEE",
+            "SUBJECTSPACE",
+            "SUBJECTSPACEE",
+            "SUBPAGENAME",
+            "SUBPAGENAMEE",
+            "TALKPAGENAME",
+            "TALKPAGENAMEE",
+            "TALKSPACE",
+            "TALKSPACEE",
+        ],
     })
 }
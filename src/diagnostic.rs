@@ -0,0 +1,422 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use serde_json::{json, Value};
+use std::fmt::Write;
+use crate::warning::ALL;
+use crate::{RelatedSpanKind, Severity, Warning, WarningContext, WikiText};
+
+/// Format of diagnostics rendered by `render_diagnostics`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DiagnosticFormat {
+    /// Plain text like `DiagnosticFormat::Plain`, colored with ANSI escape codes for display in a terminal.
+    Ansi,
+
+    /// Plain text showing the line of each warning with the warning underlined, in the same style as the Rust compiler.
+    Plain,
+
+    /// JSON in the [SARIF](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) 2.1.0 format read by code scanning tools.
+    Sarif,
+}
+
+struct Label {
+    /// Whether the span goes on past the end of the line, to a label on its last line.
+    continues: bool,
+    end: usize,
+    line: usize,
+    primary: bool,
+    start: usize,
+    text: Option<&'static str>,
+}
+
+struct Lines<'a> {
+    starts: Vec<usize>,
+    text: &'a str,
+}
+
+impl<'a> Lines<'a> {
+    fn new(text: &'a str) -> Self {
+        Lines {
+            starts: std::iter::once(0)
+                .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+                .collect(),
+            text,
+        }
+    }
+
+    /// Returns the position limited to the end of the text. A warning about an element that is not closed at the end of the text ends one byte after the text.
+    fn clamp(&self, position: usize) -> usize {
+        position.min(self.text.len())
+    }
+
+    /// Returns the zero-based index of the line containing the byte position.
+    fn index(&self, position: usize) -> usize {
+        self.starts.partition_point(|&start| start <= position) - 1
+    }
+
+    /// Returns the text of the line with the zero-based index, without the line break.
+    fn line(&self, index: usize) -> &'a str {
+        let end = self.starts.get(index + 1).map_or(self.text.len(), |start| start - 1);
+        &self.text[self.starts[index]..end]
+    }
+
+    /// Returns the one-based line and column of the byte position, counting columns in characters.
+    fn line_column(&self, position: usize) -> (usize, usize) {
+        let position = self.clamp(position);
+        let index = self.index(position);
+        (index + 1, self.text[self.starts[index]..position].chars().count() + 1)
+    }
+}
+
+/// Renders warnings as diagnostics showing the place in the wiki text each warning is about.
+///
+/// The warnings must be from parsing the given wiki text, for example `Output::warnings`. The file name is shown as the location of the wiki text and can be any name identifying the page, such as its title. Lines and columns are counted from 1, with columns counted in characters.
+#[must_use]
+pub fn render_diagnostics(
+    warnings: &[Warning],
+    wiki_text: &WikiText,
+    file_name: &str,
+    format: DiagnosticFormat,
+) -> String {
+    let text = wiki_text.as_ref();
+    let lines = Lines::new(text);
+    match format {
+        DiagnosticFormat::Ansi => render_text(warnings, &lines, file_name, true),
+        DiagnosticFormat::Plain => render_text(warnings, &lines, file_name, false),
+        DiagnosticFormat::Sarif => render_sarif(warnings, &lines, file_name),
+    }
+}
+
+fn context_note(context: &WarningContext) -> String {
+    match context {
+        WarningContext::CharacterEntity { name } => format!("character entity `&{};`", name),
+        WarningContext::ReferenceName { name } => format!("reference name `{}`", name),
+        WarningContext::TagName { name } => format!("tag name `{}`", name),
+        WarningContext::TemplateParameter { name } => format!("template parameter `{}`", name),
+    }
+}
+
+fn labels(warning: &Warning, lines: &Lines) -> Vec<Label> {
+    let mut labels = vec![];
+    let mut push = |start: usize, end: usize, primary, text| {
+        let start = lines.clamp(start);
+        let end = lines.clamp(end.max(start));
+        let line = lines.index(start);
+        // A span ending right after a line break ends on the line of the line break.
+        let end_line = lines.index(end.saturating_sub(1).max(start));
+        if end_line > line {
+            labels.push(Label {
+                continues: true,
+                end: lines.starts[line + 1] - 1,
+                line,
+                primary,
+                start,
+                text: None,
+            });
+            labels.push(Label {
+                continues: false,
+                end,
+                line: end_line,
+                primary,
+                start: lines.starts[end_line],
+                text,
+            });
+        } else {
+            labels.push(Label {
+                continues: false,
+                end,
+                line,
+                primary,
+                start,
+                text,
+            });
+        }
+    };
+    push(warning.start, warning.end, true, None);
+    for span in &warning.related {
+        push(span.start, span.end, false, Some(related_text(span.kind)));
+    }
+    labels.sort_by_key(|label| (label.line, label.start));
+    labels
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Info => "note",
+        Severity::Warning => "warning",
+    }
+}
+
+fn paint(output: &mut String, ansi: bool, style: &str, text: &str) {
+    if ansi {
+        let _ = write!(output, "\x1b[{}m{}\x1b[0m", style, text);
+    } else {
+        output.push_str(text);
+    }
+}
+
+fn related_text(kind: RelatedSpanKind) -> &'static str {
+    match kind {
+        RelatedSpanKind::FirstDefinition => "first defined here",
+        RelatedSpanKind::Start => "started here",
+    }
+}
+
+fn render_sarif(warnings: &[Warning], lines: &Lines, file_name: &str) -> String {
+    let location = |start: usize, end: usize| {
+        let (start_line, start_column) = lines.line_column(start);
+        let (end_line, end_column) = lines.line_column(end);
+        json!({
+            "physicalLocation": {
+                "artifactLocation": { "uri": file_name },
+                "region": {
+                    "endColumn": end_column,
+                    "endLine": end_line,
+                    "startColumn": start_column,
+                    "startLine": start_line,
+                },
+            },
+        })
+    };
+    // Rules are identified by code. Warnings from the same lint rule can have different severities, and the rule then gets the severity of its first warning.
+    let mut messages = ALL.to_vec();
+    for warning in warnings {
+        if !messages.iter().any(|message| message.code() == warning.message.code()) {
+            messages.push(warning.message);
+        }
+    }
+    let rules: Vec<Value> = messages
+        .iter()
+        .map(|message| {
+            json!({
+                "defaultConfiguration": { "level": level(message.severity()) },
+                "id": message.code(),
+                "shortDescription": { "text": message.message() },
+            })
+        })
+        .collect();
+    let results: Vec<Value> = warnings
+        .iter()
+        .map(|warning| {
+            let mut text = warning.message.message().to_owned();
+            if let Some(context) = &warning.context {
+                let _ = write!(text, " ({})", context_note(context));
+            }
+            let mut result = json!({
+                "level": level(warning.severity()),
+                "locations": [location(warning.start, warning.end)],
+                "message": { "text": text },
+                "ruleId": warning.message.code(),
+                "ruleIndex": messages.iter().position(|message| message.code() == warning.message.code()),
+            });
+            if !warning.related.is_empty() {
+                result["relatedLocations"] = warning
+                    .related
+                    .iter()
+                    .enumerate()
+                    .map(|(index, span)| {
+                        let mut location = location(span.start, span.end);
+                        location["id"] = json!(index);
+                        location["message"] = json!({ "text": related_text(span.kind) });
+                        location
+                    })
+                    .collect();
+            }
+            result
+        })
+        .collect();
+    let sarif = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+            "tool": {
+                "driver": {
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "name": env!("CARGO_PKG_NAME"),
+                    "rules": rules,
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            },
+        }],
+        "version": "2.1.0",
+    });
+    let mut output = serde_json::to_string_pretty(&sarif).unwrap_or_default();
+    output.push('\n');
+    output
+}
+
+fn render_text(warnings: &[Warning], lines: &Lines, file_name: &str, ansi: bool) -> String {
+    let mut output = String::new();
+    for warning in warnings {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        let severity = warning.severity();
+        let style = match severity {
+            Severity::Error => "1;31",
+            Severity::Info => "1;36",
+            Severity::Warning => "1;33",
+        };
+        paint(&mut output, ansi, style, &format!("{}[{}]", severity, warning.message.code()));
+        paint(&mut output, ansi, "1", &format!(": {}", warning.message));
+        output.push('\n');
+        let labels = labels(warning, lines);
+        let gutter = (labels.iter().map(|label| label.line).max().unwrap_or(0) + 1)
+            .to_string()
+            .len();
+        let (line, column) = lines.line_column(warning.start);
+        paint(&mut output, ansi, "1;34", &format!("{:gutter$}--> ", ""));
+        let _ = writeln!(output, "{}:{}:{}", file_name, line, column);
+        paint(&mut output, ansi, "1;34", &format!("{:gutter$} |", ""));
+        output.push('\n');
+        let mut previous_line = None;
+        for label in &labels {
+            let line = lines.line(label.line);
+            if previous_line != Some(label.line) {
+                paint(&mut output, ansi, "1;34", &format!("{:gutter$} |", label.line + 1));
+                if !line.is_empty() {
+                    let _ = write!(output, " {}", line);
+                }
+                output.push('\n');
+                previous_line = Some(label.line);
+            }
+            let line_start = lines.starts[label.line];
+            let start = label.start - line_start;
+            let end = (label.end - line_start).min(line.len());
+            let indent: String = line[..start]
+                .chars()
+                .map(|character| if character == '\t' { '\t' } else { ' ' })
+                .collect();
+            let marker = if label.primary { "^" } else { "-" };
+            let mut underline = marker.repeat(line[start..end].chars().count().max(1));
+            if label.continues {
+                underline.push_str("...");
+            }
+            if let Some(text) = label.text {
+                underline.push(' ');
+                underline.push_str(text);
+            }
+            paint(&mut output, ansi, "1;34", &format!("{:gutter$} |", ""));
+            output.push(' ');
+            output.push_str(&indent);
+            paint(&mut output, ansi, if label.primary { style } else { "1;34" }, &underline);
+            output.push('\n');
+        }
+        if let Some(context) = &warning.context {
+            paint(&mut output, ansi, "1;34", &format!("{:gutter$} =", ""));
+            let _ = writeln!(output, " note: {}", context_note(context));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{render_diagnostics, Configuration, DiagnosticFormat, LintMessage, Severity, Warning, WarningMessage, WikiText};
+
+    const WIKI_TEXT: &str = "&bogus; <blink>\n<ref>b";
+
+    fn sarif(warnings: &[Warning], wiki_text: &WikiText) -> serde_json::Value {
+        serde_json::from_str(&render_diagnostics(warnings, wiki_text, "Page", DiagnosticFormat::Sarif)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_works() {
+        let wiki_text = WikiText::new(WIKI_TEXT);
+        let output = Configuration::default().parse(wiki_text.clone()).await;
+        assert_eq!(
+            render_diagnostics(&output.warnings, &wiki_text, "Page", DiagnosticFormat::Plain),
+            concat!(
+                "warning[PWT0021]: A character entity with an unrecognized name was found.\n",
+                " --> Page:1:1\n",
+                "  |\n",
+                "1 | &bogus; <blink>\n",
+                "  | ^^^^^^^\n",
+                "  = note: character entity `&bogus;`\n",
+                "\n",
+                "warning[PWT0022]: A tag with an unrecognized tag name was found.\n",
+                " --> Page:1:10\n",
+                "  |\n",
+                "1 | &bogus; <blink>\n",
+                "  |          ^^^^^\n",
+                "  = note: tag name `blink`\n",
+                "\n",
+                "error[PWT0012]: Missing end tag. Rewinding.\n",
+                " --> Page:2:1\n",
+                "  |\n",
+                "2 | <ref>b\n",
+                "  | ^^^^^^\n",
+                "  | ----- started here\n",
+                "  = note: tag name `ref`\n",
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn ansi() {
+        let wiki_text = WikiText::new(WIKI_TEXT);
+        let output = Configuration::default().parse(wiki_text.clone()).await;
+        let ansi = render_diagnostics(&output.warnings, &wiki_text, "Page", DiagnosticFormat::Ansi);
+        assert!(ansi.starts_with("\x1b[1;33mwarning[PWT0021]\x1b[0m"));
+    }
+
+    #[tokio::test]
+    async fn multi_line_span() {
+        let wiki_text = WikiText::new("a\n<ref>b\nc\nd");
+        let output = Configuration::default().parse(wiki_text.clone()).await;
+        assert_eq!(
+            render_diagnostics(&output.warnings, &wiki_text, "Page", DiagnosticFormat::Plain),
+            concat!(
+                "error[PWT0012]: Missing end tag. Rewinding.\n",
+                " --> Page:2:1\n",
+                "  |\n",
+                "2 | <ref>b\n",
+                "  | ^^^^^^...\n",
+                "  | ----- started here\n",
+                "4 | d\n",
+                "  | ^\n",
+                "  = note: tag name `ref`\n",
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn sarif_results() {
+        let wiki_text = WikiText::new(WIKI_TEXT);
+        let output = Configuration::default().parse(wiki_text.clone()).await;
+        let sarif = sarif(&output.warnings, &wiki_text);
+        let result = &sarif["runs"][0]["results"][2];
+        assert_eq!(result["ruleId"], "PWT0012");
+        assert_eq!(result["level"], "error");
+        assert_eq!(result["locations"][0]["physicalLocation"]["region"]["startLine"], 2);
+        assert_eq!(result["relatedLocations"][0]["message"]["text"], "started here");
+        assert_eq!(sarif["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap().len(), 25);
+    }
+
+    #[tokio::test]
+    async fn sarif_lint_rule_with_several_severities() {
+        let wiki_text = WikiText::new("a b");
+        let warning = |start, severity| Warning {
+            context: None,
+            end: start + 1,
+            fix: None,
+            message: WarningMessage::Lint(LintMessage {
+                description: "Description.",
+                rule: "rule",
+                severity,
+            }),
+            related: vec![],
+            start,
+        };
+        let sarif = sarif(&[warning(0, Severity::Warning), warning(2, Severity::Error)], &wiki_text);
+        let rules = sarif["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.iter().filter(|rule| rule["id"] == "rule").count(), 1);
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results[0]["ruleIndex"], results[1]["ruleIndex"]);
+        assert_eq!(rules[results[1]["ruleIndex"].as_u64().unwrap() as usize]["id"], "rule");
+        assert_eq!(results[1]["level"], "error");
+    }
+}
@@ -0,0 +1,660 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+//! Streaming reader for dumps of a wiki in the XML export format of Mediawiki, such as `pages-articles.xml`.
+//!
+//! Dumps compressed with bzip2 or gzip are decompressed while reading. Pages of a multistream dump can be read one by one using its index with `MultistreamDump`.
+//!
+//! ```no_run
+//! use async_parse_wiki_text::dump::{open, DumpReader};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut reader = DumpReader::new(open("enwiki-latest-pages-articles.xml.bz2").await?).await?;
+//! while let Some(page) = reader.next_page().await? {
+//!     for revision in &page.revisions {
+//!         let output = revision.parse(reader.configuration()).await;
+//!         println!("{}: {} nodes", page.title, output.nodes.len());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use async_compression::tokio::bufread::{BzDecoder, GzipDecoder};
+use quick_xml::events::{BytesStart, Event};
+use std::{collections::HashMap, fmt, io::SeekFrom, path::Path, sync::Arc};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncSeek, AsyncSeekExt, BufReader};
+use crate::{Configuration, Namespace, Output, SiteNamespace, WikiText};
+
+/// Error from reading a dump.
+#[derive(Clone, Debug)]
+pub enum DumpError {
+    /// An element needed for reading the dump is missing or has an unexpected format.
+    InvalidElement(&'static str),
+
+    /// A line of a multistream index is not in the format `offset:id:title`. The number is the line number, starting from 1.
+    InvalidIndexLine(usize),
+
+    /// The dump is not well-formed XML.
+    InvalidXml(String),
+
+    /// Reading from the underlying reader failed.
+    Io(Arc<std::io::Error>),
+}
+
+/// Streaming reader for a dump, reading one page at a time.
+pub struct DumpReader<R> {
+    configuration: Configuration,
+    reader: XmlReader<R>,
+    siteinfo: Siteinfo,
+}
+
+/// Dump compressed with bzip2 as multiple streams, read by looking up pages in its index, such as `pages-articles-multistream.xml.bz2` together with `pages-articles-multistream-index.txt.bz2`.
+///
+/// Each page is read by decompressing only the stream containing the page, so any page can be read without decompressing the whole dump.
+pub struct MultistreamDump<F> {
+    configuration: Configuration,
+    file: F,
+    index: MultistreamIndex,
+    siteinfo: Siteinfo,
+}
+
+/// Index of a multistream dump, giving the byte offset in the dump of the stream containing each page.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MultistreamIndex {
+    ids: HashMap<u64, u64>,
+    titles: HashMap<String, u64>,
+}
+
+/// Page of a dump.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Page {
+    /// The identifier of the page.
+    pub id: u64,
+
+    /// The namespace of the page.
+    pub ns: Namespace,
+
+    /// The title of the page the page redirects to, if it's a redirect.
+    pub redirect: Option<String>,
+
+    /// The revisions of the page included in the dump, oldest first.
+    pub revisions: Vec<Revision>,
+
+    /// The title of the page, including namespace prefix.
+    pub title: String,
+}
+
+/// Revision of a page in a dump.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Revision {
+    /// The edit summary, unless it's missing or deleted.
+    pub comment: Option<String>,
+
+    /// The user name or IP address of the contributor, unless it's deleted.
+    pub contributor: Option<String>,
+
+    /// The serialization format of the text, such as `text/x-wiki`.
+    pub format: Option<String>,
+
+    /// The identifier of the revision.
+    pub id: u64,
+
+    /// Whether the revision is marked as a minor edit.
+    pub minor: bool,
+
+    /// The content model of the text, such as `wikitext`.
+    pub model: Option<String>,
+
+    /// The identifier of the previous revision of the page, if any.
+    pub parent_id: Option<u64>,
+
+    /// The SHA-1 hash of the text in base 36, unless it's missing.
+    pub sha1: Option<String>,
+
+    /// The text of the revision. Empty if the text is deleted.
+    pub text: WikiText,
+
+    /// The time the revision was made, in ISO 8601 format.
+    pub timestamp: String,
+}
+
+/// Information about the wiki a dump is from, read from the `<siteinfo>` element.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Siteinfo {
+    /// The URL of the main page of the wiki.
+    pub base: String,
+
+    /// The name of the database of the wiki, such as `enwiki`.
+    pub dbname: String,
+
+    /// The version of Mediawiki that made the dump.
+    pub generator: String,
+
+    /// The namespaces of the wiki.
+    pub namespaces: Vec<SiteNamespace>,
+
+    /// The name of the wiki.
+    pub sitename: String,
+}
+
+struct XmlReader<R> {
+    buffer: Vec<u8>,
+    reader: quick_xml::Reader<R>,
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DumpError::InvalidElement(name) => write!(
+                formatter,
+                "The element {:?} of the dump is missing or invalid.",
+                name
+            ),
+            DumpError::InvalidIndexLine(line) => write!(
+                formatter,
+                "Line {} of the multistream index is invalid.",
+                line
+            ),
+            DumpError::InvalidXml(message) => write!(formatter, "The dump is not valid XML: {}", message),
+            DumpError::Io(error) => write!(formatter, "Failed to read the dump: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for DumpError {}
+
+impl From<std::io::Error> for DumpError {
+    fn from(error: std::io::Error) -> Self {
+        DumpError::Io(Arc::new(error))
+    }
+}
+
+impl From<quick_xml::Error> for DumpError {
+    fn from(error: quick_xml::Error) -> Self {
+        match error {
+            quick_xml::Error::Io(error) => DumpError::Io(error),
+            error => DumpError::InvalidXml(error.to_string()),
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> DumpReader<R> {
+    /// Starts reading a dump, reading the site info at the start of the dump.
+    ///
+    /// The configuration for parsing the pages is made from the default configuration with the namespaces replaced by the namespaces of the site info. The other parts of the configuration are not included in dumps.
+    pub async fn new(reader: R) -> Result<Self, DumpError> {
+        let mut reader = XmlReader::new(reader);
+        let siteinfo = reader.read_siteinfo().await?;
+        Ok(DumpReader {
+            configuration: configuration(&siteinfo),
+            reader,
+            siteinfo,
+        })
+    }
+
+    /// Returns the configuration for parsing the pages of the dump.
+    #[must_use]
+    pub fn configuration(&self) -> &Configuration {
+        &self.configuration
+    }
+
+    /// Reads the next page of the dump. Returns `None` at the end of the dump.
+    pub async fn next_page(&mut self) -> Result<Option<Page>, DumpError> {
+        self.reader.next_page().await
+    }
+
+    /// Returns the information about the wiki the dump is from.
+    #[must_use]
+    pub fn siteinfo(&self) -> &Siteinfo {
+        &self.siteinfo
+    }
+}
+
+impl<F: AsyncRead + AsyncSeek + Unpin> MultistreamDump<F> {
+    /// Starts reading a multistream dump from a file that is not decompressed, reading the site info from the first stream of the dump.
+    ///
+    /// The configuration for parsing the pages is made the same way as for `DumpReader`.
+    pub async fn new(mut file: F, index: MultistreamIndex) -> Result<Self, DumpError> {
+        let siteinfo = stream(&mut file, 0).await?.read_siteinfo().await?;
+        Ok(MultistreamDump {
+            configuration: configuration(&siteinfo),
+            file,
+            index,
+            siteinfo,
+        })
+    }
+
+    /// Returns the configuration for parsing the pages of the dump.
+    #[must_use]
+    pub fn configuration(&self) -> &Configuration {
+        &self.configuration
+    }
+
+    /// Reads the page with the given identifier. Returns `None` if the page is not in the index.
+    pub async fn page_by_id(&mut self, id: u64) -> Result<Option<Page>, DumpError> {
+        let Some(offset) = self.index.offset_by_id(id) else {
+            return Ok(None);
+        };
+        self.find_page(offset, |page| page.id == id).await
+    }
+
+    /// Reads the page with the given title, including namespace prefix. Returns `None` if the page is not in the index.
+    pub async fn page_by_title(&mut self, title: &str) -> Result<Option<Page>, DumpError> {
+        let title = title.replace('_', " ");
+        let Some(offset) = self.index.offset_by_title(&title) else {
+            return Ok(None);
+        };
+        self.find_page(offset, |page| page.title == title).await
+    }
+
+    /// Returns the information about the wiki the dump is from.
+    #[must_use]
+    pub fn siteinfo(&self) -> &Siteinfo {
+        &self.siteinfo
+    }
+
+    async fn find_page(&mut self, offset: u64, predicate: impl Fn(&Page) -> bool) -> Result<Option<Page>, DumpError> {
+        let mut reader = stream(&mut self.file, offset).await?;
+        while let Some(page) = reader.next_page().await? {
+            if predicate(&page) {
+                return Ok(Some(page));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl MultistreamIndex {
+    /// Reads an index from its decompressed text, with one line in the format `offset:id:title` for each page.
+    pub async fn read<R: AsyncBufRead + Unpin>(reader: R) -> Result<Self, DumpError> {
+        let mut index = MultistreamIndex::default();
+        let mut lines = reader.lines();
+        let mut line_number = 0;
+        while let Some(line) = lines.next_line().await? {
+            line_number += 1;
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields = line.splitn(3, ':');
+            let (Some(offset), Some(id), Some(title)) = (
+                fields.next().and_then(|offset| offset.parse().ok()),
+                fields.next().and_then(|id| id.parse().ok()),
+                fields.next(),
+            ) else {
+                return Err(DumpError::InvalidIndexLine(line_number));
+            };
+            index.ids.insert(id, offset);
+            index.titles.insert(title.to_owned(), offset);
+        }
+        Ok(index)
+    }
+
+    /// Returns the byte offset of the stream containing the page with the given identifier.
+    #[must_use]
+    pub fn offset_by_id(&self, id: u64) -> Option<u64> {
+        self.ids.get(&id).copied()
+    }
+
+    /// Returns the byte offset of the stream containing the page with the given title, including namespace prefix.
+    #[must_use]
+    pub fn offset_by_title(&self, title: &str) -> Option<u64> {
+        self.titles.get(title).copied()
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> XmlReader<R> {
+    fn new(reader: R) -> Self {
+        XmlReader {
+            buffer: vec![],
+            reader: quick_xml::Reader::from_reader(reader),
+        }
+    }
+
+    async fn next_page(&mut self) -> Result<Option<Page>, DumpError> {
+        loop {
+            match self.next_event().await? {
+                Event::Eof => return Ok(None),
+                Event::Start(element) if element.local_name().as_ref() == b"page" => {
+                    return self.read_page().await.map(Some)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn next_event(&mut self) -> Result<Event<'static>, DumpError> {
+        self.buffer.clear();
+        Ok(self
+            .reader
+            .read_event_into_async(&mut self.buffer)
+            .await?
+            .into_owned())
+    }
+
+    async fn read_page(&mut self) -> Result<Page, DumpError> {
+        let mut id = None;
+        let mut ns = None;
+        let mut redirect = None;
+        let mut revisions = vec![];
+        let mut title = None;
+        loop {
+            match self.next_event().await? {
+                Event::Empty(element) if element.local_name().as_ref() == b"redirect" => {
+                    redirect = Some(attribute(&element, b"title")?.unwrap_or_default());
+                }
+                Event::Start(element) => match element.local_name().as_ref() {
+                    b"id" => id = Some(number(self.read_text().await?, "id")?),
+                    b"ns" => {
+                        ns = Some(Namespace {
+                            id: number(self.read_text().await?, "ns")?,
+                        })
+                    }
+                    b"redirect" => {
+                        redirect = Some(attribute(&element, b"title")?.unwrap_or_default());
+                        self.read_text().await?;
+                    }
+                    b"revision" => revisions.push(self.read_revision().await?),
+                    b"title" => title = Some(self.read_text().await?),
+                    _ => self.skip().await?,
+                },
+                Event::End(_) => break,
+                Event::Eof => return Err(DumpError::InvalidElement("page")),
+                _ => {}
+            }
+        }
+        Ok(Page {
+            id: id.ok_or(DumpError::InvalidElement("id"))?,
+            ns: ns.ok_or(DumpError::InvalidElement("ns"))?,
+            redirect,
+            revisions,
+            title: title.ok_or(DumpError::InvalidElement("title"))?,
+        })
+    }
+
+    async fn read_revision(&mut self) -> Result<Revision, DumpError> {
+        let mut revision = Revision {
+            comment: None,
+            contributor: None,
+            format: None,
+            id: 0,
+            minor: false,
+            model: None,
+            parent_id: None,
+            sha1: None,
+            text: WikiText::new(""),
+            timestamp: String::new(),
+        };
+        let mut id = None;
+        loop {
+            match self.next_event().await? {
+                Event::Empty(element) if element.local_name().as_ref() == b"minor" => revision.minor = true,
+                Event::Start(element) => match element.local_name().as_ref() {
+                    b"comment" => revision.comment = Some(self.read_text().await?),
+                    b"contributor" => revision.contributor = self.read_contributor().await?,
+                    b"format" => revision.format = Some(self.read_text().await?),
+                    b"id" => id = Some(number(self.read_text().await?, "id")?),
+                    b"minor" => {
+                        revision.minor = true;
+                        self.skip().await?;
+                    }
+                    b"model" => revision.model = Some(self.read_text().await?),
+                    b"parentid" => revision.parent_id = Some(number(self.read_text().await?, "parentid")?),
+                    b"sha1" => revision.sha1 = Some(self.read_text().await?),
+                    b"text" => revision.text = WikiText::new(self.read_text().await?),
+                    b"timestamp" => revision.timestamp = self.read_text().await?,
+                    _ => self.skip().await?,
+                },
+                Event::End(_) => break,
+                Event::Eof => return Err(DumpError::InvalidElement("revision")),
+                _ => {}
+            }
+        }
+        revision.id = id.ok_or(DumpError::InvalidElement("id"))?;
+        Ok(revision)
+    }
+
+    async fn read_contributor(&mut self) -> Result<Option<String>, DumpError> {
+        let mut contributor = None;
+        loop {
+            match self.next_event().await? {
+                Event::Start(element) => match element.local_name().as_ref() {
+                    b"ip" | b"username" => contributor = Some(self.read_text().await?),
+                    _ => self.skip().await?,
+                },
+                Event::End(_) => return Ok(contributor),
+                Event::Eof => return Err(DumpError::InvalidElement("contributor")),
+                _ => {}
+            }
+        }
+    }
+
+    async fn read_siteinfo(&mut self) -> Result<Siteinfo, DumpError> {
+        loop {
+            match self.next_event().await? {
+                Event::Eof => return Err(DumpError::InvalidElement("siteinfo")),
+                Event::Start(element) if element.local_name().as_ref() == b"siteinfo" => break,
+                _ => {}
+            }
+        }
+        let mut siteinfo = Siteinfo::default();
+        loop {
+            match self.next_event().await? {
+                Event::Start(element) => match element.local_name().as_ref() {
+                    b"base" => siteinfo.base = self.read_text().await?,
+                    b"dbname" => siteinfo.dbname = self.read_text().await?,
+                    b"generator" => siteinfo.generator = self.read_text().await?,
+                    b"namespace" => siteinfo.namespaces.push(SiteNamespace {
+                        name: self.read_text().await?,
+                        ..namespace(&element)?
+                    }),
+                    b"namespaces" => {}
+                    b"sitename" => siteinfo.sitename = self.read_text().await?,
+                    _ => self.skip().await?,
+                },
+                Event::Empty(element) if element.local_name().as_ref() == b"namespace" => {
+                    siteinfo.namespaces.push(namespace(&element)?);
+                }
+                Event::End(element) if element.local_name().as_ref() == b"siteinfo" => return Ok(siteinfo),
+                Event::Eof => return Err(DumpError::InvalidElement("siteinfo")),
+                _ => {}
+            }
+        }
+    }
+
+    async fn read_text(&mut self) -> Result<String, DumpError> {
+        let mut depth = 0;
+        let mut text = String::new();
+        loop {
+            match self.next_event().await? {
+                Event::CData(data) => text += &String::from_utf8_lossy(&data),
+                Event::End(_) if depth == 0 => return Ok(text),
+                Event::End(_) => depth -= 1,
+                Event::Eof => return Err(DumpError::InvalidXml("unexpected end of file".to_owned())),
+                Event::Start(_) => depth += 1,
+                Event::Text(value) => text += &value.unescape()?,
+                _ => {}
+            }
+        }
+    }
+
+    async fn skip(&mut self) -> Result<(), DumpError> {
+        self.read_text().await.map(|_| ())
+    }
+}
+
+impl Revision {
+    /// Parses the text of the revision with the given configuration.
+    pub async fn parse(&self, configuration: &Configuration) -> Output {
+        configuration.parse(self.text.clone()).await
+    }
+}
+
+/// Opens a dump or an index, decompressing it if it's compressed with bzip2 or gzip.
+pub async fn open(path: impl AsRef<Path>) -> Result<Box<dyn AsyncBufRead + Send + Unpin>, DumpError> {
+    decompress(BufReader::new(tokio::fs::File::open(path).await?)).await
+}
+
+/// Returns a reader of the decompressed data of the given reader if the data is compressed with bzip2 or gzip, recognized by its first bytes, or otherwise a reader of the data as is.
+///
+/// Data made of multiple compressed streams, such as a multistream dump, is decompressed as a whole.
+pub async fn decompress<R: AsyncBufRead + Send + Unpin + 'static>(
+    mut reader: R,
+) -> Result<Box<dyn AsyncBufRead + Send + Unpin>, DumpError> {
+    let header = reader.fill_buf().await?;
+    Ok(if header.starts_with(b"BZh") {
+        let mut decoder = BzDecoder::new(reader);
+        decoder.multiple_members(true);
+        Box::new(BufReader::new(decoder))
+    } else if header.starts_with(&[0x1f, 0x8b]) {
+        let mut decoder = GzipDecoder::new(reader);
+        decoder.multiple_members(true);
+        Box::new(BufReader::new(decoder))
+    } else {
+        Box::new(reader)
+    })
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Result<Option<String>, DumpError> {
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(quick_xml::Error::from)?;
+        if attribute.key.local_name().as_ref() == name {
+            return Ok(Some(attribute.unescape_value()?.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+fn configuration(siteinfo: &Siteinfo) -> Configuration {
+    let default = Configuration::default();
+    let mut site_config = default.to_source();
+    site_config.namespaces = siteinfo
+        .namespaces
+        .iter()
+        .map(|namespace| SiteNamespace {
+            canonical_name: default
+                .canonical_namespace_name(Namespace { id: namespace.id })
+                .unwrap_or(&namespace.name)
+                .to_owned(),
+            ..namespace.clone()
+        })
+        .collect();
+    match Configuration::from_site_config(site_config) {
+        Err(_) => unreachable!("the link prefix and link trail of the default configuration are valid"),
+        Ok(configuration) => configuration,
+    }
+}
+
+fn namespace(element: &BytesStart) -> Result<SiteNamespace, DumpError> {
+    Ok(SiteNamespace {
+        case_sensitive: attribute(element, b"case")?.as_deref() == Some("case-sensitive"),
+        id: number(
+            attribute(element, b"key")?.ok_or(DumpError::InvalidElement("namespace"))?,
+            "namespace",
+        )?,
+        ..Default::default()
+    })
+}
+
+async fn stream<F: AsyncRead + AsyncSeek + Unpin>(
+    file: &mut F,
+    offset: u64,
+) -> Result<XmlReader<BufReader<BzDecoder<BufReader<&mut F>>>>, DumpError> {
+    file.seek(SeekFrom::Start(offset)).await?;
+    let mut reader = XmlReader::new(BufReader::new(BzDecoder::new(BufReader::new(file))));
+    reader.reader.config_mut().check_end_names = false;
+    Ok(reader)
+}
+
+fn number<T: std::str::FromStr>(text: String, element: &'static str) -> Result<T, DumpError> {
+    text.trim().parse().map_err(|_| DumpError::InvalidElement(element))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{open, DumpReader, MultistreamDump, MultistreamIndex};
+    use crate::{Namespace, Node};
+
+    #[tokio::test]
+    async fn it_works() {
+        let file = tokio::fs::File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/data/dump.xml"))
+            .await
+            .unwrap();
+        let mut reader = DumpReader::new(tokio::io::BufReader::new(file)).await.unwrap();
+        assert_eq!(reader.siteinfo().dbname, "enwiki");
+        assert_eq!(reader.siteinfo().namespaces.len(), 9);
+        assert!(reader.configuration().is_case_sensitive(Namespace { id: 2600 }));
+        assert_eq!(
+            reader.configuration().namespace_name(Namespace::TEMPLATE),
+            Some("Template")
+        );
+
+        let page = reader.next_page().await.unwrap().unwrap();
+        assert_eq!((page.id, page.ns, &page.title as &str), (7, Namespace::MAIN, "Centrosome"));
+        assert_eq!(page.redirect, None);
+        assert_eq!(page.revisions.len(), 2);
+        assert_eq!(page.revisions[0].contributor.as_deref(), Some("Example"));
+        assert_eq!(page.revisions[0].text.as_ref(), "An '''organelle'''.");
+        let revision = &page.revisions[1];
+        assert_eq!((revision.id, revision.parent_id, revision.minor), (101, Some(100), true));
+        assert_eq!(revision.contributor.as_deref(), Some("192.0.2.1"));
+        assert_eq!(revision.comment, None);
+        assert_eq!(
+            revision.text.as_ref(),
+            "The '''centrosome''' is an [[organelle]] & more.\n[[Category:Organelles]]"
+        );
+        let output = revision.parse(reader.configuration()).await;
+        assert!(output
+            .nodes
+            .iter()
+            .any(|node| matches!(node, Node::Category { .. })));
+
+        let page = reader.next_page().await.unwrap().unwrap();
+        assert_eq!(page.redirect.as_deref(), Some("Centrosome"));
+        let output = page.revisions[0].parse(reader.configuration()).await;
+        assert!(matches!(output.nodes[0], Node::Redirect { .. }));
+
+        let page = reader.next_page().await.unwrap().unwrap();
+        assert_eq!((page.ns, &page.title as &str), (Namespace::TEMPLATE, "Template:Infobox organelle"));
+        assert_eq!(page.revisions[0].contributor, None);
+        assert_eq!(page.revisions[0].text.as_ref(), "");
+
+        assert_eq!(reader.next_page().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn compressed() {
+        for path in ["data/dump.xml.bz2", "data/dump.xml.gz"] {
+            let file = open(format!("{}/{}", env!("CARGO_MANIFEST_DIR"), path)).await.unwrap();
+            let mut reader = DumpReader::new(file).await.unwrap();
+            let mut ids = vec![];
+            while let Some(page) = reader.next_page().await.unwrap() {
+                ids.push(page.id);
+            }
+            assert_eq!(ids, [7, 8, 9]);
+        }
+    }
+
+    #[tokio::test]
+    async fn multistream() {
+        let index = open(concat!(env!("CARGO_MANIFEST_DIR"), "/data/multistream-index.txt.bz2"))
+            .await
+            .unwrap();
+        let index = MultistreamIndex::read(index).await.unwrap();
+        assert_eq!(index.offset_by_id(7), index.offset_by_title("Centrosome"));
+        let file = tokio::fs::File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/data/multistream.xml.bz2"))
+            .await
+            .unwrap();
+        let mut dump = MultistreamDump::new(file, index).await.unwrap();
+        assert_eq!(dump.siteinfo().dbname, "enwiki");
+        let page = dump.page_by_title("Template:Infobox_organelle").await.unwrap().unwrap();
+        assert_eq!(page.id, 9);
+        let page = dump.page_by_id(7).await.unwrap().unwrap();
+        assert_eq!(page.revisions.len(), 2);
+        let page = dump.page_by_id(8).await.unwrap().unwrap();
+        assert_eq!(page.redirect.as_deref(), Some("Centrosome"));
+        assert_eq!(dump.page_by_id(10).await.unwrap(), None);
+        assert_eq!(dump.page_by_title("Nucleus").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,881 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use futures_util::{stream, Stream, StreamExt};
+use std::{collections::VecDeque, ops::Range};
+use crate::state::State;
+use crate::{
+    parse, Configuration, DefinitionListItem, DefinitionListItemType, FormattingStyle, LinkType, ListItem, Namespace,
+    Node, Output, Parameter, Positioned, TableCaption, TableCell, TableCellType, TableRow, Warning, WarningMessage, WikiText,
+};
+
+/// Event from parsing wiki text, as yielded by `Configuration::parse_events`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// End of an element, with the byte position in the wiki text where the element ends.
+    End(NodeKind, usize),
+
+    /// Start of an element, with the byte position in the wiki text where the element starts.
+    Start(NodeKind, usize),
+
+    /// Plain text, with the range of bytes in the wiki text.
+    Text(Range<usize>),
+
+    /// Warning from the parser telling that something is not well-formed.
+    Warning(Warning),
+}
+
+/// Kind of element in the events from parsing wiki text, with the information of the element that is not in its content.
+///
+/// Most kinds correspond to a variant of `Node` or a struct for a part of an element. `Attributes`, `Default` and `Name` are groups of nodes in the element enclosing them, and are only emitted when the corresponding field is present and not empty.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeKind {
+    /// The HTML attributes of a table, table caption, table row or table cell.
+    Attributes,
+
+    /// Toggle bold text.
+    Bold,
+
+    /// Toggle bold and italic text.
+    BoldItalic,
+
+    /// Category. The content is the ordinal.
+    Category {
+        /// The category referred to.
+        target: WikiText,
+    },
+
+    /// Character entity or numeric character reference.
+    CharacterEntity {
+        /// The character represented.
+        character: char,
+
+        /// The second character represented, for the few entities that represent two characters.
+        second_character: Option<char>,
+    },
+
+    /// Comment.
+    Comment,
+
+    /// The default value of a parameter.
+    Default,
+
+    /// Definition list.
+    DefinitionList,
+
+    /// List item of a definition list.
+    DefinitionListItem {
+        /// The type of list item.
+        type_: DefinitionListItemType,
+    },
+
+    /// End tag.
+    EndTag {
+        /// The tag name.
+        name: WikiText,
+    },
+
+    /// Construct that was attempted but is not well-formed.
+    Error {
+        /// The kind of element that was attempted.
+        attempted: Box<NodeKind>,
+
+        /// The warning telling why the attempt failed.
+        kind: WarningMessage,
+    },
+
+    /// External link.
+    ExternalLink,
+
+    /// Bold or italic text.
+    Formatted {
+        /// The style of the formatting.
+        style: FormattingStyle,
+    },
+
+    /// Heading.
+    Heading {
+        /// The level of the heading from 1 to 6.
+        level: u8,
+    },
+
+    /// Horizontal divider.
+    HorizontalDivider,
+
+    /// Image. The content is the additional information for the image.
+    Image {
+        /// The file name of the image.
+        target: WikiText,
+    },
+
+    /// Link to the same page in a wiki in another language.
+    InterlanguageLink {
+        /// The lowercase language prefix.
+        language: WikiText,
+
+        /// The page in the other wiki.
+        target: WikiText,
+    },
+
+    /// Toggle italic text.
+    Italic,
+
+    /// Link. The content is the text to display for the link.
+    Link {
+        /// The namespace of the target of the link.
+        namespace: Namespace,
+
+        /// The target of the link.
+        target: WikiText,
+
+        /// The type of link.
+        type_: LinkType,
+    },
+
+    /// List item of an ordered list or unordered list.
+    ListItem,
+
+    /// Magic word.
+    MagicWord,
+
+    /// The name of a parameter, template or template parameter.
+    Name,
+
+    /// Ordered list.
+    OrderedList,
+
+    /// Paragraph break.
+    ParagraphBreak,
+
+    /// Parameter.
+    Parameter,
+
+    /// Block of preformatted text.
+    Preformatted,
+
+    /// Redirect.
+    Redirect {
+        /// The target of the redirect.
+        target: WikiText,
+    },
+
+    /// Start tag.
+    StartTag {
+        /// The tag name.
+        name: WikiText,
+    },
+
+    /// Table. The content is the captions and rows.
+    Table,
+
+    /// Table caption.
+    TableCaption,
+
+    /// Table cell.
+    TableCell {
+        /// The type of cell.
+        type_: TableCellType,
+    },
+
+    /// Table row. The content is the cells.
+    TableRow,
+
+    /// Extension tag.
+    Tag {
+        /// The tag name.
+        name: WikiText,
+    },
+
+    /// Template. The content is the name and the parameters.
+    Template,
+
+    /// Template parameter. The content is the name, if any, and the value.
+    TemplateParameter,
+
+    /// Unordered list.
+    UnorderedList,
+}
+
+/// Builder of the tree of nodes from the events from parsing wiki text.
+///
+/// Collecting all the events from `Configuration::parse_events` gives the same output as `Configuration::parse`, which is itself implemented this way.
+pub struct TreeBuilder {
+    nodes: Vec<Node>,
+    stack: Vec<Frame>,
+    warnings: Vec<Warning>,
+    wiki_text: WikiText,
+}
+
+struct EventParser<'a> {
+    configuration: &'a Configuration,
+    finished: bool,
+    state: State,
+}
+
+struct Frame {
+    attributes: Option<Vec<Node>>,
+    default: Option<Vec<Node>>,
+    kind: NodeKind,
+    name: Option<Vec<Node>>,
+    nodes: Vec<Node>,
+    parts: Vec<Part>,
+    start: usize,
+}
+
+enum Part {
+    DefinitionListItem(DefinitionListItem),
+    ListItem(ListItem),
+    TableCaption(TableCaption),
+    TableCell(TableCell),
+    TableRow(TableRow),
+    TemplateParameter(Parameter),
+}
+
+impl Configuration {
+    /// Parses wiki text into a stream of events, without building the whole tree of nodes.
+    ///
+    /// The parser may rewind to the start of a construct that turns out not to be closed and parse the text after it again differently, so events are buffered until they are confirmed. The parser emits the events of a node when it pushes the node at the top level, which is when the node is complete and no other node is open. While a node is open, the nodes inside it and after it are buffered, since they may yet be parsed again. Text at the top level is emitted with the node after it, since a rewind or a link prefix may still take it back until then. Only the element being parsed at the top level therefore needs to be held in memory, but not the elements before it. Warnings are emitted after the nodes emitted at the same time, in the same order as in `Output::warnings`, including warnings from text that was parsed again.
+    pub fn parse_events(&self, wiki_text: WikiText) -> impl Stream<Item = Event> + '_ {
+        stream::once(parse::start(self, wiki_text)).flat_map(move |mut state| {
+            state.events = Some(VecDeque::new());
+            stream::unfold(
+                EventParser {
+                    configuration: self,
+                    finished: false,
+                    state,
+                },
+                |mut parser| async move {
+                    loop {
+                        if let Some(event) = parser.state.events.as_mut().and_then(VecDeque::pop_front) {
+                            return Some((event, parser));
+                        }
+                        if parser.finished {
+                            return None;
+                        }
+                        parser.parse_confirmed().await;
+                    }
+                },
+            )
+        })
+    }
+}
+
+impl<'a> EventParser<'a> {
+    async fn parse_confirmed(&mut self) {
+        match parse::parse_lines(self.configuration, &mut self.state, None, parse::has_events).await {
+            None => unreachable!("parsing without a deadline is never aborted"),
+            Some(true) => {}
+            Some(false) => {
+                self.state.emit(self.state.nodes.len());
+                self.finished = true;
+            }
+        }
+    }
+}
+
+impl Frame {
+    fn new(kind: NodeKind, start: usize) -> Self {
+        Frame {
+            attributes: None,
+            default: None,
+            kind,
+            name: None,
+            nodes: vec![],
+            parts: vec![],
+            start,
+        }
+    }
+}
+
+impl TreeBuilder {
+    /// Creates a builder for the events from parsing the given wiki text.
+    #[must_use]
+    pub fn new(wiki_text: WikiText) -> Self {
+        TreeBuilder {
+            nodes: vec![],
+            stack: vec![],
+            warnings: vec![],
+            wiki_text,
+        }
+    }
+
+    /// Returns the output built from the events.
+    ///
+    /// Panics if an element is not ended.
+    #[must_use]
+    pub fn finish(self) -> Output {
+        assert!(self.stack.is_empty(), "element not ended");
+        Output {
+            nodes: self.nodes,
+            warnings: self.warnings,
+        }
+    }
+
+    /// Adds the given event to the tree.
+    ///
+    /// Panics if the event doesn't fit the events before it the way the events from the parser do.
+    pub fn push(&mut self, event: Event) {
+        match event {
+            Event::End(kind, end) => self.end(kind, end),
+            Event::Start(kind, start) => self.stack.push(Frame::new(kind, start)),
+            Event::Text(range) => {
+                let value = WikiText::new(&self.wiki_text.as_ref()[range.clone()]);
+                self.push_node(Node::Text {
+                    end: range.end,
+                    start: range.start,
+                    value,
+                });
+            }
+            Event::Warning(warning) => self.warnings.push(warning),
+        }
+    }
+
+    fn end(&mut self, kind: NodeKind, end: usize) {
+        let frame = self.stack.pop().expect("end without start");
+        assert_eq!(frame.kind, kind, "end of a different element than started");
+        let Frame {
+            attributes,
+            default,
+            kind,
+            name,
+            nodes,
+            parts,
+            start,
+        } = frame;
+        let node = match kind {
+            NodeKind::Attributes => return self.parent().attributes = Some(nodes),
+            NodeKind::Default => return self.parent().default = Some(nodes),
+            NodeKind::Name => return self.parent().name = Some(nodes),
+            NodeKind::DefinitionListItem { type_ } => {
+                return self.parent().parts.push(Part::DefinitionListItem(DefinitionListItem {
+                    end,
+                    nodes,
+                    start,
+                    type_,
+                }))
+            }
+            NodeKind::ListItem => return self.parent().parts.push(Part::ListItem(ListItem { end, nodes, start })),
+            NodeKind::TableCaption => {
+                return self.parent().parts.push(Part::TableCaption(TableCaption {
+                    attributes,
+                    content: nodes,
+                    end,
+                    start,
+                }))
+            }
+            NodeKind::TableCell { type_ } => {
+                return self.parent().parts.push(Part::TableCell(TableCell {
+                    attributes,
+                    content: nodes,
+                    end,
+                    start,
+                    type_,
+                }))
+            }
+            NodeKind::TableRow => {
+                return self.parent().parts.push(Part::TableRow(TableRow {
+                    attributes: attributes.unwrap_or_default(),
+                    cells: parts
+                        .into_iter()
+                        .map(|part| match part {
+                            Part::TableCell(cell) => cell,
+                            _ => panic!("table row containing something else than cells"),
+                        })
+                        .collect(),
+                    end,
+                    start,
+                }))
+            }
+            NodeKind::TemplateParameter => {
+                return self.parent().parts.push(Part::TemplateParameter(Parameter {
+                    end,
+                    name,
+                    start,
+                    value: nodes,
+                }))
+            }
+            NodeKind::Bold => Node::Bold { end, start },
+            NodeKind::BoldItalic => Node::BoldItalic { end, start },
+            NodeKind::Category { target } => Node::Category {
+                end,
+                ordinal: nodes,
+                start,
+                target,
+            },
+            NodeKind::CharacterEntity {
+                character,
+                second_character,
+            } => Node::CharacterEntity {
+                character,
+                end,
+                second_character,
+                start,
+            },
+            NodeKind::Comment => Node::Comment { end, start },
+            NodeKind::DefinitionList => Node::DefinitionList {
+                end,
+                items: parts
+                    .into_iter()
+                    .map(|part| match part {
+                        Part::DefinitionListItem(item) => item,
+                        _ => panic!("definition list containing something else than definition list items"),
+                    })
+                    .collect(),
+                start,
+            },
+            NodeKind::EndTag { name } => Node::EndTag { end, name, start },
+            NodeKind::Error { attempted, kind } => Node::Error {
+                attempted: *attempted,
+                end,
+                kind,
+                nodes,
+                start,
+            },
+            NodeKind::ExternalLink => Node::ExternalLink { end, nodes, start },
+            NodeKind::Formatted { style } => Node::Formatted {
+                end,
+                nodes,
+                start,
+                style,
+            },
+            NodeKind::Heading { level } => Node::Heading {
+                end,
+                level,
+                nodes,
+                start,
+            },
+            NodeKind::HorizontalDivider => Node::HorizontalDivider { end, start },
+            NodeKind::Image { target } => Node::Image {
+                end,
+                start,
+                target,
+                text: nodes,
+            },
+            NodeKind::InterlanguageLink { language, target } => Node::InterlanguageLink {
+                end,
+                language,
+                start,
+                target,
+            },
+            NodeKind::Italic => Node::Italic { end, start },
+            NodeKind::Link {
+                namespace,
+                target,
+                type_,
+            } => Node::Link {
+                end,
+                namespace,
+                start,
+                target,
+                text: nodes,
+                type_,
+            },
+            NodeKind::MagicWord => Node::MagicWord { end, start },
+            NodeKind::OrderedList => Node::OrderedList {
+                end,
+                items: list_items(parts),
+                start,
+            },
+            NodeKind::ParagraphBreak => Node::ParagraphBreak { end, start },
+            NodeKind::Parameter => Node::Parameter {
+                default,
+                end,
+                name: name.unwrap_or_default(),
+                start,
+            },
+            NodeKind::Preformatted => Node::Preformatted { end, nodes, start },
+            NodeKind::Redirect { target } => Node::Redirect { end, start, target },
+            NodeKind::StartTag { name } => Node::StartTag { end, name, start },
+            NodeKind::Table => {
+                let mut captions = vec![];
+                let mut rows = vec![];
+                for part in parts {
+                    match part {
+                        Part::TableCaption(caption) => captions.push(caption),
+                        Part::TableRow(row) => rows.push(row),
+                        _ => panic!("table containing something else than captions and rows"),
+                    }
+                }
+                Node::Table {
+                    attributes: attributes.unwrap_or_default(),
+                    captions,
+                    end,
+                    rows,
+                    start,
+                }
+            }
+            NodeKind::Tag { name } => Node::Tag {
+                end,
+                name,
+                nodes,
+                start,
+            },
+            NodeKind::Template => Node::Template {
+                end,
+                name: name.unwrap_or_default(),
+                parameters: parts
+                    .into_iter()
+                    .map(|part| match part {
+                        Part::TemplateParameter(parameter) => parameter,
+                        _ => panic!("template containing something else than parameters"),
+                    })
+                    .collect(),
+                start,
+            },
+            NodeKind::UnorderedList => Node::UnorderedList {
+                end,
+                items: list_items(parts),
+                start,
+            },
+        };
+        self.push_node(node);
+    }
+
+    fn parent(&mut self) -> &mut Frame {
+        self.stack.last_mut().expect("part of an element outside of the element")
+    }
+
+    fn push_node(&mut self, node: Node) {
+        match self.stack.last_mut() {
+            None => self.nodes.push(node),
+            Some(frame) => frame.nodes.push(node),
+        }
+    }
+}
+
+fn list_items(parts: Vec<Part>) -> Vec<ListItem> {
+    parts
+        .into_iter()
+        .map(|part| match part {
+            Part::ListItem(item) => item,
+            _ => panic!("list containing something else than list items"),
+        })
+        .collect()
+}
+
+fn push_group(events: &mut VecDeque<Event>, kind: NodeKind, position: usize, nodes: Vec<Node>) {
+    let start = nodes.first().map_or(position, Positioned::start);
+    let end = nodes.last().map_or(position, Positioned::end);
+    events.push_back(Event::Start(kind.clone(), start));
+    push_nodes(events, nodes);
+    events.push_back(Event::End(kind, end));
+}
+
+pub fn push_node(events: &mut VecDeque<Event>, node: Node) {
+    let (kind, start, end) = match node {
+        Node::Text { end, start, .. } => return events.push_back(Event::Text(start..end)),
+        Node::Bold { end, start } => (NodeKind::Bold, start, end),
+        Node::BoldItalic { end, start } => (NodeKind::BoldItalic, start, end),
+        Node::Category {
+            end,
+            ordinal,
+            start,
+            target,
+        } => return push_element(events, NodeKind::Category { target }, start, end, ordinal),
+        Node::CharacterEntity {
+            character,
+            end,
+            second_character,
+            start,
+        } => (
+            NodeKind::CharacterEntity {
+                character,
+                second_character,
+            },
+            start,
+            end,
+        ),
+        Node::Comment { end, start } => (NodeKind::Comment, start, end),
+        Node::DefinitionList { end, items, start } => {
+            events.push_back(Event::Start(NodeKind::DefinitionList, start));
+            for DefinitionListItem {
+                end,
+                nodes,
+                start,
+                type_,
+            } in items
+            {
+                push_element(events, NodeKind::DefinitionListItem { type_ }, start, end, nodes);
+            }
+            return events.push_back(Event::End(NodeKind::DefinitionList, end));
+        }
+        Node::EndTag { end, name, start } => (NodeKind::EndTag { name }, start, end),
+        Node::Error {
+            attempted,
+            end,
+            kind,
+            nodes,
+            start,
+        } => {
+            return push_element(
+                events,
+                NodeKind::Error {
+                    attempted: Box::new(attempted),
+                    kind,
+                },
+                start,
+                end,
+                nodes,
+            )
+        }
+        Node::ExternalLink { end, nodes, start } => {
+            return push_element(events, NodeKind::ExternalLink, start, end, nodes)
+        }
+        Node::Formatted {
+            end,
+            nodes,
+            start,
+            style,
+        } => return push_element(events, NodeKind::Formatted { style }, start, end, nodes),
+        Node::Heading {
+            end,
+            level,
+            nodes,
+            start,
+        } => return push_element(events, NodeKind::Heading { level }, start, end, nodes),
+        Node::HorizontalDivider { end, start } => (NodeKind::HorizontalDivider, start, end),
+        Node::Image {
+            end,
+            start,
+            target,
+            text,
+        } => return push_element(events, NodeKind::Image { target }, start, end, text),
+        Node::InterlanguageLink {
+            end,
+            language,
+            start,
+            target,
+        } => (NodeKind::InterlanguageLink { language, target }, start, end),
+        Node::Italic { end, start } => (NodeKind::Italic, start, end),
+        Node::Link {
+            end,
+            namespace,
+            start,
+            target,
+            text,
+            type_,
+        } => {
+            return push_element(
+                events,
+                NodeKind::Link {
+                    namespace,
+                    target,
+                    type_,
+                },
+                start,
+                end,
+                text,
+            )
+        }
+        Node::MagicWord { end, start } => (NodeKind::MagicWord, start, end),
+        Node::OrderedList { end, items, start } => {
+            return push_list(events, NodeKind::OrderedList, start, end, items)
+        }
+        Node::ParagraphBreak { end, start } => (NodeKind::ParagraphBreak, start, end),
+        Node::Parameter {
+            default,
+            end,
+            name,
+            start,
+        } => {
+            events.push_back(Event::Start(NodeKind::Parameter, start));
+            if !name.is_empty() {
+                push_group(events, NodeKind::Name, start, name);
+            }
+            if let Some(default) = default {
+                push_group(events, NodeKind::Default, start, default);
+            }
+            return events.push_back(Event::End(NodeKind::Parameter, end));
+        }
+        Node::Preformatted { end, nodes, start } => {
+            return push_element(events, NodeKind::Preformatted, start, end, nodes)
+        }
+        Node::Redirect { end, start, target } => (NodeKind::Redirect { target }, start, end),
+        Node::StartTag { end, name, start } => (NodeKind::StartTag { name }, start, end),
+        Node::Table {
+            attributes,
+            captions,
+            end,
+            rows,
+            start,
+        } => {
+            events.push_back(Event::Start(NodeKind::Table, start));
+            if !attributes.is_empty() {
+                push_group(events, NodeKind::Attributes, start, attributes);
+            }
+            for TableCaption {
+                attributes,
+                content,
+                end,
+                start,
+            } in captions
+            {
+                events.push_back(Event::Start(NodeKind::TableCaption, start));
+                if let Some(attributes) = attributes {
+                    push_group(events, NodeKind::Attributes, start, attributes);
+                }
+                push_nodes(events, content);
+                events.push_back(Event::End(NodeKind::TableCaption, end));
+            }
+            for TableRow {
+                attributes,
+                cells,
+                end,
+                start,
+            } in rows
+            {
+                events.push_back(Event::Start(NodeKind::TableRow, start));
+                if !attributes.is_empty() {
+                    push_group(events, NodeKind::Attributes, start, attributes);
+                }
+                for TableCell {
+                    attributes,
+                    content,
+                    end,
+                    start,
+                    type_,
+                } in cells
+                {
+                    events.push_back(Event::Start(NodeKind::TableCell { type_ }, start));
+                    if let Some(attributes) = attributes {
+                        push_group(events, NodeKind::Attributes, start, attributes);
+                    }
+                    push_nodes(events, content);
+                    events.push_back(Event::End(NodeKind::TableCell { type_ }, end));
+                }
+                events.push_back(Event::End(NodeKind::TableRow, end));
+            }
+            return events.push_back(Event::End(NodeKind::Table, end));
+        }
+        Node::Tag {
+            end,
+            name,
+            nodes,
+            start,
+        } => return push_element(events, NodeKind::Tag { name }, start, end, nodes),
+        Node::Template {
+            end,
+            name,
+            parameters,
+            start,
+        } => {
+            events.push_back(Event::Start(NodeKind::Template, start));
+            if !name.is_empty() {
+                push_group(events, NodeKind::Name, start, name);
+            }
+            for Parameter {
+                end,
+                name,
+                start,
+                value,
+            } in parameters
+            {
+                events.push_back(Event::Start(NodeKind::TemplateParameter, start));
+                if let Some(name) = name {
+                    push_group(events, NodeKind::Name, start, name);
+                }
+                push_nodes(events, value);
+                events.push_back(Event::End(NodeKind::TemplateParameter, end));
+            }
+            return events.push_back(Event::End(NodeKind::Template, end));
+        }
+        Node::UnorderedList { end, items, start } => {
+            return push_list(events, NodeKind::UnorderedList, start, end, items)
+        }
+    };
+    events.push_back(Event::Start(kind.clone(), start));
+    events.push_back(Event::End(kind, end));
+}
+
+fn push_element(events: &mut VecDeque<Event>, kind: NodeKind, start: usize, end: usize, nodes: Vec<Node>) {
+    events.push_back(Event::Start(kind.clone(), start));
+    push_nodes(events, nodes);
+    events.push_back(Event::End(kind, end));
+}
+
+fn push_list(events: &mut VecDeque<Event>, kind: NodeKind, start: usize, end: usize, items: Vec<ListItem>) {
+    events.push_back(Event::Start(kind.clone(), start));
+    for ListItem { end, nodes, start } in items {
+        push_element(events, NodeKind::ListItem, start, end, nodes);
+    }
+    events.push_back(Event::End(kind, end));
+}
+
+fn push_nodes(events: &mut VecDeque<Event>, nodes: Vec<Node>) {
+    for node in nodes {
+        push_node(events, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+    use std::collections::VecDeque;
+    use crate::{parse, Configuration, Event, NodeKind, Output, TreeBuilder, WikiText};
+
+    const TEXT: &str = "a ''b''\n\n{{c|d=[[e|f]]}}\n\n{{g\n\n{|\n|+ h\n|-\n! i || j\n|}\n*k\n;l:m\n{{{n|o}}}";
+
+    async fn events(text: &str) -> Vec<Event> {
+        Configuration::default().parse_events(WikiText::new(text)).collect().await
+    }
+
+    #[tokio::test]
+    async fn elements_and_text() {
+        let events = events(TEXT).await;
+        assert_eq!(
+            events[..6],
+            [
+                Event::Text(0..2),
+                Event::Start(NodeKind::Italic, 2),
+                Event::End(NodeKind::Italic, 4),
+                Event::Text(4..5),
+                Event::Start(NodeKind::Italic, 5),
+                Event::End(NodeKind::Italic, 7),
+            ]
+        );
+        assert!(events.contains(&Event::Start(NodeKind::Name, 11)));
+    }
+
+    #[tokio::test]
+    async fn emitted_when_pushed_at_top_level() {
+        let configuration = Configuration::default();
+        let mut state = parse::start(&configuration, WikiText::new("''a'' {{b|c\n\nd")).await;
+        state.events = Some(VecDeque::new());
+        assert_eq!(parse::parse_lines(&configuration, &mut state, None, parse::has_events).await, Some(true));
+        assert_eq!(state.scan_position, 2);
+        assert_eq!(
+            state.events.as_mut().unwrap().drain(..).collect::<Vec<_>>(),
+            [Event::Start(NodeKind::Italic, 0), Event::End(NodeKind::Italic, 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn buffered_while_open() {
+        // The unclosed template is rewound at the end of the text and parsed again as text, so nothing is emitted while it is open, and its warning is emitted after the table, which is the first node pushed at the top level after the rewind.
+        let events = events(TEXT).await;
+        let warning = events.iter().position(|event| matches!(event, Event::Warning(..))).unwrap();
+        assert_eq!(events[warning - 1], Event::End(NodeKind::Table, 53));
+        assert_eq!(events[warning + 1], Event::Start(NodeKind::UnorderedList, 54));
+        assert!(events[..warning].contains(&Event::Text(26..29)));
+    }
+
+    #[tokio::test]
+    async fn tree_builder() {
+        let configuration = Configuration::default();
+        let wiki_text = WikiText::new(TEXT);
+        let mut builder = TreeBuilder::new(wiki_text.clone());
+        for event in events(TEXT).await {
+            builder.push(event);
+        }
+        let output = builder.finish();
+        let mut state = parse::start(&configuration, wiki_text).await;
+        assert_eq!(parse::parse_lines(&configuration, &mut state, None, |_| false).await, Some(false));
+        let expected = Output {
+            nodes: state.nodes,
+            warnings: state.warnings,
+        };
+        assert_eq!(output.nodes, expected.nodes);
+        assert_eq!(output.warnings, expected.warnings);
+    }
+}
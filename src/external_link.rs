@@ -8,7 +8,7 @@ use crate::state::State;
 use crate::{Warning, Configuration, Node, WarningMessage};
 use crate::state::OpenNodeType;
 
-pub async fn parse_external_link_end<'a>(
+pub async fn parse_external_link_end(
     state: &mut State,
     start_position: usize,
     nodes: Vec<Node>,
@@ -18,7 +18,7 @@ pub async fn parse_external_link_end<'a>(
     state.scan_position += 1;
     state.flushed_position = state.scan_position;
     let nodes = std::mem::replace(&mut state.nodes, nodes);
-    state.nodes.push(Node::ExternalLink {
+    state.push_node(Node::ExternalLink {
         end: state.scan_position,
         nodes,
         start: start_position,
@@ -29,11 +29,14 @@ pub async fn parse_external_link_end_of_line(state: &mut State) {
     let end = state.scan_position;
     let open_node = state.stack.pop().unwrap();
     state.warnings.push(Warning {
+        context: None,
         end,
+        fix: None,
         message: WarningMessage::InvalidLinkSyntax,
+        related: vec![],
         start: open_node.start,
     });
-    state.rewind(open_node.nodes, open_node.start);
+    state.rewind(open_node);
     yield_now().await;
 }
 
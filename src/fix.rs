@@ -0,0 +1,113 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use crate::{TextEdit, WikiText};
+
+/// Suggested change of the wiki text resolving a warning.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Fix {
+    /// The edits making up the change, with positions in the wiki text the warning is from.
+    pub edits: Vec<TextEdit>,
+}
+
+/// Returns the wiki text with the fixes applied.
+///
+/// A fix is applied entirely or not at all. A fix is left out if any of its edits is not within the wiki text or not on character boundaries, or if it overlaps an edit of a fix earlier in the order given. Two insertions at the same position also overlap, since the order they would be made in is ambiguous.
+#[must_use]
+pub fn apply_fixes<'a>(wiki_text: &WikiText, fixes: impl IntoIterator<Item = &'a Fix>) -> WikiText {
+    let text = wiki_text.as_ref();
+    let mut edits: Vec<&TextEdit> = vec![];
+    for fix in fixes {
+        let is_applicable = fix.edits.iter().enumerate().all(|(index, edit)| {
+            edit.start <= edit.end
+                && text.get(edit.start..edit.end).is_some()
+                && !fix.edits[..index]
+                    .iter()
+                    .chain(edits.iter().copied())
+                    .any(|other| overlaps(edit, other))
+        });
+        if is_applicable {
+            edits.extend(&fix.edits);
+        }
+    }
+    edits.sort_by_key(|edit| (edit.start, edit.end));
+    let mut result = String::with_capacity(text.len());
+    let mut position = 0;
+    for edit in edits {
+        result += &text[position..edit.start];
+        result += &edit.replacement;
+        position = edit.end;
+    }
+    result += &text[position..];
+    WikiText::new(result)
+}
+
+pub fn deletion(start: usize, end: usize) -> Fix {
+    Fix {
+        edits: vec![TextEdit {
+            end,
+            replacement: String::new(),
+            start,
+        }],
+    }
+}
+
+pub fn insertion(position: usize, text: String) -> Fix {
+    Fix {
+        edits: vec![TextEdit {
+            end: position,
+            replacement: text,
+            start: position,
+        }],
+    }
+}
+
+/// Returns whether the edits replace a common part of the text, one edit inserts text within the range replaced by the other, or both insert text at the same position.
+fn overlaps(edit: &TextEdit, other: &TextEdit) -> bool {
+    edit.start < other.end && other.start < edit.end
+        || edit.start == edit.end && other.start == other.end && edit.start == other.start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deletion, insertion};
+    use crate::{apply_fixes, Configuration, WarningMessage, WikiText};
+
+    #[tokio::test]
+    async fn it_works() {
+        let configuration = Configuration::default();
+        for (wiki_text, message, fixed) in [
+            ("a\n\n\n\nb", WarningMessage::RepeatedEmptyLine, "a\n\nb"),
+            ("a\n\n \n\t\nb", WarningMessage::RepeatedEmptyLine, "a\n\nb"),
+            ("#REDIRECT [[a|b]]", WarningMessage::UselessTextInRedirect, "#REDIRECT [[a]]"),
+            ("a</ref>b", WarningMessage::UnexpectedEndTag, "ab"),
+            ("a}}b", WarningMessage::UnexpectedEndTag, "ab"),
+            ("a<ref>b", WarningMessage::MissingEndTagRewinding, "a<ref>b</ref>"),
+            ("{{a|<ref>b", WarningMessage::MissingEndTagRewinding, "{{a|<ref>b</ref>}}"),
+            (
+                "<ref>{{a|[http://example.com b</ref>",
+                WarningMessage::MissingEndTagRewinding,
+                "<ref>{{a|[http://example.com b]}}</ref>",
+            ),
+            ("<nowiki>a", WarningMessage::MissingEndTagRewinding, "<nowiki>a</nowiki>"),
+            ("<ref><nowiki>a</ref>", WarningMessage::MissingEndTagRewinding, "<ref><nowiki>a</nowiki></ref>"),
+        ] {
+            let wiki_text = WikiText::new(wiki_text);
+            let output = configuration.parse(wiki_text.clone()).await;
+            assert!(output.warnings.iter().any(|warning| warning.message == message));
+            let fixed_text = apply_fixes(&wiki_text, output.warnings.iter().filter_map(|warning| warning.fix.as_ref()));
+            assert_eq!(fixed_text.as_ref(), fixed);
+            let output = configuration.parse(fixed_text).await;
+            assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+        }
+        let fixes = [
+            deletion(0, 2),
+            deletion(1, 3),
+            insertion(3, "d".to_owned()),
+            insertion(3, "e".to_owned()),
+            deletion(2, 9),
+        ];
+        assert_eq!(apply_fixes(&WikiText::new("abc"), &fixes).as_ref(), "cd");
+    }
+}
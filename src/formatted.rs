@@ -0,0 +1,414 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use crate::{FormattingStyle, Node, Output, Positioned, Warning, WarningMessage, WikiText};
+
+struct Frame {
+    nodes: Vec<Node>,
+    start: usize,
+    style: FormattingStyle,
+}
+
+struct Line<'a> {
+    both: Option<(usize, Vec<Node>)>,
+    nodes: Vec<Node>,
+    stack: Vec<Frame>,
+    warnings: &'a mut Vec<Warning>,
+}
+
+impl Output {
+    /// Replaces the bold and italic toggles with balanced `Node::Formatted` elements, resolving them line by line the same way as the `doQuotes` algorithm of Mediawiki.
+    ///
+    /// Formatting that is still open at the end of a line is closed there, and a bold toggle may be taken as an apostrophe followed by an italic toggle, as Mediawiki does. A warning is added in both cases.
+    pub fn balance_formatting(&mut self) {
+        let nodes = std::mem::take(&mut self.nodes);
+        self.nodes = balance(nodes, &mut self.warnings);
+    }
+}
+
+impl<'a> Line<'a> {
+    fn close(&mut self, end: usize) {
+        let Frame { nodes, start, style } = self.stack.pop().unwrap();
+        self.push(Node::Formatted {
+            end,
+            nodes,
+            start,
+            style,
+        });
+    }
+
+    fn finish(mut self, end: usize) -> Vec<Node> {
+        if let Some(frame) = self.stack.first() {
+            self.warnings.push(Warning {
+                context: None,
+                end,
+                fix: None,
+                message: WarningMessage::UnbalancedQuotes,
+                related: vec![],
+                start: frame.start,
+            });
+        }
+        while !self.stack.is_empty() {
+            self.close(end);
+        }
+        if let Some((start, buffer)) = self.both.take() {
+            self.warnings.push(Warning {
+                context: None,
+                end,
+                fix: None,
+                message: WarningMessage::UnbalancedQuotes,
+                related: vec![],
+                start,
+            });
+            if !buffer.is_empty() {
+                self.resolve_both(start, buffer, FormattingStyle::Bold);
+                self.close(end);
+                self.close(end);
+            }
+        }
+        self.nodes
+    }
+
+    fn open(&mut self, start: usize, style: FormattingStyle) {
+        self.stack.push(Frame {
+            nodes: vec![],
+            start,
+            style,
+        });
+    }
+
+    fn push(&mut self, node: Node) {
+        match (&mut self.both, self.stack.last_mut()) {
+            (Some((_, buffer)), _) => buffer.push(node),
+            (None, Some(frame)) => frame.nodes.push(node),
+            (None, None) => self.nodes.push(node),
+        }
+    }
+
+    fn resolve_both(&mut self, start: usize, buffer: Vec<Node>, outer_style: FormattingStyle) {
+        self.open(start, outer_style);
+        self.open(
+            start,
+            match outer_style {
+                FormattingStyle::Bold => FormattingStyle::Italic,
+                FormattingStyle::Italic => FormattingStyle::Bold,
+            },
+        );
+        self.stack.last_mut().unwrap().nodes = buffer;
+    }
+
+    fn styles(&self) -> Vec<FormattingStyle> {
+        self.stack.iter().map(|frame| frame.style).collect()
+    }
+
+    fn toggle(&mut self, start: usize, end: usize, bold: bool, italic: bool) {
+        use FormattingStyle::{Bold, Italic};
+        if let Some((both_start, buffer)) = self.both.take() {
+            match (bold, italic) {
+                (false, _) => {
+                    self.resolve_both(both_start, buffer, Bold);
+                    self.close(end);
+                }
+                (true, false) => {
+                    self.resolve_both(both_start, buffer, Italic);
+                    self.close(end);
+                }
+                (true, true) => {
+                    self.resolve_both(both_start, buffer, Italic);
+                    self.close(end);
+                    self.close(end);
+                }
+            }
+            return;
+        }
+        match (bold, italic, self.styles().as_slice()) {
+            (false, true, [.., Italic]) => self.close(end),
+            (false, true, [Italic, Bold]) => {
+                self.close(start);
+                self.close(end);
+                self.open(end, Bold);
+            }
+            (false, true, _) => self.open(start, Italic),
+            (true, false, [.., Bold]) => self.close(end),
+            (true, false, [Bold, Italic]) => {
+                self.close(start);
+                self.close(end);
+                self.open(end, Italic);
+            }
+            (true, false, _) => self.open(start, Bold),
+            (_, _, [Bold]) => {
+                self.close(end);
+                self.open(start, Italic);
+            }
+            (_, _, [Italic]) => {
+                self.close(end);
+                self.open(start, Bold);
+            }
+            (_, _, []) => self.both = Some((start, vec![])),
+            _ => {
+                self.close(end);
+                self.close(end);
+            }
+        }
+    }
+}
+
+fn balance(mut nodes: Vec<Node>, warnings: &mut Vec<Warning>) -> Vec<Node> {
+    for node in &mut nodes {
+        balance_children(node, warnings);
+    }
+    if !nodes.iter().any(is_toggle) {
+        return nodes;
+    }
+    let mut result = vec![];
+    let mut line = vec![];
+    for node in split_lines(nodes) {
+        let is_block = is_block(&node);
+        if is_block || matches!(&node, Node::Text { value, .. } if value.as_ref().starts_with('\n')) {
+            result.extend(balance_line(std::mem::take(&mut line), warnings));
+        }
+        if is_block {
+            result.push(node);
+        } else {
+            line.push(node);
+        }
+    }
+    result.extend(balance_line(line, warnings));
+    merge_text(result)
+}
+
+fn balance_children(node: &mut Node, warnings: &mut Vec<Warning>) {
+    let mut balance_in_place = |nodes: &mut Vec<Node>| *nodes = balance(std::mem::take(nodes), warnings);
+    match node {
+        Node::DefinitionList { items, .. } => {
+            for item in items {
+                balance_in_place(&mut item.nodes);
+            }
+        }
+        Node::OrderedList { items, .. } | Node::UnorderedList { items, .. } => {
+            for item in items {
+                balance_in_place(&mut item.nodes);
+            }
+        }
+        Node::Error { nodes, .. }
+        | Node::ExternalLink { nodes, .. }
+        | Node::Heading { nodes, .. }
+        | Node::Preformatted { nodes, .. }
+        | Node::Tag { nodes, .. } => balance_in_place(nodes),
+        Node::Image { text, .. } | Node::Link { text, .. } => balance_in_place(text),
+        Node::Table { captions, rows, .. } => {
+            for caption in captions {
+                balance_in_place(&mut caption.content);
+            }
+            for row in rows {
+                for cell in &mut row.cells {
+                    balance_in_place(&mut cell.content);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn balance_line(mut nodes: Vec<Node>, warnings: &mut Vec<Warning>) -> Vec<Node> {
+    let end = match nodes.last() {
+        None => return nodes,
+        Some(node) => node.end(),
+    };
+    let (italic_count, bold_count) = nodes.iter().fold((0, 0), |(italic_count, bold_count), node| match node {
+        Node::Bold { .. } => (italic_count, bold_count + 1),
+        Node::BoldItalic { .. } => (italic_count + 1, bold_count + 1),
+        Node::Italic { .. } => (italic_count + 1, bold_count),
+        _ => (italic_count, bold_count),
+    });
+    if italic_count % 2 == 1 && bold_count % 2 == 1 {
+        if let Some(index) = find_bold_to_split(&nodes) {
+            let start = nodes[index].start();
+            let end = nodes[index].end();
+            warnings.push(Warning {
+                context: None,
+                end,
+                fix: None,
+                message: WarningMessage::UnbalancedQuotes,
+                related: vec![],
+                start,
+            });
+            nodes.splice(
+                index..=index,
+                [
+                    Node::Text {
+                        end: start + 1,
+                        start,
+                        value: WikiText::new("'"),
+                    },
+                    Node::Italic { end, start: start + 1 },
+                ],
+            );
+        }
+    }
+    let mut line = Line {
+        both: None,
+        nodes: vec![],
+        stack: vec![],
+        warnings,
+    };
+    for node in nodes {
+        match node {
+            Node::Bold { end, start } => line.toggle(start, end, true, false),
+            Node::BoldItalic { end, start } => line.toggle(start, end, true, true),
+            Node::Italic { end, start } => line.toggle(start, end, false, true),
+            _ => line.push(node),
+        }
+    }
+    line.finish(end)
+}
+
+fn find_bold_to_split(nodes: &[Node]) -> Option<usize> {
+    let mut first_multiletter_word = None;
+    let mut first_space = None;
+    for (index, node) in nodes.iter().enumerate() {
+        if !matches!(node, Node::Bold { .. }) {
+            continue;
+        }
+        // Mediawiki looks at the two bytes before the toggle, taking the same byte twice if there is only one.
+        let text = match index.checked_sub(1).map(|index| &nodes[index]) {
+            Some(Node::Text { value, .. }) => value.as_ref().rsplit('\n').next().unwrap().as_bytes(),
+            _ => b"",
+        };
+        let x1 = text.last();
+        let x2 = text.get(text.len().saturating_sub(2));
+        if x1 == Some(&b' ') {
+            first_space.get_or_insert(index);
+        } else if x2 == Some(&b' ') {
+            return Some(index);
+        } else {
+            first_multiletter_word.get_or_insert(index);
+        }
+    }
+    first_multiletter_word.or(first_space)
+}
+
+fn is_block(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::DefinitionList { .. }
+            | Node::Heading { .. }
+            | Node::HorizontalDivider { .. }
+            | Node::OrderedList { .. }
+            | Node::ParagraphBreak { .. }
+            | Node::Preformatted { .. }
+            | Node::Table { .. }
+            | Node::UnorderedList { .. }
+    )
+}
+
+fn is_toggle(node: &Node) -> bool {
+    matches!(node, Node::Bold { .. } | Node::BoldItalic { .. } | Node::Italic { .. })
+}
+
+fn merge_text(nodes: Vec<Node>) -> Vec<Node> {
+    let mut result: Vec<Node> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        if let (
+            Some(Node::Text {
+                end: previous_end,
+                value: previous_value,
+                ..
+            }),
+            Node::Text { end, start, value },
+        ) = (result.last_mut(), &node)
+        {
+            if *previous_end == *start {
+                *previous_value = WikiText::new(previous_value.as_ref().to_owned() + value.as_ref());
+                *previous_end = *end;
+                continue;
+            }
+        }
+        result.push(node);
+    }
+    result
+}
+
+fn split_lines(nodes: Vec<Node>) -> Vec<Node> {
+    let mut result = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        match node {
+            Node::Text { end, start, value }
+                if value.as_ref().match_indices('\n').any(|(position, _)| position > 0) =>
+            {
+                let text = value.as_ref();
+                let mut piece_start = 0;
+                for (position, _) in text.match_indices('\n').filter(|&(position, _)| position > 0) {
+                    result.push(Node::Text {
+                        end: start + position,
+                        start: start + piece_start,
+                        value: WikiText::new(&text[piece_start..position]),
+                    });
+                    piece_start = position;
+                }
+                result.push(Node::Text {
+                    end,
+                    start: start + piece_start,
+                    value: WikiText::new(&text[piece_start..]),
+                });
+            }
+            _ => result.push(node),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, FormattingStyle, Node, WarningMessage, WikiText};
+
+    async fn styles(wiki_text: &str) -> (String, usize) {
+        fn write(nodes: &[Node], result: &mut String) {
+            for node in nodes {
+                match node {
+                    Node::Formatted { nodes, style, .. } => {
+                        let tag = match style {
+                            FormattingStyle::Bold => "b",
+                            FormattingStyle::Italic => "i",
+                        };
+                        *result += &format!("<{}>", tag);
+                        write(nodes, result);
+                        *result += &format!("</{}>", tag);
+                    }
+                    Node::Text { value, .. } => *result += value.as_ref(),
+                    Node::ParagraphBreak { .. } => *result += "¶",
+                    _ => *result += "?",
+                }
+            }
+        }
+        let mut output = Configuration::default().parse(WikiText::new(wiki_text)).await;
+        output.balance_formatting();
+        let mut result = String::new();
+        write(&output.nodes, &mut result);
+        let warning_count = output
+            .warnings
+            .iter()
+            .filter(|warning| warning.message == WarningMessage::UnbalancedQuotes)
+            .count();
+        (result, warning_count)
+    }
+
+    #[tokio::test]
+    async fn it_works() {
+        for (wiki_text, expected, warning_count) in [
+            ("a''b''c", "a<i>b</i>c", 0),
+            ("a'''b'''c", "a<b>b</b>c", 0),
+            ("'''''a'''b''", "<i><b>a</b>b</i>", 0),
+            ("'''''a''b'''", "<b><i>a</i>b</b>", 0),
+            ("''a'''b''c'''", "<i>a<b>b</b></i><b>c</b>", 0),
+            ("a''''b''''", "a'<b>b'</b>", 0),
+            ("a''b\nc''d", "a<i>b</i>\nc<i>d</i>", 2),
+            ("l'''amour'' ''x''", "l'<i>amour</i> <i>x</i>", 1),
+            ("a '''b''c", "a '<i>b</i>c", 1),
+            ("'''''", "", 1),
+        ] {
+            assert_eq!(styles(wiki_text).await, (expected.to_owned(), warning_count), "{:?}", wiki_text);
+        }
+    }
+}
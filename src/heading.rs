@@ -15,11 +15,14 @@ pub async fn parse_heading_end(state: &mut State) {
     let open_node = state.stack.pop().unwrap();
     if state.get_byte(end_position - 1).await != Some(b'=') || end_position < open_node.start + 3 {
         state.warnings.push(Warning {
+            context: None,
             end: end_position,
+            fix: None,
             message: WarningMessage::InvalidHeadingSyntaxRewinding,
+            related: vec![],
             start: open_node.start,
         });
-        state.rewind(open_node.nodes, open_node.start);
+        state.rewind(open_node);
         return;
     }
     let start_level = match open_node.type_ {
@@ -36,8 +39,11 @@ pub async fn parse_heading_end(state: &mut State) {
     let position = state.skip_whitespace_backwards(end_position - end_level as usize).await;
     if end_level < start_level {
         state.warnings.push(Warning {
+            context: None,
             end: end_position,
+            fix: None,
             message: WarningMessage::UnexpectedHeadingLevelCorrecting,
+            related: vec![],
             start: open_node.start,
         });
         let inner_start_position = open_node.start + end_level as usize;
@@ -66,7 +72,7 @@ pub async fn parse_heading_end(state: &mut State) {
     }
     state.flush(position).await;
     let nodes = std::mem::replace(&mut state.nodes, open_node.nodes);
-    state.nodes.push(Node::Heading {
+    state.push_node(Node::Heading {
         end: end_position,
         level: end_level,
         nodes,
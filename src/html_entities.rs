@@ -6,7 +6,7 @@ pub const HTML_ENTITIES: &[(&str, char)] = &[
     ("AElig", '\u{c6}'),
     ("Aacute", '\u{c1}'),
     ("Acirc", '\u{c2}'),
-    ("Agrav", '\u{c0}'),
+    ("Agrave", '\u{c0}'),
     ("Alpha", '\u{391}'),
     ("Aring", '\u{c5}'),
     ("Atilde", '\u{c3}'),
@@ -0,0 +1,305 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use indexmap::IndexMap;
+use crate::references::{content, for_each_child, template_kind};
+use crate::{Configuration, Node, Output, Warning, WikiText};
+
+/// Value of a field of an infobox.
+#[derive(Debug, PartialEq)]
+pub struct FieldValue<'a> {
+    /// The targets of the links in the value, in order.
+    pub links: Vec<WikiText>,
+
+    /// The value as parsed, without leading and trailing comments and whitespace of a named field.
+    pub nodes: &'a [Node],
+
+    /// The value rendered as plain text, with markup, templates and extension tags left out and line breaks kept.
+    pub text: String,
+}
+
+/// Template presenting facts about the subject of a page, such as `{{Infobox person}}`.
+#[derive(Debug, PartialEq)]
+pub struct Infobox<'a> {
+    /// The byte position in the wiki text where the template ends.
+    pub end: usize,
+
+    /// The fields of the infobox in the order they are first given. Positional parameters are named by their position, starting from `1`.
+    pub fields: IndexMap<String, FieldValue<'a>>,
+
+    /// The kind of the template, as given by `TemplateName::kind`, such as `infobox person`.
+    pub kind: String,
+
+    /// The byte position in the wiki text where the template starts.
+    pub start: usize,
+}
+
+/// The infoboxes of a page.
+#[derive(Debug, Default)]
+pub struct Infoboxes<'a> {
+    /// The infoboxes in the order they appear in the wiki text.
+    pub infoboxes: Vec<Infobox<'a>>,
+
+    /// Warnings about fields given more than once.
+    pub warnings: Vec<Warning>,
+}
+
+impl Output {
+    /// Returns the templates whose names match the given pattern, such as `"infobox*"`, with their parameters as fields.
+    ///
+    /// The pattern is matched against the kind of each template, as given by `TemplateName::kind`. In the pattern, underscores match spaces and `*` matches any sequence of characters. Templates transcluding pages in other namespaces are left out, and templates inside other templates are included.
+    ///
+    /// Field values borrow from the given wiki text, which must be the wiki text this output was parsed from, with the given configuration.
+    #[must_use]
+    pub fn infoboxes<'a>(&'a self, configuration: &Configuration, wiki_text: &WikiText, pattern: &str) -> Infoboxes<'a> {
+        let mut infoboxes = Infoboxes::default();
+        collect(&self.nodes, configuration, wiki_text, &normalize_pattern(pattern), &mut infoboxes);
+        infoboxes
+    }
+}
+
+fn collect<'a>(
+    nodes: &'a [Node],
+    configuration: &Configuration,
+    wiki_text: &WikiText,
+    pattern: &str,
+    infoboxes: &mut Infoboxes<'a>,
+) {
+    for node in nodes {
+        if let Node::Template { end, start, .. } = node {
+            if let Some(kind) = template_kind(configuration, node).filter(|kind| matches_pattern(pattern, kind)) {
+                let mut fields = IndexMap::new();
+                for argument in node.arguments() {
+                    let name = argument.name().unwrap_or_else(|| match &argument.parameter.name {
+                        None => String::new(),
+                        Some(name) => content(wiki_text, name).to_owned(),
+                    });
+                    let mut links = vec![];
+                    collect_links(argument.nodes, &mut links);
+                    let mut text = String::new();
+                    render(argument.nodes, &mut text);
+                    let value = FieldValue {
+                        links,
+                        nodes: argument.nodes,
+                        text: text
+                            .lines()
+                            .map(str::trim)
+                            .filter(|line| !line.is_empty())
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    };
+                    fields.insert(name, value);
+                }
+                infoboxes.warnings.extend(node.duplicate_arguments());
+                infoboxes.infoboxes.push(Infobox {
+                    end: *end,
+                    fields,
+                    kind,
+                    start: *start,
+                });
+            }
+        }
+        for_each_child(node, |nodes| collect(nodes, configuration, wiki_text, pattern, infoboxes));
+    }
+}
+
+fn collect_links(nodes: &[Node], links: &mut Vec<WikiText>) {
+    for node in nodes {
+        if let Node::Link { target, .. } = node {
+            links.push(target.clone());
+        }
+        for_each_child(node, |nodes| collect_links(nodes, links));
+    }
+}
+
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap();
+    let Some(mut remainder) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = parts.collect::<Vec<_>>();
+    let Some(last) = parts.pop() else {
+        return remainder.is_empty();
+    };
+    for part in parts {
+        match remainder.find(part) {
+            None => return false,
+            Some(position) => remainder = &remainder[position + part.len()..],
+        }
+    }
+    remainder.ends_with(last)
+}
+
+/// Normalizes the pattern the same way as the kind of a template, so that it can be given like the name of a template.
+fn normalize_pattern(pattern: &str) -> String {
+    let pattern = pattern.replace('_', " ");
+    let mut characters = pattern.trim().chars();
+    match characters.next() {
+        None => String::new(),
+        Some(first_character) => first_character.to_lowercase().chain(characters).collect(),
+    }
+}
+
+fn render(nodes: &[Node], text: &mut String) {
+    for node in nodes {
+        match node {
+            Node::CharacterEntity {
+                character,
+                second_character,
+                ..
+            } => {
+                text.push(*character);
+                text.extend(second_character);
+            }
+            Node::DefinitionList { items, .. } => {
+                for item in items {
+                    render(&item.nodes, text);
+                    text.push('\n');
+                }
+            }
+            Node::ExternalLink { nodes, .. } => {
+                let mut link_text = String::new();
+                render(nodes, &mut link_text);
+                let link_text = link_text.trim();
+                text.push_str(match link_text.split_once(char::is_whitespace) {
+                    None => link_text,
+                    Some((_, display)) => display,
+                });
+            }
+            Node::Error { nodes, .. } | Node::Formatted { nodes, .. } => render(nodes, text),
+            Node::Link { target, text: nodes, .. } => {
+                if nodes.is_empty() {
+                    text.push_str(target.as_ref());
+                } else {
+                    render(nodes, text);
+                }
+            }
+            Node::OrderedList { items, .. } | Node::UnorderedList { items, .. } => {
+                for item in items {
+                    text.push('\n');
+                    render(&item.nodes, text);
+                }
+                text.push('\n');
+            }
+            Node::ParagraphBreak { .. } => text.push('\n'),
+            Node::StartTag { name, .. } if name.as_ref().eq_ignore_ascii_case("br") => text.push('\n'),
+            Node::Text { value, .. } => text.push_str(value.as_ref()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, Infoboxes, Output, WarningMessage, WikiText};
+
+    async fn parse(text: &str) -> (Output, WikiText) {
+        let wiki_text = WikiText::new(text);
+        (Configuration::default().parse(wiki_text.clone()).await, wiki_text)
+    }
+
+    fn infoboxes<'a>(output: &'a Output, wiki_text: &WikiText, pattern: &str) -> Infoboxes<'a> {
+        output.infoboxes(&Configuration::default(), wiki_text, pattern)
+    }
+
+    #[tokio::test]
+    async fn kinds_matching_pattern() {
+        let (output, wiki_text) = parse(concat!(
+            "{{Infobox_person}}{{Navbox|title=Ignored}}{{Template:Infobox ship|a=b}}{{subst:infobox_ship}}",
+            "{{User:Infobox x}}{{Foo|{{Infobox inner}}}}",
+        ))
+        .await;
+        let kinds = |pattern| {
+            infoboxes(&output, &wiki_text, pattern)
+                .infoboxes
+                .iter()
+                .map(|infobox| infobox.kind.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            kinds("Infobox_*"),
+            ["infobox person", "infobox ship", "infobox ship", "infobox inner"]
+        );
+        assert_eq!(kinds("infobox ship"), ["infobox ship", "infobox ship"]);
+        assert_eq!(kinds("*box*"), ["infobox person", "navbox", "infobox ship", "infobox ship", "infobox inner"]);
+        assert!(kinds("infobox").is_empty());
+    }
+
+    #[tokio::test]
+    async fn fields() {
+        let (output, wiki_text) = parse(concat!(
+            "{{Infobox person\n",
+            "| name = Ada Lovelace\n",
+            "| Positional\n",
+            "| birth_place = London\n",
+            "| name = Augusta Ada King\n",
+            "}}",
+        ))
+        .await;
+        let infoboxes = infoboxes(&output, &wiki_text, "infobox *");
+        assert_eq!(
+            infoboxes.infoboxes[0]
+                .fields
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.text.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("name", "Augusta Ada King"),
+                ("1", "Positional"),
+                ("birth_place", "London"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn rendered_text() {
+        let (output, wiki_text) = parse(concat!(
+            "{{Infobox person\n",
+            "| name = '''Ada''' Lovelace<ref>Cited</ref>\n",
+            "| birth_place = [[London]], [[England|UK]]<br />{{flag|UK}}\n",
+            "| website = [https://example.com Example] &amp; more\n",
+            "}}",
+        ))
+        .await;
+        let infoboxes = infoboxes(&output, &wiki_text, "infobox *");
+        assert_eq!(
+            infoboxes.infoboxes[0]
+                .fields
+                .values()
+                .map(|value| value.text.as_str())
+                .collect::<Vec<_>>(),
+            ["Ada Lovelace", "London, UK", "Example & more"]
+        );
+    }
+
+    #[tokio::test]
+    async fn links() {
+        let (output, wiki_text) =
+            parse("{{Infobox person|birth_place=[[London]], [[England|UK]] {{flag|[[United Kingdom]]}}}}").await;
+        let infoboxes = infoboxes(&output, &wiki_text, "infobox *");
+        assert_eq!(
+            infoboxes.infoboxes[0].fields["birth_place"]
+                .links
+                .iter()
+                .map(|target| target.as_ref())
+                .collect::<Vec<_>>(),
+            ["London", "England", "United Kingdom"]
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_fields() {
+        let (output, wiki_text) = parse("{{Infobox person|name=A|name=B}} {{Infobox ship|name=C}}").await;
+        let infoboxes = infoboxes(&output, &wiki_text, "infobox *");
+        assert_eq!(
+            infoboxes
+                .warnings
+                .iter()
+                .map(|warning| (warning.start, warning.message))
+                .collect::<Vec<_>>(),
+            [(24, WarningMessage::DuplicateTemplateParameter)]
+        );
+    }
+}
@@ -55,7 +55,7 @@
 //! - Text matching the configured set of magic words is parsed as magic words.
 //! - Extension tags have the same syntax as HTML tags, but are parsed differently. The configuration tells which tag names are to be treated as extension tags.
 //!
-//! The configuration can be seen by making a request to the [site info](https://www.mediawiki.org/wiki/API:Siteinfo) resource on the wiki. The utility [Fetch site configuration](https://github.com/portstrom/fetch_site_configuration) fetches the parts of the configuration needed for parsing pages in the wiki, and outputs Rust code for instantiating a parser with that configuration. Parse Wiki Text contains a default configuration that can be used for testing.
+//! The configuration can be seen by making a request to the [site info](https://www.mediawiki.org/wiki/API:Siteinfo) resource on the wiki. The utility [Fetch site configuration](https://github.com/portstrom/fetch_site_configuration) fetches the parts of the configuration needed for parsing pages in the wiki, and outputs Rust code for instantiating a parser with that configuration. A saved response from the site info resource can also be read at runtime with `Configuration::from_siteinfo_json`. A built configuration can be saved with `Configuration::to_snapshot` and quickly loaded again with `Configuration::from_snapshot`. Parse Wiki Text contains a default configuration that can be used for testing.
 //!
 //! # Limitations
 //!
@@ -78,14 +78,16 @@
 //! Reuse the same configuration when parsing multiple pages for efficiency.
 //!
 //! ```
-//! use parse_wiki_text::{Configuration, Node};
+//! use async_parse_wiki_text::{Configuration, Node, WikiText};
+//! # #[tokio::main]
+//! # async fn main() {
 //! let wiki_text = concat!(
 //!     "==Our values==\n",
 //!     "*Correctness\n",
 //!     "*Speed\n",
 //!     "*Ergonomics"
 //! );
-//! let result = Configuration::default().parse(wiki_text);
+//! let result = Configuration::default().parse(WikiText::new(wiki_text)).await;
 //! assert!(result.warnings.is_empty());
 //! # let mut found = false;
 //! for node in result.nodes {
@@ -93,7 +95,7 @@
 //!         println!("Our values are:");
 //!         for item in items {
 //!             println!("- {}", item.nodes.iter().map(|node| match node {
-//!                 Node::Text { value, .. } => value,
+//!                 Node::Text { value, .. } => value.as_ref(),
 //!                 _ => ""
 //!             }).collect::<String>());
 //! #           found = true;
@@ -101,54 +103,98 @@
 //!     }
 //! }
 //! # assert!(found);
+//! # }
 //! ```
 
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
+mod argument;
 mod bold_italic;
+mod bulk;
 mod case_folding_simple;
 mod character_entity;
 mod comment;
 mod configuration;
+mod csl;
 mod default;
+mod diagnostic;
+pub mod dump;
+mod event;
 mod external_link;
+mod fix;
+mod formatted;
 mod heading;
+mod html5_entities;
 mod html_entities;
+mod infobox;
 mod line;
 mod link;
+mod link_affix;
+pub mod lint;
 mod list;
 mod magic_word;
 mod parse;
 mod positioned;
+mod recovery;
 mod redirect;
+mod references;
+mod reparse;
+mod section;
+mod site_config;
+mod siteinfo;
+mod snapshot;
 mod state;
 mod table;
 mod tag;
 mod template;
+mod template_name;
 mod text;
+mod title;
 mod trie;
 mod warning;
 
-use configuration::Namespace;
-pub use configuration::ConfigurationSource;
+pub use argument::Argument;
+pub use bulk::{parse_many, PageStats, ParseAborted, ParseLimits};
+pub use configuration::{ConfigurationError, ConfigurationSource, Namespace, NamespaceSource};
+pub use csl::{CitationMapping, NameMapping};
+pub use diagnostic::{render_diagnostics, DiagnosticFormat};
+pub use event::{Event, NodeKind, TreeBuilder};
+pub use fix::{apply_fixes, Fix};
+pub use infobox::{FieldValue, Infobox, Infoboxes};
+use link_affix::LinkAffix;
+pub use references::{CitationTemplate, Citations, Reference};
+pub use reparse::TextEdit;
+pub use section::append_section;
+pub use site_config::{ConfigurationBuilder, SiteConfig, SiteNamespace};
+pub use siteinfo::SiteinfoError;
+pub use snapshot::SnapshotError;
 use std::collections::{HashMap, HashSet};
-use trie::Trie;
-pub use warning::{Warning, WarningMessage};
+pub use template_name::{TemplateAliases, TemplateName};
 pub use text::Text as WikiText;
 use text::Text;
+use trie::{Trie, TrieBuilder};
+pub use warning::{
+    LintMessage, RelatedSpan, RelatedSpanKind, Severity, Warning, WarningContext, WarningMessage,
+};
 
 /// Configuration for the parser.
 ///
 /// A configuration to correctly parse a real wiki can be created with `Configuration::new`. A configuration for testing and quick and dirty prototyping can be created with `Default::default`.
 pub struct Configuration {
-    character_entities: Trie<char>,
-    link_trail_character_set: HashSet<char>,
+    character_entities: Trie<(char, Option<char>)>,
+    interwiki_prefixes: Trie<InterwikiClass>,
+    link_prefix: LinkAffix,
+    link_trail: LinkAffix,
     magic_words: Trie<()>,
+    namespace_definitions: HashMap<Namespace, SiteNamespace>,
     namespaces: Trie<Namespace>,
+    parser_functions: HashSet<String>,
     protocols: Trie<()>,
     redirect_magic_words: Trie<()>,
+    site_config: SiteConfig,
     tag_name_map: HashMap<Text, TagClass>,
+    variables: HashSet<String>,
 }
 
 /// List item of a definition list.
@@ -177,6 +223,26 @@ pub enum DefinitionListItemType {
     Term,
 }
 
+/// Identifier for the style of formatted text.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FormattingStyle {
+    /// Bold text. Parsed from the code `'''`.
+    Bold,
+
+    /// Italic text. Parsed from the code `''`.
+    Italic,
+}
+
+/// Identifier for the type of a link.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LinkType {
+    /// Link to a page in another wiki. Parsed from a target starting with an interwiki prefix and `:`.
+    Interwiki,
+
+    /// Link to a page in the same wiki.
+    Internal,
+}
+
 /// List item of an ordered list or unordered list.
 #[derive(Debug, PartialEq)]
 pub struct ListItem {
@@ -226,7 +292,7 @@ pub enum Node {
         target: Text,
     },
 
-    /// Character entity. Parsed from code starting with `&` and ending with `;`.
+    /// Character entity or numeric character reference. Parsed from code starting with `&` and ending with `;`.
     CharacterEntity {
         /// The character represented.
         character: char,
@@ -234,6 +300,9 @@ pub enum Node {
         /// The byte position in the wiki text where the element ends.
         end: usize,
 
+        /// The second character represented, for the few entities that represent two characters.
+        second_character: Option<char>,
+
         /// The byte position in the wiki text where the element starts.
         start: usize,
     },
@@ -271,6 +340,24 @@ pub enum Node {
         start: usize,
     },
 
+    /// Construct that was attempted but is not well-formed, with the nodes the parser made when parsing the text of it again. Only made by `Configuration::parse_with_recovery`.
+    Error {
+        /// The kind of element that was attempted.
+        attempted: NodeKind,
+
+        /// The byte position in the wiki text where the element ends.
+        end: usize,
+
+        /// The warning telling why the attempt failed.
+        kind: WarningMessage,
+
+        /// The content of the element.
+        nodes: Vec<Node>,
+
+        /// The byte position in the wiki text where the element starts.
+        start: usize,
+    },
+
     /// External link. Parsed from code starting with `[` and a valid protocol.
     ExternalLink {
         /// The byte position in the wiki text where the element ends.
@@ -283,6 +370,21 @@ pub enum Node {
         start: usize,
     },
 
+    /// Bold or italic text. Made from pairs of bold and italic toggles by `Output::balance_formatting`.
+    Formatted {
+        /// The byte position in the wiki text where the element ends.
+        end: usize,
+
+        /// The content of the element.
+        nodes: Vec<Node>,
+
+        /// The byte position in the wiki text where the element starts.
+        start: usize,
+
+        /// The style of the formatting.
+        style: FormattingStyle,
+    },
+
     /// Heading. Parsed from code starting with `=` and ending with `=`.
     Heading {
         /// The byte position in the wiki text where the element ends.
@@ -322,6 +424,21 @@ pub enum Node {
         text: Vec<Node>,
     },
 
+    /// Link to the same page in a wiki in another language. Parsed from code starting with `[[`, a language prefix and `:`.
+    InterlanguageLink {
+        /// The byte position in the wiki text where the element ends.
+        end: usize,
+
+        /// The lowercase language prefix.
+        language: Text,
+
+        /// The byte position in the wiki text where the element starts.
+        start: usize,
+
+        /// The page in the other wiki.
+        target: Text,
+    },
+
     /// Toggle italic text. Parsed from the code `''`.
     Italic {
         /// The byte position in the wiki text where the element ends.
@@ -336,6 +453,9 @@ pub enum Node {
         /// The byte position in the wiki text where the element ends.
         end: usize,
 
+        /// The namespace of the target of the link.
+        namespace: Namespace,
+
         /// The byte position in the wiki text where the element starts.
         start: usize,
 
@@ -344,6 +464,9 @@ pub enum Node {
 
         /// The text to display for the link.
         text: Vec<Node>,
+
+        /// The type of link.
+        type_: LinkType,
     },
 
     /// Magic word. Parsed from the code `__`, a valid magic word and `__`.
@@ -535,6 +658,25 @@ pub trait Positioned {
     fn start(&self) -> usize;
 }
 
+/// Section of wiki text, as used for editing a part of a page.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Section {
+    /// The byte position in the wiki text where the section ends.
+    pub end: usize,
+
+    /// The level of the heading that starts the section, or 0 for the text before the first heading.
+    pub level: u8,
+
+    /// The byte position in the wiki text where the section starts.
+    pub start: usize,
+}
+
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+enum InterwikiClass {
+    Interwiki,
+    Language,
+}
+
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 enum TagClass {
     ExtensionTag,
@@ -601,3 +743,19 @@ pub struct TableRow {
     /// The byte position in the wiki text where the element starts.
     pub start: usize,
 }
+
+/// Title of a page, parsed from the target of a link.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Title {
+    /// The fragment identifying a section of the page, if any.
+    pub fragment: Option<String>,
+
+    /// The lowercase prefix of the wiki the page is in, if it's in another wiki.
+    pub interwiki: Option<String>,
+
+    /// The name of the page, without namespace prefix and with whitespace normalized.
+    pub name: String,
+
+    /// The namespace of the page. The main namespace for pages in other wikis.
+    pub namespace: Namespace,
+}
@@ -3,7 +3,7 @@
 // the file LICENSE at the top-level directory of this distribution.
 
 use crate::state::{State};
-use crate::{Node, Warning, WarningMessage};
+use crate::{fix, Node, Warning, WarningMessage};
 use crate::state::OpenNodeType;
 use crate::state::OpenNode;
 
@@ -29,9 +29,16 @@ pub async fn parse_beginning_of_line(state: &mut State, line_start_position: Opt
             }
             Some(b'\n') => {
                 if has_line_break {
+                    // The empty line has only whitespace since the previous line break.
+                    let line_start_position = state.wiki_text.as_ref()[..state.scan_position]
+                        .rfind('\n')
+                        .map_or(0, |position| position + 1);
                     state.warnings.push(Warning {
+                        context: None,
                         end: state.scan_position + 1,
+                        fix: Some(fix::deletion(line_start_position, state.scan_position + 1)),
                         message: WarningMessage::RepeatedEmptyLine,
+                        related: vec![],
                         start: state.scan_position,
                     });
                 }
@@ -86,7 +93,7 @@ pub async fn parse_beginning_of_line(state: &mut State, line_start_position: Opt
                     while state.get_byte(state.scan_position).await == Some(b'-') {
                         state.scan_position += 1;
                     }
-                    state.nodes.push(Node::HorizontalDivider {
+                    state.push_node(Node::HorizontalDivider {
                         end: state.scan_position,
                         start,
                     });
@@ -128,7 +135,7 @@ pub async fn parse_beginning_of_line(state: &mut State, line_start_position: Opt
         Some(position) => if has_line_break {
             let flush_position = state.skip_whitespace_backwards(position).await;
             state.flush(flush_position).await;
-            state.nodes.push(Node::ParagraphBreak {
+            state.push_node(Node::ParagraphBreak {
                 end: state.scan_position,
                 start: position,
             });
@@ -159,7 +166,7 @@ pub async fn parse_end_of_line(state: &mut State) {
             crate::list::parse_list_end_of_line(state).await;
         }
         Some(OpenNode {
-            type_: OpenNodeType::ExternalLink { .. },
+            type_: OpenNodeType::ExternalLink,
             ..
         }) => {
             crate::external_link::parse_external_link_end_of_line(state).await;
@@ -171,7 +178,7 @@ pub async fn parse_end_of_line(state: &mut State) {
             crate::heading::parse_heading_end(state).await;
         }
         Some(OpenNode {
-            type_: OpenNodeType::Link { .. },
+            type_: OpenNodeType::Link(..),
             ..
         })
         | Some(OpenNode {
@@ -237,7 +244,7 @@ async fn parse_preformatted_end_of_line(state: &mut State) {
     state.flush(position).await;
     state.scan_position += 1;
     let nodes = std::mem::replace(&mut state.nodes, open_node.nodes);
-    state.nodes.push(Node::Preformatted {
+    state.push_node(Node::Preformatted {
         end: state.scan_position,
         nodes,
         start: open_node.start,
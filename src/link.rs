@@ -6,19 +6,24 @@ use tokio::task::yield_now;
 
 use crate::text::Text;
 use crate::configuration::Namespace;
-use crate::state::{State};
-use crate::{Warning, Node, Configuration, WarningMessage};
+use crate::state::{Link, State};
+use crate::{Warning, Node, NodeKind, Configuration, Positioned, WarningMessage, InterwikiClass, LinkType};
 use crate::state::OpenNodeType;
 use crate::state::OpenNode;
 
-pub async fn parse_link_end<'a>(
+pub async fn parse_link_end(
     state: &mut State,
     configuration: &Configuration,
     start_position: usize,
     nodes: Vec<Node>,
-    namespace: Option<Namespace>,
-    target: Text,
+    open_link: Link,
 ) {
+    let Link {
+        interwiki,
+        leading_colon,
+        namespace,
+        target,
+    } = open_link;
     let inner_end_position = state.skip_whitespace_backwards(state.scan_position).await;
     state.flush(inner_end_position).await;
     state.scan_position += 2;
@@ -26,80 +31,115 @@ pub async fn parse_link_end<'a>(
     let mut text = std::mem::replace(&mut state.nodes, nodes);
     let end = state.scan_position;
     let start = start_position;
-    state.nodes.push(match namespace {
-        None => {
-            let mut trail_end_position = end;
-            for character in state.wiki_text.as_ref()[end..].chars() {
-                if !configuration.link_trail_character_set.contains(&character) {
-                    break;
-                }
-                trail_end_position += character.len_utf8();
-            }
+    let node = match (leading_colon, interwiki, namespace) {
+        (false, Some(InterwikiClass::Language), _) => {
+            interlanguage_link(configuration, end, start, &target)
+        }
+        (false, None, Namespace::CATEGORY) => Node::Category {
+            end,
+            ordinal: text,
+            start,
+            target,
+        },
+        (false, None, Namespace::FILE) => Node::Image {
+            end,
+            start,
+            target,
+            text,
+        },
+        _ => {
+            let trail_end_position =
+                end + configuration.link_trail.match_forwards(&state.wiki_text.as_ref()[end..]);
             if trail_end_position > end {
                 text.push(Node::Text {
                     end: trail_end_position,
                     start: end,
                     value: Text::new(&state.wiki_text.as_ref()[end..trail_end_position]),
                 });
+                state.flushed_position = trail_end_position;
+                state.scan_position = trail_end_position;
             }
+            let start = match take_link_prefix(state, configuration, start) {
+                None => start,
+                Some(prefix) => {
+                    let start = prefix.start();
+                    text.insert(0, prefix);
+                    start
+                }
+            };
+            check_target(state, configuration, start, trail_end_position, &target);
             Node::Link {
                 end: trail_end_position,
+                namespace,
                 start,
                 target,
                 text,
+                type_: link_type(interwiki),
             }
         }
-        Some(Namespace::Category) => Node::Category {
-            end,
-            ordinal: text,
-            start,
-            target,
-        },
-        Some(Namespace::File) => Node::Image {
-            end,
-            start,
-            target,
-            text,
-        },
-    });
+    };
+    state.push_node(node);
 }
 
 pub async fn parse_link_start(state: &mut State, configuration: &Configuration) {
     if match state.stack.last() {
         Some(OpenNode {
-            type_: OpenNodeType::Link { namespace, .. },
+            type_:
+                OpenNodeType::Link(Link {
+                    interwiki,
+                    leading_colon,
+                    namespace,
+                    ..
+                }),
             ..
-        }) => *namespace != Some(Namespace::File),
+        }) => *leading_colon || interwiki.is_some() || *namespace != Namespace::FILE,
         _ => false,
     } {
         let open_node = state.stack.pop().unwrap();
         state.warnings.push(Warning {
+            context: None,
             end: state.scan_position,
+            fix: None,
             message: WarningMessage::InvalidLinkSyntax,
+            related: vec![],
             start: open_node.start,
         });
-        state.rewind(open_node.nodes, open_node.start);
+        state.rewind(open_node);
         return;
     }
     let mut target_end_position;
     let target_start_position = state.skip_whitespace_forwards(state.scan_position + 2).await;
+    let leading_colon = state.get_byte(target_start_position).await == Some(b':');
+    let namespace_start_position = if leading_colon {
+        state.skip_whitespace_forwards(target_start_position + 1).await
+    } else {
+        target_start_position
+    };
     let namespace = match configuration
         .namespaces
-        .find(&state.wiki_text.as_ref()[target_start_position..])
+        .find(&state.wiki_text.as_ref()[namespace_start_position..])
     {
         Err(match_length) => {
-            target_end_position = match_length + target_start_position;
-            None
+            target_end_position = match_length + namespace_start_position;
+            Namespace::MAIN
         }
         Ok((match_length, namespace)) => {
-            target_end_position = match_length + target_start_position;
-            Some(namespace)
+            target_end_position = match_length + namespace_start_position;
+            namespace
         }
     };
+    let interwiki = match namespace {
+        Namespace::MAIN => configuration
+            .interwiki_prefixes
+            .find(&state.wiki_text.as_ref()[namespace_start_position..])
+            .ok()
+            .map(|(_, interwiki)| interwiki),
+        _ => None,
+    };
     loop {
         match state.get_byte(target_end_position).await {
             None | Some(b'\n') | Some(b'[') | Some(b'{') | Some(b'}') => {
-                parse_unexpected_end(state, target_end_position).await;
+                parse_unexpected_end(state, target_start_position, target_end_position, interwiki, namespace).await;
                 break;
             }
             Some(b']') => {
@@ -108,16 +148,20 @@ pub async fn parse_link_start(state: &mut State, configuration: &Configuration)
                     configuration,
                     target_start_position,
                     target_end_position,
+                    interwiki,
+                    leading_colon,
                     namespace,
                 ).await;
                 break;
             }
             Some(b'|') => {
                 state.push_open_node(
-                    OpenNodeType::Link {
+                    OpenNodeType::Link(Link {
+                        interwiki,
+                        leading_colon,
                         namespace,
-                        target: Text::new(&state.wiki_text.as_ref()[target_start_position..target_end_position]),
-                    },
+                        target: Text::new(state.wiki_text.as_ref()[target_start_position..target_end_position].trim_end()),
+                    }),
                     target_end_position + 1,
                 ).await;
                 break;
@@ -127,67 +171,124 @@ pub async fn parse_link_start(state: &mut State, configuration: &Configuration)
     }
 }
 
+fn check_target(
+    state: &mut State,
+    configuration: &Configuration,
+    start_position: usize,
+    end_position: usize,
+    target: &Text,
+) {
+    if configuration.parse_title(target.as_ref()).is_none() {
+        state.warnings.push(Warning {
+            context: None,
+            end: end_position,
+            fix: None,
+            message: WarningMessage::InvalidLinkTarget,
+            related: vec![],
+            start: start_position,
+        });
+    }
+}
+
+fn interlanguage_link(configuration: &Configuration, end: usize, start: usize, target: &Text) -> Node {
+    let target = target.as_ref();
+    let prefix_length = match configuration.interwiki_prefixes.find(target) {
+        Ok((match_length, _)) => match_length,
+        Err(_) => 0,
+    };
+    Node::InterlanguageLink {
+        end,
+        language: Text::new(target[..prefix_length.saturating_sub(1)].trim_end().to_lowercase()),
+        start,
+        target: Text::new(target[prefix_length..].trim_start()),
+    }
+}
+
+pub fn link_type(interwiki: Option<InterwikiClass>) -> LinkType {
+    match interwiki {
+        None => LinkType::Internal,
+        Some(_) => LinkType::Interwiki,
+    }
+}
+
 async fn parse_end(
     state: &mut State,
     configuration: &Configuration,
     target_start_position: usize,
     target_end_position: usize,
-    namespace: Option<Namespace>,
+    interwiki: Option<InterwikiClass>,
+    leading_colon: bool,
+    namespace: Namespace,
 ) {
     if state.get_byte(target_end_position + 1).await != Some(b']') {
-        parse_unexpected_end(state, target_end_position).await;
+        parse_unexpected_end(state, target_start_position, target_end_position, interwiki, namespace).await;
         return;
     }
     let start_position = state.scan_position;
     state.flush(start_position).await;
     let trail_start_position = target_end_position + 2;
     let mut trail_end_position = trail_start_position;
-    match namespace {
-        Some(Namespace::Category) => {
-            state.nodes.push(Node::Category {
+    match (leading_colon, interwiki, namespace) {
+        (false, Some(InterwikiClass::Language), _) => {
+            let target = Text::new(state.wiki_text.as_ref()[target_start_position..target_end_position].trim_end());
+            let node = interlanguage_link(configuration, trail_end_position, start_position, &target);
+            state.push_node(node);
+        }
+        (false, None, Namespace::CATEGORY) => {
+            state.push_node(Node::Category {
                 end: trail_end_position,
                 ordinal: vec![],
                 start: state.scan_position,
                 target: Text::new(state.wiki_text.as_ref()[target_start_position..target_end_position].trim_end()),
             });
         }
-        Some(Namespace::File) => {
-            state.nodes.push(Node::Image {
+        (false, None, Namespace::FILE) => {
+            state.push_node(Node::Image {
                 end: trail_end_position,
                 start: state.scan_position,
                 target: Text::new(state.wiki_text.as_ref()[target_start_position..target_end_position].trim_end()),
                 text: vec![],
             });
         }
-        None => {
-            for character in state.wiki_text.as_ref()[trail_start_position..].chars() {
-                if !configuration.link_trail_character_set.contains(&character) {
-                    break;
-                }
-                trail_end_position += character.len_utf8();
-            }
+        _ => {
+            trail_end_position += configuration
+                .link_trail
+                .match_forwards(&state.wiki_text.as_ref()[trail_start_position..]);
+            let text_start_position = if leading_colon {
+                target_start_position + 1
+            } else {
+                target_start_position
+            };
             let target_text = Node::Text {
                 end: target_end_position,
-                start: target_start_position,
-                value: Text::new(&state.wiki_text.as_ref()[target_start_position..target_end_position]),
+                start: text_start_position,
+                value: Text::new(&state.wiki_text.as_ref()[text_start_position..target_end_position]),
             };
-            let text = if trail_end_position > trail_start_position {
-                vec![
-                    target_text,
-                    Node::Text {
-                        end: trail_end_position,
-                        start: trail_start_position,
-                        value: Text::new(&state.wiki_text.as_ref()[trail_start_position..trail_end_position]),
-                    },
-                ]
-            } else {
-                vec![target_text]
+            let mut text = vec![target_text];
+            if trail_end_position > trail_start_position {
+                text.push(Node::Text {
+                    end: trail_end_position,
+                    start: trail_start_position,
+                    value: Text::new(&state.wiki_text.as_ref()[trail_start_position..trail_end_position]),
+                });
+            }
+            let start_position = match take_link_prefix(state, configuration, start_position) {
+                None => start_position,
+                Some(prefix) => {
+                    let start_position = prefix.start();
+                    text.insert(0, prefix);
+                    start_position
+                }
             };
-            state.nodes.push(Node::Link {
+            let target = Text::new(state.wiki_text.as_ref()[target_start_position..target_end_position].trim_end());
+            check_target(state, configuration, start_position, trail_end_position, &target);
+            state.push_node(Node::Link {
                 end: trail_end_position,
-                start: state.scan_position,
-                target: Text::new(state.wiki_text.as_ref()[target_start_position..target_end_position].trim_end()),
+                namespace,
+                start: start_position,
+                target,
                 text,
+                type_: link_type(interwiki),
             });
         }
     }
@@ -195,12 +296,115 @@ async fn parse_end(
     state.scan_position = trail_end_position;
 }
 
-async fn parse_unexpected_end(state: &mut State, target_end_position: usize) {
+fn take_link_prefix(state: &mut State, configuration: &Configuration, start_position: usize) -> Option<Node> {
+    let text_start_position = match state.nodes.last() {
+        Some(Node::Text { end, start, .. }) if *end == start_position => *start,
+        _ => return None,
+    };
+    let prefix_start_position = start_position
+        - configuration
+            .link_prefix
+            .match_backwards(&state.wiki_text.as_ref()[text_start_position..start_position]);
+    if prefix_start_position == start_position {
+        return None;
+    }
+    state.nodes.pop();
+    if prefix_start_position > text_start_position {
+        state.push_node(Node::Text {
+            end: prefix_start_position,
+            start: text_start_position,
+            value: Text::new(&state.wiki_text.as_ref()[text_start_position..prefix_start_position]),
+        });
+    }
+    Some(Node::Text {
+        end: start_position,
+        start: prefix_start_position,
+        value: Text::new(&state.wiki_text.as_ref()[prefix_start_position..start_position]),
+    })
+}
+
+async fn parse_unexpected_end(
+    state: &mut State,
+    target_start_position: usize,
+    target_end_position: usize,
+    interwiki: Option<InterwikiClass>,
+    namespace: Namespace,
+) {
     state.warnings.push(Warning {
+        context: None,
         end: target_end_position,
+        fix: None,
         message: WarningMessage::InvalidLinkSyntax,
+        related: vec![],
         start: state.scan_position,
     });
+    let wiki_text = state.wiki_text.clone();
+    let target = &wiki_text.as_ref()[target_start_position..target_end_position];
+    state.recover(state.scan_position, || NodeKind::Link {
+        namespace,
+        target: Text::new(target.trim_end()),
+        type_: link_type(interwiki),
+    });
     state.scan_position += 1;
     yield_now().await;
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, LinkType, Namespace, Node, WarningMessage, WikiText};
+
+    #[tokio::test]
+    async fn it_works() {
+        let configuration = Configuration::default();
+        let output = configuration
+            .parse(WikiText::new("[[de: Haus]]\n[[:de:Haus]]\n[[w:Foo|bar]]\n[[Foo]]"))
+            .await;
+        assert!(output.warnings.is_empty());
+        let mut nodes = output.nodes.iter().filter(|node| !matches!(node, Node::Text { .. }));
+        match nodes.next() {
+            Some(Node::InterlanguageLink {
+                end: 12,
+                language,
+                start: 0,
+                target,
+            }) => assert_eq!((language.as_ref(), target.as_ref()), ("de", "Haus")),
+            node => panic!("{:?}", node),
+        }
+        for (expected_target, expected_type) in [
+            (":de:Haus", LinkType::Interwiki),
+            ("w:Foo", LinkType::Interwiki),
+            ("Foo", LinkType::Internal),
+        ] {
+            match nodes.next() {
+                Some(Node::Link {
+                    namespace,
+                    target,
+                    type_,
+                    ..
+                }) => {
+                    assert_eq!(target.as_ref(), expected_target);
+                    assert_eq!(*namespace, Namespace::MAIN);
+                    assert_eq!(*type_, expected_type);
+                }
+                node => panic!("{:?}", node),
+            }
+        }
+        assert!(nodes.next().is_none());
+    }
+
+    #[tokio::test]
+    async fn target_with_comment() {
+        let configuration = Configuration::default();
+        let output = configuration
+            .parse(WikiText::new("[[Foo<!-- c -->]] [[Foo<nowiki/>|bar]] [[Foo<b>]]"))
+            .await;
+        assert_eq!(
+            output
+                .warnings
+                .iter()
+                .map(|warning| (warning.start, warning.message))
+                .collect::<Vec<_>>(),
+            [(39, WarningMessage::InvalidLinkTarget)]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Clone, Debug, Default)]
+struct Alternative {
+    not_followed_by: Option<CharacterSet>,
+    sequence: Vec<CharacterSet>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct CharacterSet {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+}
+
+/// Matcher for the text that is pulled into a link from before or after it.
+///
+/// Compiled from the subset of regular expressions Mediawiki uses for its `linktrail` and `linkprefixcharset` settings: a repetition of a character class or of alternatives made of characters, character classes and a final negative lookahead.
+#[derive(Clone, Debug, Default)]
+pub struct LinkAffix {
+    alternatives: Vec<Alternative>,
+    case_insensitive: bool,
+}
+
+impl LinkAffix {
+    /// Compiles a link prefix rule given as the body of a character class, the format of Mediawiki's `linkprefixcharset`.
+    pub fn prefix(charset: &str) -> Option<Self> {
+        if charset.is_empty() {
+            return Some(Default::default());
+        }
+        let set = parse_class(&mut charset.chars().peekable(), false)?;
+        Some(LinkAffix {
+            alternatives: vec![Alternative {
+                not_followed_by: None,
+                sequence: vec![set],
+            }],
+            case_insensitive: false,
+        })
+    }
+
+    /// Compiles a link trail rule given either as a regular expression in the format of Mediawiki's `linktrail` or as the characters that can appear in link trails.
+    pub fn trail(rule: &str) -> Option<Self> {
+        let pattern = match rule.strip_prefix('/') {
+            None => {
+                return Some(LinkAffix {
+                    alternatives: rule
+                        .chars()
+                        .map(|character| Alternative {
+                            not_followed_by: None,
+                            sequence: vec![CharacterSet::single(character)],
+                        })
+                        .collect(),
+                    case_insensitive: false,
+                })
+            }
+            Some(pattern) => pattern,
+        };
+        let pattern_end = pattern.rfind('/')?;
+        let case_insensitive = pattern[pattern_end + 1..].contains('i');
+        let body = pattern[..pattern_end]
+            .strip_prefix("^(")?
+            .strip_suffix(")(.*)$")?;
+        let mut iterator = body.chars().peekable();
+        let alternatives = match iterator.next() {
+            None => vec![],
+            Some('[') => vec![Alternative {
+                not_followed_by: None,
+                sequence: vec![parse_class(&mut iterator, true)?],
+            }],
+            Some('(') => {
+                if iterator.next()? != '?' || iterator.next()? != ':' {
+                    return None;
+                }
+                let mut alternatives = vec![parse_alternative(&mut iterator)?];
+                loop {
+                    match iterator.next()? {
+                        '|' => alternatives.push(parse_alternative(&mut iterator)?),
+                        ')' => break,
+                        _ => return None,
+                    }
+                }
+                alternatives
+            }
+            Some(_) => return None,
+        };
+        if !alternatives.is_empty() && !matches!(iterator.next(), Some('+') | Some('*')) {
+            return None;
+        }
+        if iterator.next().is_some() {
+            return None;
+        }
+        Some(LinkAffix {
+            alternatives,
+            case_insensitive,
+        })
+    }
+
+    /// Returns the length in bytes of the longest match at the end of the text.
+    pub fn match_backwards(&self, text: &str) -> usize {
+        let mut length = 0;
+        for character in text.chars().rev() {
+            if !self.alternatives.iter().any(|alternative| {
+                alternative.not_followed_by.is_none()
+                    && matches!(alternative.sequence.as_slice(), [set] if set.contains(character, self.case_insensitive))
+            }) {
+                break;
+            }
+            length += character.len_utf8();
+        }
+        length
+    }
+
+    /// Returns the length in bytes of the match at the start of the text.
+    pub fn match_forwards(&self, text: &str) -> usize {
+        let mut position = 0;
+        'a: loop {
+            for alternative in &self.alternatives {
+                if let Some(length) = alternative.match_at(&text[position..], self.case_insensitive) {
+                    position += length;
+                    continue 'a;
+                }
+            }
+            return position;
+        }
+    }
+}
+
+impl Alternative {
+    fn match_at(&self, text: &str, case_insensitive: bool) -> Option<usize> {
+        let mut iterator = text.chars();
+        let mut length = 0;
+        for set in &self.sequence {
+            let character = iterator.next()?;
+            if !set.contains(character, case_insensitive) {
+                return None;
+            }
+            length += character.len_utf8();
+        }
+        match (&self.not_followed_by, iterator.next()) {
+            (Some(set), Some(character)) if set.contains(character, case_insensitive) => None,
+            _ if length == 0 => None,
+            _ => Some(length),
+        }
+    }
+}
+
+impl CharacterSet {
+    fn contains(&self, character: char, case_insensitive: bool) -> bool {
+        let in_ranges = |character| self.ranges.iter().any(|&(first, last)| (first..=last).contains(&character));
+        let found = in_ranges(character)
+            || case_insensitive
+                && character
+                    .to_lowercase()
+                    .chain(character.to_uppercase())
+                    .any(in_ranges);
+        found != self.negated
+    }
+
+    fn single(character: char) -> Self {
+        CharacterSet {
+            negated: false,
+            ranges: vec![(character, character)],
+        }
+    }
+}
+
+fn parse_alternative(iterator: &mut Peekable<Chars>) -> Option<Alternative> {
+    let mut alternative = Alternative::default();
+    loop {
+        let set = match *iterator.peek()? {
+            '|' | ')' => break,
+            '(' => {
+                iterator.next();
+                if iterator.next()? != '?' || iterator.next()? != '!' {
+                    return None;
+                }
+                let set = parse_element(iterator)?;
+                if iterator.next()? != ')' || !matches!(iterator.peek(), Some('|') | Some(')')) {
+                    return None;
+                }
+                alternative.not_followed_by = Some(set);
+                break;
+            }
+            _ => parse_element(iterator)?,
+        };
+        alternative.sequence.push(set);
+    }
+    if alternative.sequence.is_empty() {
+        return None;
+    }
+    Some(alternative)
+}
+
+fn parse_class(iterator: &mut Peekable<Chars>, terminated: bool) -> Option<CharacterSet> {
+    let mut set = CharacterSet::default();
+    if terminated && iterator.peek() == Some(&'^') {
+        iterator.next();
+        set.negated = true;
+    }
+    let mut previous = None;
+    loop {
+        let character = match iterator.next() {
+            None if terminated => return None,
+            None => break,
+            Some(']') if terminated => break,
+            Some('-') if previous.is_some() && !matches!(iterator.peek(), None | Some(']')) => {
+                let first = previous.take().unwrap();
+                let last = match iterator.next()? {
+                    '\\' => parse_escape(iterator)?,
+                    last => last,
+                };
+                if last < first {
+                    return None;
+                }
+                set.ranges.push((first, last));
+                continue;
+            }
+            Some('\\') => parse_escape(iterator)?,
+            Some(character) => character,
+        };
+        if let Some(previous) = previous.replace(character) {
+            set.ranges.push((previous, previous));
+        }
+    }
+    if let Some(previous) = previous {
+        set.ranges.push((previous, previous));
+    }
+    Some(set)
+}
+
+fn parse_element(iterator: &mut Peekable<Chars>) -> Option<CharacterSet> {
+    match iterator.next()? {
+        '[' => parse_class(iterator, true),
+        '\\' => parse_escape(iterator).map(CharacterSet::single),
+        '.' => Some(CharacterSet {
+            negated: true,
+            ranges: vec![],
+        }),
+        '$' | '(' | ')' | '*' | '+' | '?' | '^' | '{' | '|' | '}' => None,
+        character => Some(CharacterSet::single(character)),
+    }
+}
+
+fn parse_escape(iterator: &mut Peekable<Chars>) -> Option<char> {
+    match iterator.next()? {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'x' => {
+            let mut digits = String::new();
+            if iterator.peek() == Some(&'{') {
+                iterator.next();
+                loop {
+                    match iterator.next()? {
+                        '}' => break,
+                        character => digits.push(character),
+                    }
+                }
+            } else {
+                digits.extend(iterator.by_ref().take(2));
+            }
+            char::from_u32(u32::from_str_radix(&digits, 16).ok()?)
+        }
+        character if character.is_ascii_alphanumeric() => None,
+        character => Some(character),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinkAffix;
+    use crate::{ConfigurationBuilder, Node, WikiText};
+
+    #[tokio::test]
+    async fn it_works() {
+        let trail = LinkAffix::trail("/^([a-zäöüß]+)(.*)$/sDu").unwrap();
+        assert_eq!(trail.match_forwards("ös foo"), 3);
+        assert_eq!(trail.match_forwards("Ös"), 0);
+        let trail = LinkAffix::trail("/^((?:[a-zàèéíòóúç·ïü]|'(?!'))+)(.*)$/sDu").unwrap();
+        assert_eq!(trail.match_forwards("l'ós''"), 5);
+        assert_eq!(LinkAffix::trail("/^()(.*)$/sD").unwrap().match_forwards("abc"), 0);
+        assert_eq!(LinkAffix::trail("abc").unwrap().match_forwards("cab d"), 3);
+        assert!(LinkAffix::trail("/^(\\w+)(.*)$/sD").is_none());
+        let prefix = LinkAffix::prefix("a-zA-Z\\x{80}-\\x{10ffff}").unwrap();
+        assert_eq!(prefix.match_backwards("foo الب"), 6);
+        assert_eq!(LinkAffix::prefix("").unwrap().match_backwards("abc"), 0);
+        let output = ConfigurationBuilder::new()
+            .link_prefix("a-z")
+            .link_trail("/^([a-z]+)(.*)$/sD")
+            .build()
+            .unwrap()
+            .parse(WikiText::new("x al[[foo]]s y"))
+            .await;
+        assert!(matches!(
+            output.nodes.as_slice(),
+            [
+                Node::Text { end: 2, start: 0, .. },
+                Node::Link { end: 12, start: 2, text, .. },
+                Node::Text { end: 14, start: 12, .. },
+            ] if text.len() == 3
+        ));
+    }
+}
@@ -0,0 +1,692 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+//! Style checks running over the nodes of a parsed page, reporting warnings in addition to those from the parser.
+//!
+//! Each check is a `Rule` with an identifier, a default severity and its own configuration. A `Linter` runs a set of rules and can change the severity of each rule. The warnings from the rules have the kind `WarningMessage::Lint`, so they can be handled and rendered the same way as warnings from the parser.
+//!
+//! ```
+//! use async_parse_wiki_text::lint::{Context, Linter};
+//! use async_parse_wiki_text::{Configuration, Severity, WikiText};
+//! # #[tokio::main]
+//! # async fn main() {
+//! let configuration = Configuration::default();
+//! let wiki_text = WikiText::new("== A ==\n==== B ====\n<center>c</center>");
+//! let output = configuration.parse(wiki_text.clone()).await;
+//! let linter = Linter::default().severity("heading-level-skip", Severity::Error);
+//! let warnings = linter.lint(&Context {
+//!     configuration: &configuration,
+//!     nodes: &output.nodes,
+//!     title: Some("Example"),
+//!     wiki_text: &wiki_text,
+//! });
+//! assert_eq!(warnings[0].message.code(), "heading-level-skip");
+//! assert_eq!(warnings[0].severity(), Severity::Error);
+//! assert_eq!(warnings[1].message.code(), "deprecated-tag");
+//! # }
+//! ```
+
+use std::collections::HashSet;
+use crate::references::{content, for_each_child};
+use crate::{
+    Configuration, Fix, LintMessage, Namespace, Node, RelatedSpan, Severity, Warning, WarningContext,
+    WarningMessage, WikiText,
+};
+
+/// Rule reporting a reference with nothing but a URL as its content, such as `<ref>http://example.com</ref>`.
+#[derive(Clone, Debug, Default)]
+pub struct BareUrlInReference;
+
+/// Rule reporting misused line break tags: the end tag `</br>`, and runs of line breaks used for vertical spacing.
+#[derive(Clone, Debug, Default)]
+pub struct BrMisuse;
+
+/// Page being checked by a rule.
+#[derive(Clone, Copy)]
+pub struct Context<'a> {
+    /// The configuration the page was parsed with.
+    pub configuration: &'a Configuration,
+
+    /// The nodes parsed from the wiki text.
+    pub nodes: &'a [Node],
+
+    /// The title of the page, if known.
+    pub title: Option<&'a str>,
+
+    /// The wiki text of the page.
+    pub wiki_text: &'a WikiText,
+}
+
+/// Rule reporting HTML tags that are deprecated.
+#[derive(Clone, Debug)]
+pub struct DeprecatedTags {
+    /// The lowercase names of the deprecated tags. By default `center`, `font`, `strike` and `tt`.
+    pub names: Vec<String>,
+}
+
+/// Rule reporting links to disambiguation pages.
+#[derive(Clone, Debug, Default)]
+pub struct DisambiguationLinks {
+    pages: HashSet<(Namespace, String)>,
+}
+
+/// Rule reporting template arguments overriding an earlier argument with the same name, as found by `Node::duplicate_arguments`.
+#[derive(Clone, Debug, Default)]
+pub struct DuplicateTemplateArgument;
+
+/// Rule reporting sections with no content, that is headings followed directly by a heading of the same or higher level or by the end of the page.
+#[derive(Clone, Debug, Default)]
+pub struct EmptySection;
+
+/// Problem found by a rule. Made into a warning by `Linter::lint`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Finding {
+    /// Structured information about the problem, if any.
+    pub context: Option<WarningContext>,
+
+    /// The byte position in the wiki text where the problem ends.
+    pub end: usize,
+
+    /// Suggested change of the wiki text resolving the problem, if any.
+    pub fix: Option<Fix>,
+
+    /// Other places in the wiki text related to the problem.
+    pub related: Vec<RelatedSpan>,
+
+    /// The byte position in the wiki text where the problem starts.
+    pub start: usize,
+}
+
+/// Rule reporting headings more than one level below the previous heading, such as a heading of level 4 following a heading of level 2. The title of the page counts as a heading of level 1.
+#[derive(Clone, Debug, Default)]
+pub struct HeadingLevelSkip;
+
+/// Set of rules to check pages with, each with the severity it's configured with.
+///
+/// `Linter::default` has all rules of this module that need no further configuration. `Linter::new` has no rules.
+pub struct Linter {
+    rules: Vec<(Box<dyn Rule>, Severity)>,
+}
+
+/// Check over the nodes of a page.
+pub trait Rule: Send + Sync {
+    /// Checks the page, adding the problems found to the findings.
+    fn check(&self, context: &Context, findings: &mut Vec<Finding>);
+
+    /// The severity of the warnings from the rule, unless changed with `Linter::severity`.
+    fn default_severity(&self) -> Severity;
+
+    /// Human-readable description of what the rule checks, used as the message of its warnings.
+    fn description(&self) -> &'static str;
+
+    /// The identifier of the rule, such as `heading-level-skip`, used as the code of its warnings.
+    fn id(&self) -> &'static str;
+}
+
+/// Rule reporting links to the page they are on. Needs the title of the page in the context.
+#[derive(Clone, Debug, Default)]
+pub struct SelfLink;
+
+impl Default for DeprecatedTags {
+    fn default() -> Self {
+        DeprecatedTags {
+            names: ["center", "font", "strike", "tt"].map(str::to_owned).to_vec(),
+        }
+    }
+}
+
+impl DisambiguationLinks {
+    /// Makes the rule with the titles of the disambiguation pages of the wiki. The titles are resolved with the configuration, so they are compared the same way as the targets of links.
+    #[must_use]
+    pub fn new<S: AsRef<str>>(configuration: &Configuration, pages: impl IntoIterator<Item = S>) -> Self {
+        DisambiguationLinks {
+            pages: pages
+                .into_iter()
+                .filter_map(|page| page_key(configuration, page.as_ref()))
+                .collect(),
+        }
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Linter::new()
+            .rule(BareUrlInReference)
+            .rule(BrMisuse)
+            .rule(DeprecatedTags::default())
+            .rule(DuplicateTemplateArgument)
+            .rule(EmptySection)
+            .rule(HeadingLevelSkip)
+            .rule(SelfLink)
+    }
+}
+
+impl Linter {
+    /// Makes a linter with no rules.
+    #[must_use]
+    pub fn new() -> Self {
+        Linter { rules: vec![] }
+    }
+
+    /// Removes the rule with the given identifier, if any.
+    #[must_use]
+    pub fn disable(mut self, id: &str) -> Self {
+        self.rules.retain(|(rule, _)| rule.id() != id);
+        self
+    }
+
+    /// Checks the page with all rules, returning the warnings ordered by position.
+    #[must_use]
+    pub fn lint(&self, context: &Context) -> Vec<Warning> {
+        let mut warnings = vec![];
+        for (rule, severity) in &self.rules {
+            let message = WarningMessage::Lint(LintMessage {
+                description: rule.description(),
+                rule: rule.id(),
+                severity: *severity,
+            });
+            let mut findings = vec![];
+            rule.check(context, &mut findings);
+            warnings.extend(findings.into_iter().map(|finding| Warning {
+                context: finding.context,
+                end: finding.end,
+                fix: finding.fix,
+                message,
+                related: finding.related,
+                start: finding.start,
+            }));
+        }
+        warnings.sort_by_key(|warning| warning.start);
+        warnings
+    }
+
+    /// Adds the rule with its default severity, replacing any rule with the same identifier.
+    #[must_use]
+    pub fn rule(self, rule: impl Rule + 'static) -> Self {
+        let mut linter = self.disable(rule.id());
+        let severity = rule.default_severity();
+        linter.rules.push((Box::new(rule), severity));
+        linter
+    }
+
+    /// Changes the severity of the rule with the given identifier, if any.
+    #[must_use]
+    pub fn severity(mut self, id: &str, severity: Severity) -> Self {
+        for (rule, rule_severity) in &mut self.rules {
+            if rule.id() == id {
+                *rule_severity = severity;
+            }
+        }
+        self
+    }
+}
+
+impl Rule for BareUrlInReference {
+    fn check(&self, context: &Context, findings: &mut Vec<Finding>) {
+        walk(context.nodes, &mut |nodes| {
+            for node in nodes {
+                if let Node::Tag {
+                    end,
+                    name,
+                    nodes,
+                    start,
+                } = node
+                {
+                    let text = content(context.wiki_text, nodes);
+                    let url = text
+                        .strip_prefix('[')
+                        .and_then(|text| text.strip_suffix(']'))
+                        .unwrap_or(text);
+                    if name.as_ref() == "ref"
+                        && context.configuration.protocols.find(url).is_ok()
+                        && !url.contains(char::is_whitespace)
+                    {
+                        findings.push(Finding {
+                            end: *end,
+                            start: *start,
+                            ..Finding::default()
+                        });
+                    }
+                }
+            }
+        });
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn description(&self) -> &'static str {
+        "A reference contains only a bare URL."
+    }
+
+    fn id(&self) -> &'static str {
+        "bare-url-in-reference"
+    }
+}
+
+impl Rule for BrMisuse {
+    fn check(&self, context: &Context, findings: &mut Vec<Finding>) {
+        walk(context.nodes, &mut |nodes| {
+            let mut run: Option<(usize, usize, usize)> = None;
+            for node in nodes {
+                match node {
+                    Node::EndTag { end, name, start } if name.as_ref() == "br" => findings.push(Finding {
+                        end: *end,
+                        start: *start,
+                        ..Finding::default()
+                    }),
+                    Node::StartTag { end, name, start } if name.as_ref() == "br" => {
+                        run = match run {
+                            None => Some((*start, *end, 1)),
+                            Some((run_start, _, count)) => Some((run_start, *end, count + 1)),
+                        };
+                        continue;
+                    }
+                    Node::Text { value, .. } if value.as_ref().trim().is_empty() => continue,
+                    _ => {}
+                }
+                push_run(findings, run.take());
+            }
+            push_run(findings, run);
+        });
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn description(&self) -> &'static str {
+        "A line break tag is misused, as an end tag or repeated for vertical spacing."
+    }
+
+    fn id(&self) -> &'static str {
+        "br-misuse"
+    }
+}
+
+impl Rule for DeprecatedTags {
+    fn check(&self, context: &Context, findings: &mut Vec<Finding>) {
+        walk(context.nodes, &mut |nodes| {
+            for node in nodes {
+                if let Node::StartTag { end, name, start } | Node::Tag { end, name, start, .. } = node {
+                    if self.names.iter().any(|deprecated| deprecated == name.as_ref()) {
+                        findings.push(Finding {
+                            context: Some(WarningContext::TagName {
+                                name: name.as_ref().to_owned(),
+                            }),
+                            end: *end,
+                            fix: None,
+                            related: vec![],
+                            start: *start,
+                        });
+                    }
+                }
+            }
+        });
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn description(&self) -> &'static str {
+        "A deprecated HTML tag is used."
+    }
+
+    fn id(&self) -> &'static str {
+        "deprecated-tag"
+    }
+}
+
+impl Rule for DisambiguationLinks {
+    fn check(&self, context: &Context, findings: &mut Vec<Finding>) {
+        walk(context.nodes, &mut |nodes| {
+            for node in nodes {
+                if let Node::Link { end, start, target, .. } = node {
+                    if page_key(context.configuration, target.as_ref())
+                        .is_some_and(|key| self.pages.contains(&key))
+                    {
+                        findings.push(Finding {
+                            end: *end,
+                            start: *start,
+                            ..Finding::default()
+                        });
+                    }
+                }
+            }
+        });
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn description(&self) -> &'static str {
+        "A link leads to a disambiguation page."
+    }
+
+    fn id(&self) -> &'static str {
+        "disambiguation-link"
+    }
+}
+
+impl Rule for DuplicateTemplateArgument {
+    fn check(&self, context: &Context, findings: &mut Vec<Finding>) {
+        walk(context.nodes, &mut |nodes| {
+            for node in nodes {
+                findings.extend(node.duplicate_arguments().into_iter().map(|warning| Finding {
+                    context: warning.context,
+                    end: warning.end,
+                    fix: warning.fix,
+                    related: warning.related,
+                    start: warning.start,
+                }));
+            }
+        });
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn description(&self) -> &'static str {
+        "A template argument is given more than once. The last value is used."
+    }
+
+    fn id(&self) -> &'static str {
+        "duplicate-template-argument"
+    }
+}
+
+impl Rule for EmptySection {
+    fn check(&self, context: &Context, findings: &mut Vec<Finding>) {
+        for (index, node) in context.nodes.iter().enumerate() {
+            let Node::Heading { end, level, start, .. } = node else {
+                continue;
+            };
+            let mut following = context.nodes[index + 1..].iter().skip_while(|node| match node {
+                Node::Category { .. }
+                | Node::Comment { .. }
+                | Node::InterlanguageLink { .. }
+                | Node::ParagraphBreak { .. } => true,
+                Node::Text { value, .. } => value.as_ref().trim().is_empty(),
+                _ => false,
+            });
+            if match following.next() {
+                None => true,
+                Some(Node::Heading { level: next_level, .. }) => next_level <= level,
+                Some(_) => false,
+            } {
+                findings.push(Finding {
+                    end: *end,
+                    start: *start,
+                    ..Finding::default()
+                });
+            }
+        }
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn description(&self) -> &'static str {
+        "A section has no content."
+    }
+
+    fn id(&self) -> &'static str {
+        "empty-section"
+    }
+}
+
+impl Rule for HeadingLevelSkip {
+    fn check(&self, context: &Context, findings: &mut Vec<Finding>) {
+        let mut previous_level = 1;
+        for node in context.nodes {
+            if let Node::Heading { end, level, start, .. } = node {
+                if *level > previous_level + 1 {
+                    findings.push(Finding {
+                        end: *end,
+                        start: *start,
+                        ..Finding::default()
+                    });
+                }
+                previous_level = *level;
+            }
+        }
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn description(&self) -> &'static str {
+        "A heading is more than one level below the previous heading."
+    }
+
+    fn id(&self) -> &'static str {
+        "heading-level-skip"
+    }
+}
+
+impl Rule for SelfLink {
+    fn check(&self, context: &Context, findings: &mut Vec<Finding>) {
+        let Some(page) = context
+            .title
+            .and_then(|title| page_key(context.configuration, title))
+        else {
+            return;
+        };
+        walk(context.nodes, &mut |nodes| {
+            for node in nodes {
+                if let Node::Link { end, start, target, .. } = node {
+                    // A link to a section of the same page is not a self-link.
+                    if context.configuration.parse_title(target.as_ref()).is_some_and(|title| {
+                        title.fragment.is_none()
+                            && title.interwiki.is_none()
+                            && title.namespace == page.0
+                            && title.name == page.1
+                    }) {
+                        findings.push(Finding {
+                            end: *end,
+                            start: *start,
+                            ..Finding::default()
+                        });
+                    }
+                }
+            }
+        });
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn description(&self) -> &'static str {
+        "A link leads to the page it's on."
+    }
+
+    fn id(&self) -> &'static str {
+        "self-link"
+    }
+}
+
+/// Returns the namespace and name of the page the target refers to, or `None` if the target is not a valid title or refers to another wiki.
+fn page_key(configuration: &Configuration, target: &str) -> Option<(Namespace, String)> {
+    let title = configuration.parse_title(target)?;
+    if title.interwiki.is_some() || title.name.is_empty() {
+        return None;
+    }
+    Some((title.namespace, title.name))
+}
+
+fn push_run(findings: &mut Vec<Finding>, run: Option<(usize, usize, usize)>) {
+    if let Some((start, end, count)) = run {
+        if count > 1 {
+            findings.push(Finding {
+                end,
+                start,
+                ..Finding::default()
+            });
+        }
+    }
+}
+
+fn walk<'a>(nodes: &'a [Node], function: &mut impl FnMut(&'a [Node])) {
+    function(nodes);
+    for node in nodes {
+        for_each_child(node, |nodes| walk(nodes, function));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Context, DeprecatedTags, DisambiguationLinks, Linter, Rule};
+    use crate::{Configuration, Severity, WikiText};
+
+    /// Returns the code, the text and the severity of each warning from linting the wiki text.
+    async fn lint(linter: &Linter, title: Option<&str>, wiki_text: &str) -> Vec<(&'static str, String, Severity)> {
+        let configuration = Configuration::default();
+        let wiki_text = WikiText::new(wiki_text);
+        let output = configuration.parse(wiki_text.clone()).await;
+        linter
+            .lint(&Context {
+                configuration: &configuration,
+                nodes: &output.nodes,
+                title,
+                wiki_text: &wiki_text,
+            })
+            .into_iter()
+            .map(|warning| (warning.message.code(), wiki_text.as_ref()[warning.start..warning.end].to_owned(), warning.severity()))
+            .collect()
+    }
+
+    async fn lint_rule(rule: impl Rule + 'static, title: Option<&str>, wiki_text: &str) -> Vec<String> {
+        lint(&Linter::new().rule(rule), title, wiki_text)
+            .await
+            .into_iter()
+            .map(|(_, text, _)| text)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn bare_url_in_reference() {
+        assert_eq!(
+            lint_rule(super::BareUrlInReference, None, "x<ref>http://example.com</ref><ref> [http://example.org] </ref>").await,
+            ["<ref>http://example.com</ref>", "<ref> [http://example.org] </ref>"]
+        );
+        assert!(lint_rule(super::BareUrlInReference, None, "<ref>[http://example.net c]</ref><ref>See http://example.com</ref>")
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn br_misuse() {
+        assert_eq!(lint_rule(super::BrMisuse, None, "e<br><br> <br>f</br>").await, ["<br><br> <br>", "</br>"]);
+        assert!(lint_rule(super::BrMisuse, None, "a<br>b<br>c").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn deprecated_tag() {
+        assert_eq!(
+            lint_rule(DeprecatedTags::default(), None, "<center>d</center><font>e</font><div>f</div>").await,
+            ["<center>", "<font>"]
+        );
+        assert_eq!(
+            lint_rule(DeprecatedTags { names: vec!["div".to_owned()] }, None, "<center>d</center><div>f</div>").await,
+            ["<div>"]
+        );
+    }
+
+    #[tokio::test]
+    async fn disambiguation_link() {
+        let rule = DisambiguationLinks::new(&Configuration::default(), ["J", "Help:K"]);
+        assert_eq!(
+            lint_rule(rule.clone(), None, "[[j]] [[J#x|y]] [[help:K]]").await,
+            ["[[j]]", "[[J#x|y]]", "[[help:K]]"]
+        );
+        assert!(lint_rule(rule, None, "[[K]] [[w:J]] [[Jay]]").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn duplicate_template_argument() {
+        let configuration = Configuration::default();
+        let wiki_text = WikiText::new("{{g|h=1|h=2}}");
+        let output = configuration.parse(wiki_text.clone()).await;
+        let warnings = Linter::new().rule(super::DuplicateTemplateArgument).lint(&Context {
+            configuration: &configuration,
+            nodes: &output.nodes,
+            title: None,
+            wiki_text: &wiki_text,
+        });
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(&wiki_text.as_ref()[warnings[0].start..warnings[0].end], "h=2");
+        assert_eq!(warnings[0].related[0].start, 4);
+        assert!(lint_rule(super::DuplicateTemplateArgument, None, "{{g|h=1|i=2}}").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn empty_section() {
+        assert_eq!(
+            lint_rule(super::EmptySection, None, "== A ==\n\n== B ==\nb\n== C ==\n<!-- c -->\n[[Category:C]]").await,
+            ["== A ==", "== C =="]
+        );
+        assert!(lint_rule(super::EmptySection, None, "== A ==\n=== B ===\nb").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn heading_level_skip() {
+        assert_eq!(
+            lint_rule(super::HeadingLevelSkip, None, "=== A ===\n== B ==\n==== C ====").await,
+            ["=== A ===", "==== C ===="]
+        );
+        assert!(lint_rule(super::HeadingLevelSkip, None, "== A ==\n=== B ===\n== C ==").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn self_link() {
+        let wiki_text = "[[Example]] [[example|e]] [[example#i]] [[Other]] [[w:Example]]";
+        assert_eq!(lint_rule(super::SelfLink, Some("Example"), wiki_text).await, ["[[Example]]", "[[example|e]]"]);
+        assert!(lint_rule(super::SelfLink, None, wiki_text).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn default_rules() {
+        assert_eq!(
+            lint(&Linter::default(), Some("Example"), "== A ==\n==== B ====\n<center>c</center> [[Example]]").await,
+            [
+                ("heading-level-skip", "==== B ====".to_owned(), Severity::Warning),
+                ("deprecated-tag", "<center>".to_owned(), Severity::Warning),
+                ("self-link", "[[Example]]".to_owned(), Severity::Info),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn severity() {
+        let linter = Linter::default().severity("heading-level-skip", Severity::Error);
+        assert_eq!(
+            lint(&linter, None, "== A ==\n==== B ====\nb").await,
+            [("heading-level-skip", "==== B ====".to_owned(), Severity::Error)]
+        );
+    }
+
+    #[tokio::test]
+    async fn disable() {
+        let linter = Linter::default().disable("heading-level-skip");
+        assert!(lint(&linter, None, "== A ==\n==== B ====\nb").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn replace_rule() {
+        let linter = Linter::default()
+            .severity("deprecated-tag", Severity::Error)
+            .rule(DeprecatedTags { names: vec!["div".to_owned()] });
+        assert_eq!(
+            lint(&linter, None, "<center>c</center><div>d</div>").await,
+            [("deprecated-tag", "<div>".to_owned(), Severity::Warning)]
+        );
+    }
+}
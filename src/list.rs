@@ -45,8 +45,11 @@ pub async fn parse_list_end_of_line(state: &mut State) {
             state.scan_position -= level - term_level;
             level = term_level;
             state.warnings.push(Warning {
+                context: None,
                 end: state.scan_position,
+                fix: None,
                 message: WarningMessage::DefinitionTermContinuation,
+                related: vec![],
                 start: state.scan_position - 1,
             });
         }
@@ -95,7 +98,7 @@ pub async fn parse_list_end_of_line(state: &mut State) {
             }
             _ => unreachable!(),
         };
-        state.nodes.push(node);
+        state.push_node(node);
     }
     state.flushed_position = state.scan_position;
     if parse_list_item_start(state).await {
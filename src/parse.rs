@@ -2,20 +2,55 @@
 // This is free software distributed under the terms specified in
 // the file LICENSE at the top-level directory of this distribution.
 
+use std::{collections::VecDeque, time::Instant};
 use crate::state::{State, OpenNode};
 use crate::state::OpenNodeType;
-use crate::{Warning, Output, Configuration, WarningMessage};
-use crate::{redirect, line, template, table, magic_word, link, character_entity, bold_italic, external_link, comment, tag, WikiText};
+use crate::{Warning, Output, Configuration, TreeBuilder, WarningMessage};
+use crate::{fix, redirect, line, template, table, magic_word, link, character_entity, bold_italic, external_link, comment, tag, WikiText};
+
+const DEADLINE_CHECK_INTERVAL: u32 = 1024;
 
 #[must_use]
-pub async fn parse<'a>(configuration: &Configuration, wiki_text: WikiText) -> Output {
+pub async fn parse(configuration: &Configuration, wiki_text: WikiText) -> Output {
+    match parse_until(configuration, wiki_text, None).await {
+        None => unreachable!("parsing without a deadline is never aborted"),
+        Some(output) => output,
+    }
+}
+
+pub async fn parse_until(configuration: &Configuration, wiki_text: WikiText, deadline: Option<Instant>) -> Option<Output> {
+    let mut state = start(configuration, wiki_text.clone()).await;
+    state.events = Some(VecDeque::new());
+    let mut builder = TreeBuilder::new(wiki_text);
+    loop {
+        let stopped = parse_lines(configuration, &mut state, deadline, has_events).await?;
+        if !stopped {
+            state.emit(state.nodes.len());
+        }
+        if let Some(events) = &mut state.events {
+            events.drain(..).for_each(|event| builder.push(event));
+        }
+        if !stopped {
+            return Some(builder.finish());
+        }
+    }
+}
+
+/// Returns whether the parser has emitted events that are not yet taken, to stop parsing until they are.
+pub fn has_events(state: &State) -> bool {
+    state.events.as_ref().is_some_and(|events| !events.is_empty())
+}
+
+pub async fn start(configuration: &Configuration, wiki_text: WikiText) -> State {
     let mut state = State {
+        events: None,
         flushed_position: 0,
         nodes: vec![],
+        recoveries: None,
         scan_position: 0,
         stack: vec![],
         warnings: vec![],
-        wiki_text: wiki_text.clone(),
+        wiki_text,
     };
     {
         let mut has_line_break = false;
@@ -25,8 +60,11 @@ pub async fn parse<'a>(configuration: &Configuration, wiki_text: WikiText) -> Ou
                 Some(b'\n') => {
                     if has_line_break {
                         state.warnings.push(Warning {
+                            context: None,
                             end: position + 1,
+                            fix: Some(fix::deletion(state.flushed_position, position + 1)),
                             message: WarningMessage::RepeatedEmptyLine,
+                            related: vec![],
                             start: position,
                         });
                     }
@@ -45,20 +83,45 @@ pub async fn parse<'a>(configuration: &Configuration, wiki_text: WikiText) -> Ou
         }
     }
     line::parse_beginning_of_line(&mut state, None).await;
+    state
+}
+
+pub async fn parse_lines(
+    configuration: &Configuration,
+    state: &mut State,
+    deadline: Option<Instant>,
+    mut stop: impl FnMut(&State) -> bool,
+) -> Option<bool> {
+    let mut iterations: u32 = 0;
     loop {
+        if stop(state) {
+            return Some(true);
+        }
+        if let Some(deadline) = deadline {
+            if iterations.is_multiple_of(DEADLINE_CHECK_INTERVAL) && Instant::now() >= deadline {
+                return None;
+            }
+            iterations = iterations.wrapping_add(1);
+        }
         match state.get_byte(state.scan_position).await {
             None => {
-                line::parse_end_of_line(&mut state).await;
+                line::parse_end_of_line(state).await;
                 if state.scan_position < state.wiki_text.len() {
                     continue;
                 }
-                if let Some(OpenNode { nodes, start, .. }) = state.stack.pop() {
+                let fix = state
+                    .closing_code(0)
+                    .map(|code| fix::insertion(state.wiki_text.len(), code));
+                if let Some(open_node) = state.stack.pop() {
                     state.warnings.push(Warning {
+                        context: state.open_node_context(&open_node),
                         end: state.scan_position,
+                        fix,
                         message: WarningMessage::MissingEndTagRewinding,
-                        start,
+                        related: vec![state.open_node_start_span(&open_node)],
+                        start: open_node.start,
                     });
-                    state.rewind(nodes, start);
+                    state.rewind(open_node);
                 } else {
                     break;
                 }
@@ -69,41 +132,42 @@ pub async fn parse<'a>(configuration: &Configuration, wiki_text: WikiText) -> Ou
             | Some(24) | Some(25) | Some(26) | Some(27) | Some(28) | Some(29) | Some(30)
             | Some(31) | Some(127) => {
                 state.warnings.push(Warning {
+                    context: None,
                     end: state.scan_position + 1,
+                    fix: None,
                     message: WarningMessage::InvalidCharacter,
+                    related: vec![],
                     start: state.scan_position,
                 });
                 state.scan_position += 1;
             }
             Some(b'\n') => {
-                line::parse_end_of_line(&mut state).await;
+                line::parse_end_of_line(state).await;
             }
             Some(b'!') if state.get_byte(state.scan_position + 1) .await== Some(b'!') => {
-                table::parse_heading_cell(&mut state).await;
+                table::parse_heading_cell(state).await;
+            }
+            Some(b'&') => character_entity::parse_character_entity(state, configuration).await,
+            Some(b'\'') if state.get_byte(state.scan_position + 1).await == Some(b'\'') => {
+                bold_italic::parse_bold_italic(state).await;
             }
-            Some(b'&') => character_entity::parse_character_entity(&mut state, configuration).await,
-            Some(b'\'') => if state.get_byte(state.scan_position + 1) .await== Some(b'\'') {
-                bold_italic::parse_bold_italic(&mut state).await;
-            } else {
-                state.scan_position += 1;
-            },
             Some(b'<') => match state.get_byte(state.scan_position + 1) .await{
                 Some(b'!')
                     if state.get_byte(state.scan_position + 2).await == Some(b'-')
                         && state.get_byte(state.scan_position + 3).await == Some(b'-') =>
                 {
-                    comment::parse_comment(&mut state).await
+                    comment::parse_comment(state).await
                 }
-                Some(b'/') => tag::parse_end_tag(&mut state, configuration).await,
-                _ => tag::parse_start_tag(&mut state, configuration).await,
+                Some(b'/') => tag::parse_end_tag(state, configuration).await,
+                _ => tag::parse_start_tag(state, configuration).await,
             },
             Some(b'=') => {
-                template::parse_parameter_name_end(&mut state).await;
+                template::parse_parameter_name_end(state).await;
             }
             Some(b'[') => if state.get_byte(state.scan_position + 1) .await== Some(b'[') {
-                link::parse_link_start(&mut state, configuration).await;
+                link::parse_link_start(state, configuration).await;
             } else {
-                external_link::parse_external_link_start(&mut state, configuration).await;
+                external_link::parse_external_link_start(state, configuration).await;
             },
             Some(b']') => match state.stack.pop() {
                 None => state.scan_position += 1,
@@ -112,27 +176,20 @@ pub async fn parse<'a>(configuration: &Configuration, wiki_text: WikiText) -> Ou
                     start,
                     type_: OpenNodeType::ExternalLink,
                 }) => {
-                    external_link::parse_external_link_end(&mut state, start, nodes).await;
+                    external_link::parse_external_link_end(state, start, nodes).await;
                 }
                 Some(OpenNode {
                     nodes,
                     start,
-                    type_: OpenNodeType::Link { namespace, target },
+                    type_: OpenNodeType::Link(open_link),
                 }) => if state.get_byte(state.scan_position + 1) .await== Some(b']') {
-                    link::parse_link_end(
-                        &mut state,
-                        configuration,
-                        start,
-                        nodes,
-                        namespace,
-                        target,
-                    ).await;
+                    link::parse_link_end(state, configuration, start, nodes, open_link).await;
                 } else {
                     state.scan_position += 1;
                     state.stack.push(OpenNode {
                         nodes,
                         start,
-                        type_: OpenNodeType::Link { namespace, target },
+                        type_: OpenNodeType::Link(open_link),
                     });
                 },
                 Some(open_node) => {
@@ -140,51 +197,42 @@ pub async fn parse<'a>(configuration: &Configuration, wiki_text: WikiText) -> Ou
                     state.stack.push(open_node);
                 }
             },
-            Some(b'_') => if state.get_byte(state.scan_position + 1) .await== Some(b'_') {
-                magic_word::parse_magic_word(&mut state, configuration).await;
-            } else {
-                state.scan_position += 1;
-            },
-            Some(b'{') => if state.get_byte(state.scan_position + 1) .await== Some(b'{') {
-                template::parse_template_start(&mut state).await;
-            } else {
-                state.scan_position += 1;
-            },
+            Some(b'_') if state.get_byte(state.scan_position + 1).await == Some(b'_') => {
+                magic_word::parse_magic_word(state, configuration).await;
+            }
+            Some(b'{') if state.get_byte(state.scan_position + 1).await == Some(b'{') => {
+                template::parse_template_start(state).await;
+            }
             Some(b'|') => match state.stack.last_mut() {
                 Some(OpenNode {
                     type_: OpenNodeType::Parameter { default: None, .. },
                     ..
                 }) => {
-                    template::parse_parameter_separator(&mut state).await;
+                    template::parse_parameter_separator(state).await;
                 }
                 Some(OpenNode {
                     type_: OpenNodeType::Table(..),
                     ..
                 }) => {
-                    table::parse_inline_token(&mut state).await;
+                    table::parse_inline_token(state).await;
                 }
                 Some(OpenNode {
                     type_: OpenNodeType::Template { .. },
                     ..
                 }) => {
-                    template::parse_template_separator(&mut state).await;
+                    template::parse_template_separator(state).await;
                 }
                 _ => state.scan_position += 1,
             },
-            Some(b'}') => if state.get_byte(state.scan_position + 1) .await== Some(b'}') {
-                template::parse_template_end(&mut state).await;
-            } else {
-                state.scan_position += 1;
-            },
+            Some(b'}') if state.get_byte(state.scan_position + 1).await == Some(b'}') => {
+                template::parse_template_end(state).await;
+            }
             _ => {
                 state.scan_position += 1;
             }
         }
     }
-    let end_position = state.skip_whitespace_backwards(wiki_text.len()).await;
+    let end_position = state.skip_whitespace_backwards(state.wiki_text.len()).await;
     state.flush(end_position).await;
-    Output {
-        nodes: state.nodes,
-        warnings: state.warnings,
-    }
+    Some(false)
 }
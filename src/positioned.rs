@@ -22,6 +22,7 @@ macro_rules! impl_positioned {
 impl_positioned!(DefinitionListItem);
 impl_positioned!(ListItem);
 impl_positioned!(Parameter);
+impl_positioned!(Section);
 impl_positioned!(TableCaption);
 impl_positioned!(TableCell);
 impl_positioned!(TableRow);
@@ -36,10 +37,13 @@ impl Positioned for Node {
             Node::Comment { end, .. } => end,
             Node::DefinitionList { end, .. } => end,
             Node::EndTag { end, .. } => end,
+            Node::Error { end, .. } => end,
             Node::ExternalLink { end, .. } => end,
+            Node::Formatted { end, .. } => end,
             Node::Heading { end, .. } => end,
             Node::HorizontalDivider { end, .. } => end,
             Node::Image { end, .. } => end,
+            Node::InterlanguageLink { end, .. } => end,
             Node::Italic { end, .. } => end,
             Node::Link { end, .. } => end,
             Node::MagicWord { end, .. } => end,
@@ -66,10 +70,13 @@ impl Positioned for Node {
             Node::Comment { start, .. } => start,
             Node::DefinitionList { start, .. } => start,
             Node::EndTag { start, .. } => start,
+            Node::Error { start, .. } => start,
             Node::ExternalLink { start, .. } => start,
+            Node::Formatted { start, .. } => start,
             Node::Heading { start, .. } => start,
             Node::HorizontalDivider { start, .. } => start,
             Node::Image { start, .. } => start,
+            Node::InterlanguageLink { start, .. } => start,
             Node::Italic { start, .. } => start,
             Node::Link { start, .. } => start,
             Node::MagicWord { start, .. } => start,
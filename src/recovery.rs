@@ -0,0 +1,198 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use std::cmp::Reverse;
+use crate::state::Recovery;
+use crate::{parse, Configuration, Node, Output, Positioned, WikiText};
+
+impl Configuration {
+    /// Parses wiki text into structured data, keeping a record of the constructs that are not well-formed.
+    ///
+    /// When the parser finds that a construct such as a link or template is not well-formed, it rewinds and parses the text of it again as text and other elements. Here the nodes parsed again from the start of the construct to where the parser found it not to be well-formed are wrapped in `Node::Error`, so that the broken construct can be found next to the warning telling why. A range that would cross the boundary of an element is cut at the end of the element. Otherwise the output is the same as from `Configuration::parse`.
+    pub async fn parse_with_recovery(&self, wiki_text: WikiText) -> Output {
+        let mut state = parse::start(self, wiki_text).await;
+        state.recoveries = Some(vec![]);
+        if parse::parse_lines(self, &mut state, None, |_| false).await.is_none() {
+            unreachable!("parsing without a deadline is never aborted");
+        }
+        let mut recoveries = state.recoveries.take().unwrap_or_default();
+        recoveries.sort_by_key(|recovery| (recovery.start, Reverse(recovery.end)));
+        for recovery in recoveries {
+            wrap(&mut state.nodes, recovery, &state.wiki_text);
+        }
+        Output {
+            nodes: state.nodes,
+            warnings: state.warnings,
+        }
+    }
+}
+
+fn child_lists(node: &mut Node) -> Vec<&mut Vec<Node>> {
+    match node {
+        Node::Category { ordinal, .. } => vec![ordinal],
+        Node::DefinitionList { items, .. } => items.iter_mut().map(|item| &mut item.nodes).collect(),
+        Node::Error { nodes, .. }
+        | Node::ExternalLink { nodes, .. }
+        | Node::Formatted { nodes, .. }
+        | Node::Heading { nodes, .. }
+        | Node::Preformatted { nodes, .. }
+        | Node::Tag { nodes, .. } => vec![nodes],
+        Node::Image { text, .. } | Node::Link { text, .. } => vec![text],
+        Node::OrderedList { items, .. } | Node::UnorderedList { items, .. } => {
+            items.iter_mut().map(|item| &mut item.nodes).collect()
+        }
+        Node::Parameter { default, name, .. } => std::iter::once(name).chain(default).collect(),
+        Node::Table {
+            attributes,
+            captions,
+            rows,
+            ..
+        } => {
+            let mut lists = vec![attributes];
+            for caption in captions {
+                lists.extend(&mut caption.attributes);
+                lists.push(&mut caption.content);
+            }
+            for row in rows {
+                lists.push(&mut row.attributes);
+                for cell in &mut row.cells {
+                    lists.extend(&mut cell.attributes);
+                    lists.push(&mut cell.content);
+                }
+            }
+            lists
+        }
+        Node::Template { name, parameters, .. } => {
+            let mut lists = vec![name];
+            for parameter in parameters {
+                lists.extend(&mut parameter.name);
+                lists.push(&mut parameter.value);
+            }
+            lists
+        }
+        _ => vec![],
+    }
+}
+
+fn split_text(nodes: &mut Vec<Node>, index: usize, position: usize, wiki_text: &WikiText) {
+    if let Node::Text { end, start, .. } = nodes[index] {
+        if start < position && position < end {
+            nodes[index] = Node::Text {
+                end: position,
+                start,
+                value: WikiText::new(&wiki_text.as_ref()[start..position]),
+            };
+            nodes.insert(
+                index + 1,
+                Node::Text {
+                    end,
+                    start: position,
+                    value: WikiText::new(&wiki_text.as_ref()[position..end]),
+                },
+            );
+        }
+    }
+}
+
+fn wrap(nodes: &mut Vec<Node>, recovery: Recovery, wiki_text: &WikiText) {
+    if let Some(index) = nodes
+        .iter()
+        .position(|node| node.start() < recovery.start && recovery.start < node.end())
+    {
+        split_text(nodes, index, recovery.start, wiki_text);
+        // The range starts within an element, so it goes in the list of nodes of the element with the first node after its start.
+        if let Some(children) = child_lists(&mut nodes[index])
+            .into_iter()
+            .filter_map(|children| {
+                let start = children
+                    .iter()
+                    .find(|node| node.end() > recovery.start)?
+                    .start();
+                Some((start, children))
+            })
+            .min_by_key(|(start, _)| *start)
+            .map(|(_, children)| children)
+        {
+            return wrap(children, recovery, wiki_text);
+        }
+    }
+    let Some(first) = nodes.iter().position(|node| node.end() > recovery.start) else {
+        return;
+    };
+    let Some(last) = nodes.iter().rposition(|node| node.start() < recovery.end) else {
+        return;
+    };
+    if last < first {
+        return;
+    }
+    split_text(nodes, last, recovery.end, wiki_text);
+    let wrapped: Vec<Node> = nodes.drain(first..=last).collect();
+    let start = recovery.start.min(wrapped[0].start());
+    let end = wrapped[wrapped.len() - 1].end();
+    nodes.insert(
+        first,
+        Node::Error {
+            attempted: recovery.attempted,
+            end,
+            kind: recovery.kind,
+            nodes: wrapped,
+            start,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, Node, NodeKind, WarningMessage, WikiText};
+
+    #[tokio::test]
+    async fn it_works() {
+        let configuration = Configuration::default();
+        let output = configuration
+            .parse_with_recovery(WikiText::new("a [[b\nc]] {{d|x [[e|f}}\n\n{{g|[[h]]\n\ni"))
+            .await;
+        assert_eq!(output.warnings.len(), 3);
+        assert!(matches!(
+            &output.nodes[1],
+            Node::Error {
+                attempted: NodeKind::Link { target, .. },
+                end: 5,
+                kind: WarningMessage::InvalidLinkSyntax,
+                nodes,
+                start: 2,
+            } if target.as_ref() == "b" && matches!(nodes[..], [Node::Text { end: 5, start: 2, .. }])
+        ));
+        match &output.nodes[3] {
+            Node::Template { parameters, .. } => assert!(matches!(
+                parameters[0].value[..],
+                [
+                    Node::Text { end: 16, .. },
+                    Node::Error {
+                        attempted: NodeKind::Link { .. },
+                        end: 19,
+                        kind: WarningMessage::UnexpectedEndTagRewinding,
+                        start: 16,
+                        ..
+                    }
+                ]
+            )),
+            node => panic!("{:?}", node),
+        }
+        assert!(matches!(
+            &output.nodes[5],
+            Node::Error {
+                attempted: NodeKind::Template,
+                end: 37,
+                kind: WarningMessage::MissingEndTagRewinding,
+                nodes,
+                start: 25,
+            } if matches!(nodes[1], Node::Link { .. })
+        ));
+        let wiki_text = WikiText::new("a [[b]] {{c}}");
+        assert_eq!(
+            configuration.parse_with_recovery(wiki_text.clone()).await.nodes,
+            configuration.parse(wiki_text).await.nodes
+        );
+    }
+}
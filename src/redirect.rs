@@ -4,7 +4,7 @@
 
 use crate::text::Text;
 use crate::state::State;
-use crate::{Configuration, Warning, Node, WarningMessage};
+use crate::{fix, Configuration, Warning, Node, WarningMessage};
 
 pub async fn parse_redirect(state: &mut State, configuration: &Configuration, start_position: usize) {
     let mut position = match configuration
@@ -46,19 +46,25 @@ pub async fn parse_redirect(state: &mut State, configuration: &Configuration, st
                 break;
             }
             Some(b'|') => {
-                state.warnings.push(Warning {
-                    end: position + 1,
-                    message: WarningMessage::UselessTextInRedirect,
-                    start: position,
-                });
                 target_end_position = position;
                 position += 1;
-                loop {
+                let label_end_position = loop {
                     match state.get_byte(position).await {
-                        None | Some(b'\n') => return,
-                        Some(b']') => break,
+                        None | Some(b'\n') => break None,
+                        Some(b']') => break Some(position),
                         Some(_) => position += 1,
                     }
+                };
+                state.warnings.push(Warning {
+                    context: None,
+                    end: target_end_position + 1,
+                    fix: label_end_position.map(|end| fix::deletion(target_end_position, end)),
+                    message: WarningMessage::UselessTextInRedirect,
+                    related: vec![],
+                    start: target_end_position,
+                });
+                if label_end_position.is_none() {
+                    return;
                 }
                 break;
             }
@@ -67,7 +73,7 @@ pub async fn parse_redirect(state: &mut State, configuration: &Configuration, st
     }
     if state.get_byte(position + 1).await == Some(b']') {
         position += 2;
-        state.nodes.push(Node::Redirect {
+        state.push_node(Node::Redirect {
             end: position,
             start: start_position,
             target: Text::new(&state.wiki_text.as_ref()[target_start_position..target_end_position]),
@@ -76,8 +82,11 @@ pub async fn parse_redirect(state: &mut State, configuration: &Configuration, st
         state.scan_position = state.flushed_position;
         if state.wiki_text.len() > position {
             state.warnings.push(Warning {
+                context: None,
                 end: state.wiki_text.len(),
+                fix: None,
                 message: WarningMessage::TextAfterRedirect,
+                related: vec![],
                 start: start_position,
             });
         }
@@ -0,0 +1,508 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use std::collections::HashMap;
+use crate::{Configuration, Node, Output, Parameter, Positioned, RelatedSpan, RelatedSpanKind, Warning, WarningContext, WarningMessage, WikiText};
+
+/// Template used for formatting a citation, such as `{{cite web}}` or `{{cite journal}}`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CitationTemplate {
+    /// The byte position in the wiki text where the template ends.
+    pub end: usize,
+
+    /// The kind of the template, as given by `TemplateName::kind`, such as `cite web`.
+    pub name: String,
+
+    /// The names and values of the parameters in the wiki text of the template, in order. Positional parameters are named by their position, starting from `1`.
+    pub parameters: Vec<(String, String)>,
+
+    /// The byte position in the wiki text where the template starts.
+    pub start: usize,
+}
+
+/// The references of a page, gathered from its `<ref>` and `<references>` tags, the same way as the Cite extension of Mediawiki.
+#[derive(Debug, Default)]
+pub struct Citations<'a> {
+    /// The references in the order they are first used, or defined for references defined inside `<references>` before they are used.
+    pub references: Vec<Reference<'a>>,
+
+    /// Warnings about references with undefined or duplicate names, and about references defined inside `<references>` that are never used.
+    pub warnings: Vec<Warning>,
+}
+
+/// Reference, defined by one `<ref>` tag and used by one or more `<ref>` tags.
+#[derive(Debug, PartialEq)]
+pub struct Reference<'a> {
+    /// The citation templates in the content of the reference.
+    pub citation_templates: Vec<CitationTemplate>,
+
+    /// The byte position in the wiki text where the tag defining the reference ends, or where the first tag using it ends if the reference is never defined.
+    pub end: usize,
+
+    /// The name of the reference that this reference continues, from the `follow` attribute.
+    pub follow: Option<String>,
+
+    /// The group of the reference, from the `group` attribute. Empty for the default group.
+    pub group: String,
+
+    /// The name of the reference, from the `name` attribute.
+    pub name: Option<String>,
+
+    /// The content of the reference. Empty if the reference is never defined.
+    pub nodes: &'a [Node],
+
+    /// The number of the reference within its group, starting from 1, given when the reference is first used. `None` for a reference that continues another reference or is never used.
+    pub number: Option<usize>,
+
+    /// The byte position in the wiki text where the tag defining the reference starts, or where the first tag using it starts if the reference is never defined.
+    pub start: usize,
+
+    /// The byte positions in the wiki text where the tags using the reference start, including the tag defining it unless it's defined inside `<references>`.
+    pub uses: Vec<usize>,
+}
+
+struct Collector<'a, 'b> {
+    citations: Citations<'a>,
+    configuration: &'b Configuration,
+    group_counts: HashMap<String, usize>,
+    names: HashMap<(String, String), usize>,
+    wiki_text: &'b WikiText,
+}
+
+impl Output {
+    /// Returns the references of the wiki text, numbered per group in the order they are first used and with named references resolved.
+    ///
+    /// The content of references and the parameters of citation templates are read from the wiki text by their positions, so the wiki text and configuration must be the ones this output was parsed from.
+    #[must_use]
+    pub fn citations<'a>(&'a self, configuration: &Configuration, wiki_text: &WikiText) -> Citations<'a> {
+        let mut collector = Collector {
+            citations: Default::default(),
+            configuration,
+            group_counts: HashMap::new(),
+            names: HashMap::new(),
+            wiki_text,
+        };
+        collector.collect(&self.nodes, None);
+        for reference in &collector.citations.references {
+            if reference.follow.is_some() {
+                continue;
+            }
+            let message = if reference.name.is_some() && reference.nodes.is_empty() {
+                WarningMessage::UndefinedReferenceName
+            } else if reference.uses.is_empty() {
+                WarningMessage::UnusedReferenceName
+            } else {
+                continue;
+            };
+            collector.citations.warnings.push(Warning {
+                context: reference
+                    .name
+                    .clone()
+                    .map(|name| WarningContext::ReferenceName { name }),
+                end: reference.end,
+                fix: None,
+                message,
+                related: vec![],
+                start: reference.start,
+            });
+        }
+        collector.citations
+    }
+}
+
+impl<'a, 'b> Collector<'a, 'b> {
+    fn collect(&mut self, nodes: &'a [Node], references_group: Option<&str>) {
+        for node in nodes {
+            match node {
+                Node::Tag {
+                    name,
+                    nodes,
+                    start,
+                    end,
+                } if name.as_ref() == "ref" => self.collect_ref(*start, *end, nodes, references_group),
+                Node::Tag {
+                    name, nodes, start, ..
+                } if name.as_ref() == "references" => {
+                    let group = attribute(self.wiki_text, *start, "group").unwrap_or_default();
+                    self.collect(nodes, Some(&group));
+                }
+                _ => for_each_child(node, |nodes| self.collect(nodes, references_group)),
+            }
+        }
+    }
+
+    fn collect_ref(&mut self, start: usize, end: usize, nodes: &'a [Node], references_group: Option<&str>) {
+        let name = attribute(self.wiki_text, start, "name").filter(|name| !name.is_empty());
+        let group = attribute(self.wiki_text, start, "group")
+            .or_else(|| references_group.map(str::to_owned))
+            .unwrap_or_default();
+        let follow = attribute(self.wiki_text, start, "follow").filter(|name| !name.is_empty());
+        let in_references = references_group.is_some();
+        if let Some(follow) = follow {
+            if !self.names.contains_key(&(group.clone(), follow.clone())) {
+                self.citations.warnings.push(Warning {
+                    context: Some(WarningContext::ReferenceName {
+                        name: follow.clone(),
+                    }),
+                    end,
+                    fix: None,
+                    message: WarningMessage::UndefinedReferenceName,
+                    related: vec![],
+                    start,
+                });
+            }
+            let reference = Reference {
+                follow: Some(follow),
+                group,
+                ..self.reference(start, end, nodes)
+            };
+            self.citations.references.push(reference);
+            return;
+        }
+        let name = match name {
+            None => {
+                if !nodes.is_empty() {
+                    let reference = Reference {
+                        number: (!in_references).then(|| self.next_number(&group)),
+                        uses: if in_references { vec![] } else { vec![start] },
+                        group,
+                        ..self.reference(start, end, nodes)
+                    };
+                    self.citations.references.push(reference);
+                }
+                return;
+            }
+            Some(name) => name,
+        };
+        let key = (group.clone(), name.clone());
+        let index = match self.names.get(&key) {
+            Some(index) => *index,
+            None => {
+                let reference = Reference {
+                    name: Some(name),
+                    group,
+                    ..self.reference(start, end, &[])
+                };
+                self.names.insert(key, self.citations.references.len());
+                self.citations.references.push(reference);
+                self.citations.references.len() - 1
+            }
+        };
+        if !in_references {
+            if self.citations.references[index].number.is_none() {
+                let group = self.citations.references[index].group.clone();
+                self.citations.references[index].number = Some(self.next_number(&group));
+            }
+            self.citations.references[index].uses.push(start);
+        }
+        let reference = &mut self.citations.references[index];
+        if nodes.is_empty() {
+            return;
+        }
+        if reference.nodes.is_empty() {
+            reference.citation_templates = citation_templates(self.configuration, self.wiki_text, nodes);
+            reference.end = end;
+            reference.nodes = nodes;
+            reference.start = start;
+        } else if content(self.wiki_text, reference.nodes) != content(self.wiki_text, nodes) {
+            self.citations.warnings.push(Warning {
+                context: reference
+                    .name
+                    .clone()
+                    .map(|name| WarningContext::ReferenceName { name }),
+                end,
+                fix: None,
+                message: WarningMessage::DuplicateReferenceName,
+                related: vec![RelatedSpan {
+                    end: reference.end,
+                    kind: RelatedSpanKind::FirstDefinition,
+                    start: reference.start,
+                }],
+                start,
+            });
+        }
+    }
+
+    fn next_number(&mut self, group: &str) -> usize {
+        let count = self.group_counts.entry(group.to_owned()).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    fn reference(&self, start: usize, end: usize, nodes: &'a [Node]) -> Reference<'a> {
+        Reference {
+            citation_templates: citation_templates(self.configuration, self.wiki_text, nodes),
+            end,
+            follow: None,
+            group: String::new(),
+            name: None,
+            nodes,
+            number: None,
+            start,
+            uses: vec![],
+        }
+    }
+}
+
+fn attribute(wiki_text: &WikiText, tag_start: usize, name: &str) -> Option<String> {
+    let text = &wiki_text.as_ref()[tag_start + 1..];
+    let text = &text[..text.find('>')?];
+    let text = text.strip_suffix('/').unwrap_or(text);
+    let mut attributes = text[text.find(char::is_whitespace)?..].trim_start();
+    while !attributes.is_empty() {
+        let name_end = attributes
+            .find(|character: char| character == '=' || character.is_whitespace())
+            .unwrap_or(attributes.len());
+        let attribute_name = &attributes[..name_end];
+        attributes = attributes[name_end..].trim_start();
+        let value = match attributes.strip_prefix('=') {
+            None => "",
+            Some(remainder) => {
+                let remainder = remainder.trim_start();
+                let (value, rest) = match remainder.chars().next() {
+                    Some(quote @ '"') | Some(quote @ '\'') => match remainder[1..].find(quote) {
+                        None => (&remainder[1..], ""),
+                        Some(position) => (&remainder[1..position + 1], &remainder[position + 2..]),
+                    },
+                    _ => {
+                        let position = remainder.find(char::is_whitespace).unwrap_or(remainder.len());
+                        (&remainder[..position], &remainder[position..])
+                    }
+                };
+                attributes = rest.trim_start();
+                value
+            }
+        };
+        if attribute_name.eq_ignore_ascii_case(name) {
+            return Some(value.trim().to_owned());
+        }
+    }
+    None
+}
+
+pub fn citation_template(configuration: &Configuration, wiki_text: &WikiText, node: &Node) -> Option<CitationTemplate> {
+    match node {
+        Node::Template {
+            end,
+            parameters,
+            start,
+            ..
+        } => Some(CitationTemplate {
+            end: *end,
+            name: template_kind(configuration, node)?,
+            parameters: template_parameters(wiki_text, parameters),
+            start: *start,
+        }),
+        _ => None,
+    }
+}
+
+fn citation_templates(configuration: &Configuration, wiki_text: &WikiText, nodes: &[Node]) -> Vec<CitationTemplate> {
+    nodes
+        .iter()
+        .filter_map(|node| citation_template(configuration, wiki_text, node))
+        .filter(|template| template.name.starts_with("cite ") || template.name == "citation")
+        .collect()
+}
+
+pub fn content<'a>(wiki_text: &'a WikiText, nodes: &[Node]) -> &'a str {
+    match (nodes.first(), nodes.last()) {
+        (Some(first), Some(last)) => wiki_text.as_ref()[first.start()..last.end()].trim(),
+        _ => "",
+    }
+}
+
+pub fn for_each_child<'a>(node: &'a Node, mut function: impl FnMut(&'a [Node])) {
+    match node {
+        Node::DefinitionList { items, .. } => {
+            for item in items {
+                function(&item.nodes);
+            }
+        }
+        Node::OrderedList { items, .. } | Node::UnorderedList { items, .. } => {
+            for item in items {
+                function(&item.nodes);
+            }
+        }
+        Node::Error { nodes, .. }
+        | Node::ExternalLink { nodes, .. }
+        | Node::Formatted { nodes, .. }
+        | Node::Heading { nodes, .. }
+        | Node::Preformatted { nodes, .. }
+        | Node::Tag { nodes, .. } => function(nodes),
+        Node::Image { text, .. } | Node::Link { text, .. } => function(text),
+        Node::Table { captions, rows, .. } => {
+            for caption in captions {
+                function(&caption.content);
+            }
+            for row in rows {
+                for cell in &row.cells {
+                    function(&cell.content);
+                }
+            }
+        }
+        Node::Template { parameters, .. } => {
+            for parameter in parameters {
+                function(&parameter.value);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns the kind of the template, or `None` if the node is not a template or does not transclude a page in the template namespace.
+pub fn template_kind(configuration: &Configuration, node: &Node) -> Option<String> {
+    configuration.template_name(node)?.kind()
+}
+
+fn template_parameters(wiki_text: &WikiText, parameters: &[Parameter]) -> Vec<(String, String)> {
+    let mut position = 0;
+    parameters
+        .iter()
+        .map(|parameter| {
+            let name = match &parameter.name {
+                None => {
+                    position += 1;
+                    position.to_string()
+                }
+                Some(name) => content(wiki_text, name).to_owned(),
+            };
+            (name, content(wiki_text, &parameter.value).to_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, WarningMessage, WikiText};
+
+    /// Returns the name, group, number and count of uses of each reference in the wiki text, and the kinds of the warnings.
+    async fn citations(wiki_text: &str) -> (Vec<(Option<String>, String, Option<usize>, usize)>, Vec<WarningMessage>) {
+        let configuration = Configuration::default();
+        let wiki_text = WikiText::new(wiki_text);
+        let output = configuration.parse(wiki_text.clone()).await;
+        let citations = output.citations(&configuration, &wiki_text);
+        (
+            citations
+                .references
+                .iter()
+                .map(|reference| (reference.name.clone(), reference.group.clone(), reference.number, reference.uses.len()))
+                .collect(),
+            citations.warnings.iter().map(|warning| warning.message).collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn numbering_per_group() {
+        assert_eq!(
+            citations("a<ref>A</ref> b<ref group=note>N</ref> c<ref>C</ref>").await,
+            (
+                vec![
+                    (None, String::new(), Some(1), 1),
+                    (None, "note".to_owned(), Some(1), 1),
+                    (None, String::new(), Some(2), 1),
+                ],
+                vec![]
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn named_reference_reused() {
+        assert_eq!(
+            citations("a<ref name=\"a\">A</ref> b<ref name=a /> c<ref name='a'>A</ref>").await,
+            (vec![(Some("a".to_owned()), String::new(), Some(1), 3)], vec![])
+        );
+    }
+
+    #[tokio::test]
+    async fn list_defined_reference_numbered_at_first_use() {
+        assert_eq!(
+            citations(concat!(
+                "a<ref>A</ref> b<ref name=b/> c<ref group=note name=n/>",
+                "<references>\n<ref name=\"b\">B</ref>\n</references>",
+                "<references group=note>\n<ref name=\"n\">N</ref>\n</references>",
+            ))
+            .await,
+            (
+                vec![
+                    (None, String::new(), Some(1), 1),
+                    (Some("b".to_owned()), String::new(), Some(2), 1),
+                    (Some("n".to_owned()), "note".to_owned(), Some(1), 1),
+                ],
+                vec![]
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn unused_list_defined_reference() {
+        assert_eq!(
+            citations("a<references>\n<ref name=\"d\">D</ref>\n<ref>E</ref>\n</references>").await,
+            (
+                vec![(Some("d".to_owned()), String::new(), None, 0), (None, String::new(), None, 0)],
+                vec![WarningMessage::UnusedReferenceName, WarningMessage::UnusedReferenceName]
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn undefined_reference() {
+        assert_eq!(
+            citations("a<ref name=c/> b<ref follow=d>D</ref>").await.1,
+            [WarningMessage::UndefinedReferenceName, WarningMessage::UndefinedReferenceName]
+        );
+    }
+
+    #[tokio::test]
+    async fn follow() {
+        assert_eq!(
+            citations("a<ref name=a>A</ref> b<ref follow=a>More</ref>").await,
+            (
+                vec![(Some("a".to_owned()), String::new(), Some(1), 1), (None, String::new(), None, 0)],
+                vec![]
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_reference_name() {
+        let configuration = Configuration::default();
+        let wiki_text = WikiText::new("a<ref name=a>A</ref> b<ref name=a>B</ref>");
+        let output = configuration.parse(wiki_text.clone()).await;
+        let warnings = output.citations(&configuration, &wiki_text).warnings;
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, WarningMessage::DuplicateReferenceName);
+        assert_eq!(warnings[0].related[0].start, 1);
+        assert!(citations("a<ref name=a>A</ref> b<ref name=a> A </ref>").await.1.is_empty());
+    }
+
+    #[tokio::test]
+    async fn citation_templates() {
+        let configuration = Configuration::default();
+        let wiki_text = WikiText::new(concat!(
+            "a<ref>{{subst:Cite_web |url=https://example.com |title= Example }}</ref>",
+            " b<ref>Plain {{Template:cite_book |title=B}} {{User:Cite web}}</ref>",
+        ));
+        let output = configuration.parse(wiki_text.clone()).await;
+        let citations = output.citations(&configuration, &wiki_text);
+        let template = &citations.references[0].citation_templates[0];
+        assert_eq!(template.name, "cite web");
+        assert_eq!(
+            template.parameters,
+            [
+                ("url".to_owned(), "https://example.com".to_owned()),
+                ("title".to_owned(), "Example".to_owned()),
+            ]
+        );
+        assert_eq!(
+            citations.references[1]
+                .citation_templates
+                .iter()
+                .map(|template| template.name.as_str())
+                .collect::<Vec<_>>(),
+            ["cite book"]
+        );
+    }
+}
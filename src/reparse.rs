@@ -0,0 +1,522 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use crate::state::{OpenNodeType, State};
+use crate::{
+    line, parse, Configuration, DefinitionListItem, Fix, ListItem, Node, Output, Parameter, Positioned, RelatedSpan, TableCaption,
+    TableRow, Warning, WarningMessage, WikiText,
+};
+
+/// Edit of wiki text, replacing a range of bytes with other text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TextEdit {
+    /// The byte position in the wiki text before the edit where the replaced range ends.
+    pub end: usize,
+
+    /// The text replacing the range.
+    pub replacement: String,
+
+    /// The byte position in the wiki text before the edit where the replaced range starts.
+    pub start: usize,
+}
+
+impl TextEdit {
+    /// Returns the wiki text with the edit applied.
+    ///
+    /// Panics if the range is not within the wiki text or not on character boundaries.
+    #[must_use]
+    pub fn apply(&self, wiki_text: &WikiText) -> WikiText {
+        let wiki_text = wiki_text.as_ref();
+        WikiText::new(
+            [
+                &wiki_text[..self.start],
+                &self.replacement,
+                &wiki_text[self.end..],
+            ]
+            .concat(),
+        )
+    }
+}
+
+impl Configuration {
+    /// Parses wiki text after an edit, reusing the output of parsing the wiki text before the edit.
+    ///
+    /// Parsing starts at the last paragraph break, or line starting a heading, list, table or horizontal divider, before the edit that the edit can't affect, and stops at the first such boundary after the edit where the parser is in the same state as when parsing the wiki text before the edit. The nodes and warnings after that are taken from the previous output with their positions shifted. The result is identical to parsing the whole wiki text after the edit.
+    ///
+    /// The previous output must be the result of parsing the wiki text before the edit with this configuration, and the given wiki text must be the result of applying the edit to it.
+    pub async fn reparse(&self, previous: Output, edit: &TextEdit, wiki_text: WikiText) -> Output {
+        self.reparse_region(previous, edit, wiki_text).await.0
+    }
+
+    /// Reparses the same way as `reparse`, also returning the range of the wiki text after the edit that was parsed again.
+    async fn reparse_region(&self, previous: Output, edit: &TextEdit, wiki_text: WikiText) -> (Output, Range<usize>) {
+        let edit_end = edit.start + edit.replacement.len();
+        let shift = |position: usize| position - edit.end + edit_end;
+        let previous_length = wiki_text.len() - edit_end + edit.end;
+        let text = wiki_text.as_ref();
+        let warnings = &previous.warnings;
+        // The parser decides whether a line after an empty line starts a paragraph, and which block a line starts, by looking at up to four characters of the line. A block is parsed again from the start of its line, the same way as after any other line.
+        let mut following_start = usize::MAX;
+        let resume = previous.nodes.iter().enumerate().rev().find_map(|(index, node)| {
+            let is_in_table = following_start < node.start();
+            following_start = following_start.min(node.start());
+            let (prefix_length, position, is_block) = match node {
+                Node::ParagraphBreak { end, .. } => (index + 1, *end, false),
+                _ => {
+                    let start = block_start(node).filter(|start| !is_in_table && start + 4 <= edit.start)?;
+                    let line_start = line_start(text, start)?;
+                    let prefix_length = previous.nodes[..index].partition_point(|node| node.start() < line_start);
+                    let prefix = &previous.nodes[..prefix_length];
+                    if prefix.is_empty() || !follows_prefix(node, prefix) {
+                        return None;
+                    }
+                    (prefix_length, line_start, true)
+                }
+            };
+            let index = following_warnings_index(warnings, position);
+            (node.start().max(position) + 4 <= edit.start
+                && is_independent_of_following_text(&warnings[..index], position)
+                && is_last_pass(&warnings[index..], previous_length))
+                .then_some((prefix_length, position, is_block))
+        });
+        let resume_position = resume.map_or(0, |(_, position, _)| position);
+        let paragraph_stops: HashSet<(usize, usize)> = previous
+            .nodes
+            .iter()
+            .filter_map(|node| match node {
+                Node::ParagraphBreak { end, start }
+                    if *start >= edit.end
+                        && is_last_pass(&warnings[following_warnings_index(warnings, *end)..], previous_length) =>
+                {
+                    Some((shift(*start), shift(*end)))
+                }
+                _ => None,
+            })
+            .collect();
+        // Blocks the parser can stop at, by their start after the edit. A block that the parser before the edit reached by continuing after a horizontal divider or table is left out, since the parser then goes on the same way it did after the divider or table rather than after the start of a line. So is a block inside a table, which is output before the table.
+        let mut following_start = usize::MAX;
+        let block_stops: HashMap<usize, usize> = previous
+            .nodes
+            .iter()
+            .enumerate()
+            .rev()
+            .filter_map(|(index, node)| {
+                let is_in_table = following_start < node.start();
+                following_start = following_start.min(node.start());
+                let start = block_start(node).filter(|start| !is_in_table && *start >= edit.end)?;
+                let line_start = line_start(text, shift(start))?;
+                (line_start > edit_end
+                    && follows_prefix(node, &previous.nodes[..index])
+                    && is_last_pass(&warnings[following_warnings_index(warnings, start)..], previous_length))
+                    .then_some((shift(start), start))
+            })
+            .collect();
+        let mut previous_nodes = previous.nodes;
+        let following_nodes = previous_nodes.split_off(resume.map_or(0, |(prefix_length, _, _)| prefix_length));
+        let mut previous_warnings = previous.warnings;
+        let following_warnings = previous_warnings.split_off(following_warnings_index(&previous_warnings, resume_position));
+        let mut state = match resume {
+            None => parse::start(self, wiki_text).await,
+            Some((_, position, is_block)) => {
+                let mut state = State {
+                    events: None,
+                    flushed_position: position,
+                    nodes: previous_nodes,
+                    recoveries: None,
+                    scan_position: position,
+                    stack: vec![],
+                    warnings: previous_warnings,
+                    wiki_text,
+                };
+                if is_block {
+                    line::parse_beginning_of_line(&mut state, None).await;
+                }
+                state
+            }
+        };
+        let mut block_stop = None;
+        let mut checked_position = edit_end;
+        let mut warning_count = state.warnings.len();
+        let stop = |state: &State| {
+            if state.stack.is_empty()
+                && state.flushed_position == state.scan_position
+                && match state.nodes.last() {
+                    Some(Node::ParagraphBreak { end, start }) => {
+                        *end == state.scan_position && *start >= edit_end && paragraph_stops.contains(&(*start, *end))
+                    }
+                    _ => false,
+                }
+            {
+                return true;
+            }
+            // A block is checked when the parser first reaches it, so the warnings of this step that start at the block are the first ones after the parser passed the start of its line for the last time.
+            let nodes = state.stack.first().map_or(&state.nodes, |open_node| &open_node.nodes);
+            let closed_block = nodes.last().and_then(|node| {
+                Some((block_start(node)?, !matches!(node, Node::Heading { .. } | Node::HorizontalDivider { .. })))
+            });
+            let open_block = state.stack.first().and_then(|open_node| match open_node.type_ {
+                OpenNodeType::Heading { .. } => Some((open_node.start, false)),
+                OpenNodeType::DefinitionList { .. }
+                | OpenNodeType::OrderedList { .. }
+                | OpenNodeType::Table(..)
+                | OpenNodeType::UnorderedList { .. } => Some((open_node.start, true)),
+                _ => None,
+            });
+            for (start, is_list_or_table) in closed_block.into_iter().chain(open_block) {
+                if start <= checked_position {
+                    continue;
+                }
+                checked_position = start;
+                let Some(&previous_start) = block_stops.get(&start) else {
+                    continue;
+                };
+                let prefix_length = nodes.partition_point(|node| node.start() < start);
+                if is_list_or_table && matches!(nodes[..prefix_length].last(), Some(Node::HorizontalDivider { .. } | Node::Table { .. })) {
+                    continue;
+                }
+                let batch = &state.warnings[warning_count..];
+                block_stop = Some((start, previous_start, warning_count + following_warnings_index(batch, start)));
+                return true;
+            }
+            warning_count = state.warnings.len();
+            false
+        };
+        let stopped = match parse::parse_lines(self, &mut state, None, stop).await {
+            None => unreachable!("parsing without a deadline is never aborted"),
+            Some(stopped) => stopped,
+        };
+        let (mut output, stop_position) = match block_stop {
+            None => {
+                let stop_position = match stopped {
+                    false => state.wiki_text.len(),
+                    true => state.nodes.last().map_or(0, Positioned::end),
+                };
+                let output = Output {
+                    nodes: state.nodes,
+                    warnings: state.warnings,
+                };
+                (output, stop_position)
+            }
+            Some((start, _, warning_count)) => {
+                let mut nodes = match state.stack.into_iter().next() {
+                    None => state.nodes,
+                    Some(open_node) => open_node.nodes,
+                };
+                nodes.truncate(nodes.partition_point(|node| node.start() < start));
+                let mut warnings = state.warnings;
+                warnings.truncate(warning_count);
+                (Output { nodes, warnings }, start)
+            }
+        };
+        let region = resume_position..stop_position;
+        if stopped {
+            let stop_position = block_stop.map_or(stop_position - edit_end + edit.end, |(_, previous_start, _)| previous_start);
+            output.nodes.extend(
+                following_nodes
+                    .into_iter()
+                    .filter(|node| node.start() >= stop_position)
+                    .map(|mut node| {
+                        shift_node(&mut node, edit.end, edit_end);
+                        node
+                    }),
+            );
+            let index = following_warnings_index(&following_warnings, stop_position);
+            output.warnings.extend(
+                following_warnings
+                    .into_iter()
+                    .skip(index)
+                    .map(|warning| Warning {
+                        end: shift(warning.end),
+                        fix: warning.fix.map(|fix| Fix {
+                            edits: fix
+                                .edits
+                                .into_iter()
+                                .map(|edit| TextEdit {
+                                    end: shift(edit.end),
+                                    start: shift(edit.start),
+                                    ..edit
+                                })
+                                .collect(),
+                        }),
+                        related: warning
+                            .related
+                            .into_iter()
+                            .map(|span| RelatedSpan {
+                                end: shift(span.end),
+                                start: shift(span.start),
+                                ..span
+                            })
+                            .collect(),
+                        start: shift(warning.start),
+                        ..warning
+                    }),
+            );
+        }
+        (output, region)
+    }
+}
+
+/// Returns the start of the given node if it's a heading, list, table or horizontal divider, which the parser starts at the beginning of a line.
+fn block_start(node: &Node) -> Option<usize> {
+    match node {
+        Node::DefinitionList { start, .. }
+        | Node::Heading { start, .. }
+        | Node::HorizontalDivider { start, .. }
+        | Node::OrderedList { start, .. }
+        | Node::Table { start, .. }
+        | Node::UnorderedList { start, .. } => Some(*start),
+        _ => None,
+    }
+}
+
+/// Returns whether the parser reached the given block the same way as after the start of any line, given the nodes before it. After a horizontal divider or table, the parser goes on skipping whitespace and line breaks after the start of a list or table, which it otherwise doesn't.
+fn follows_prefix(node: &Node, prefix: &[Node]) -> bool {
+    matches!(node, Node::Heading { .. } | Node::HorizontalDivider { .. })
+        || !matches!(prefix.last(), Some(Node::HorizontalDivider { .. } | Node::Table { .. }))
+}
+
+/// Returns the start of the line of the given position if only whitespace beginning with a space comes before the position on the line, the way a table can be indented.
+fn line_start(text: &str, position: usize) -> Option<usize> {
+    let start = text[..position].trim_end_matches([' ', '\t']).len();
+    ((start == 0 || text.as_bytes()[start - 1] == b'\n') && (start == position || text.as_bytes()[start] == b' '))
+        .then_some(start)
+}
+
+/// Returns whether the given warnings, emitted before the parser passed a position for the last time, show that the state of the parser at the position doesn't depend on the text after it. A warning ending after the position means the parser looked past it, and constructs that are rewound because the end of the text or an end tag is never found depend on all the text after them.
+fn is_independent_of_following_text(warnings: &[Warning], position: usize) -> bool {
+    warnings.iter().all(|warning| match warning.message {
+        WarningMessage::InvalidTagSyntax | WarningMessage::MissingEndTagRewinding => false,
+        _ => warning.end <= position,
+    })
+}
+
+/// Returns whether the given warnings, all starting at or after a position, can only have been emitted after the parser passed the position for the last time. A construct rewound at an end tag may have started before the position, making the parser pass it again, while a construct rewound at the end of the text continues parsing after its start.
+fn is_last_pass(warnings: &[Warning], length: usize) -> bool {
+    warnings.iter().all(|warning| match warning.message {
+        WarningMessage::MissingEndTagRewinding => warning.end == length,
+        WarningMessage::UnexpectedEndTagRewinding => false,
+        _ => true,
+    })
+}
+
+/// Returns the index of the first warning emitted after the parser passed the given position for the last time. Warnings from passes that are rewound are kept, so the warnings after the position are the longest run at the end that all start at or after the position.
+fn following_warnings_index(warnings: &[Warning], position: usize) -> usize {
+    warnings
+        .iter()
+        .rposition(|warning| warning.start < position)
+        .map_or(0, |index| index + 1)
+}
+
+fn shift_node(node: &mut Node, from: usize, to: usize) {
+    let (start, end) = match node {
+        Node::Bold { end, start }
+        | Node::BoldItalic { end, start }
+        | Node::CharacterEntity { end, start, .. }
+        | Node::Comment { end, start }
+        | Node::EndTag { end, start, .. }
+        | Node::HorizontalDivider { end, start }
+        | Node::InterlanguageLink { end, start, .. }
+        | Node::Italic { end, start }
+        | Node::MagicWord { end, start }
+        | Node::ParagraphBreak { end, start }
+        | Node::Redirect { end, start, .. }
+        | Node::StartTag { end, start, .. }
+        | Node::Text { end, start, .. } => (start, end),
+        Node::Category {
+            end, ordinal, start, ..
+        } => {
+            shift_nodes(ordinal, from, to);
+            (start, end)
+        }
+        Node::DefinitionList { end, items, start } => {
+            for DefinitionListItem { end, nodes, start, .. } in items {
+                shift_nodes(nodes, from, to);
+                shift_range(start, end, from, to);
+            }
+            (start, end)
+        }
+        Node::Error { end, nodes, start, .. }
+        | Node::ExternalLink { end, nodes, start }
+        | Node::Formatted { end, nodes, start, .. }
+        | Node::Heading { end, nodes, start, .. }
+        | Node::Preformatted { end, nodes, start }
+        | Node::Tag { end, nodes, start, .. } => {
+            shift_nodes(nodes, from, to);
+            (start, end)
+        }
+        Node::Image { end, start, text, .. } | Node::Link { end, start, text, .. } => {
+            shift_nodes(text, from, to);
+            (start, end)
+        }
+        Node::OrderedList { end, items, start } | Node::UnorderedList { end, items, start } => {
+            for ListItem { end, nodes, start } in items {
+                shift_nodes(nodes, from, to);
+                shift_range(start, end, from, to);
+            }
+            (start, end)
+        }
+        Node::Parameter {
+            default,
+            end,
+            name,
+            start,
+        } => {
+            shift_nodes(name, from, to);
+            if let Some(default) = default {
+                shift_nodes(default, from, to);
+            }
+            (start, end)
+        }
+        Node::Table {
+            attributes,
+            captions,
+            end,
+            rows,
+            start,
+        } => {
+            shift_nodes(attributes, from, to);
+            for TableCaption {
+                attributes,
+                content,
+                end,
+                start,
+            } in captions
+            {
+                shift_nodes(attributes.iter_mut().flatten(), from, to);
+                shift_nodes(content, from, to);
+                shift_range(start, end, from, to);
+            }
+            for TableRow {
+                attributes,
+                cells,
+                end,
+                start,
+            } in rows
+            {
+                shift_nodes(attributes, from, to);
+                for cell in cells {
+                    shift_nodes(cell.attributes.iter_mut().flatten(), from, to);
+                    shift_nodes(&mut cell.content, from, to);
+                    shift_range(&mut cell.start, &mut cell.end, from, to);
+                }
+                shift_range(start, end, from, to);
+            }
+            (start, end)
+        }
+        Node::Template {
+            end,
+            name,
+            parameters,
+            start,
+        } => {
+            shift_nodes(name, from, to);
+            for Parameter {
+                end,
+                name,
+                start,
+                value,
+            } in parameters
+            {
+                shift_nodes(name.iter_mut().flatten(), from, to);
+                shift_nodes(value, from, to);
+                shift_range(start, end, from, to);
+            }
+            (start, end)
+        }
+    };
+    shift_range(start, end, from, to);
+}
+
+fn shift_nodes<'a>(nodes: impl IntoIterator<Item = &'a mut Node>, from: usize, to: usize) {
+    for node in nodes {
+        shift_node(node, from, to);
+    }
+}
+
+fn shift_range(start: &mut usize, end: &mut usize, from: usize, to: usize) {
+    *start = *start - from + to;
+    *end = *end - from + to;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, TextEdit, WikiText};
+
+    const FRAGMENTS: &[&str] = &[
+        "", "\n", "\n\n", " ", "x", "{{", "}}", "{{a|b=c}}", "{{{", "}}}", "[[", "]]", "[[a]]b", "[", "]", "''", "'''",
+        "<!--", "-->", "<div>", "</div>", "<ref>", "</ref>", "<nowiki>", "</nowiki>", "<span", ">", "==", "== h ==\n",
+        "*", "#", ":", ";", "----", "{|", "|}", "|-", "|", "&amp;", "__TOC__",
+    ];
+
+    const BLOCKS: &str = "Lead text.\n== One ==\nText ''one''.\n*a\n*b [[c]]\n== Two ==\n#d\n#:e\n{|\n|f\n|}\nText two.\n----\n;g:h\n== Three ==\n {|\n|i\n|}\n=== Four ===\nText {{j}} four.\n== Five ==\nText five.";
+
+    #[tokio::test]
+    async fn it_works() {
+        let configuration = Configuration::default();
+        let mut random: u64 = 1;
+        let mut next = |bound: usize| {
+            random = random.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (random >> 33) as usize % bound.max(1)
+        };
+        for text in [
+            "Lead ''text''.\n\n== One ==\n\n*a\n*b\n\n{|\n|c\n|}\n\n{{t|x=\n\ny}}\n\n<div>\n\nd\n\n</div>\n\ne [[f]]g\n\n\n<!-- h\n\n-->i\n\n----\n\n j\n\nk",
+            "{{Infobox\n| name = ''x''\n| y = [[z]]\n}}\n'''A''' is <ref>{{cite|a}}</ref> b.\n\n== C ==\n\n; d : e\n\n# f\n## g\n\n<nowiki>[[h]]</nowiki>\n\n{{{p|q}}}\n\n[http://example.com l]\n\n[[Category:R]]",
+            BLOCKS,
+        ] {
+            // Edits are made in short chains from the original text, since unclosed constructs piling up make the parser rewind exponentially.
+            for _ in 0..40 {
+                let mut wiki_text = WikiText::new(text);
+                let mut output = configuration.parse(wiki_text.clone()).await;
+                for _ in 0..4 {
+                    let length = wiki_text.len();
+                    let mut start = next(length + 1);
+                    while !wiki_text.as_ref().is_char_boundary(start) {
+                        start -= 1;
+                    }
+                    let mut end = (start + next(8)).min(length);
+                    while !wiki_text.as_ref().is_char_boundary(end) {
+                        end += 1;
+                    }
+                    let edit = TextEdit {
+                        end,
+                        replacement: FRAGMENTS[next(FRAGMENTS.len())].to_owned(),
+                        start,
+                    };
+                    wiki_text = edit.apply(&wiki_text);
+                    output = configuration.reparse(output, &edit, wiki_text.clone()).await;
+                    let expected = configuration.parse(wiki_text.clone()).await;
+                    assert_eq!(output.nodes, expected.nodes, "{:?}", edit);
+                    assert_eq!(output.warnings, expected.warnings, "{:?}", edit);
+                }
+            }
+        }
+        // Edits that don't change the structure of the text are parsed again only from the start of the line of the previous block to the start of the next block.
+        for (needle, replacement) in [
+            ("one''", "x"),
+            ("b [[c]]", "[[a]]b"),
+            ("e\n", "&amp;"),
+            ("f\n", "x"),
+            ("two.", "[[a]]b"),
+            ("i\n", "&amp;"),
+            ("four.", "x"),
+        ] {
+            let wiki_text = WikiText::new(BLOCKS);
+            let previous = configuration.parse(wiki_text.clone()).await;
+            let start = BLOCKS.find(needle).unwrap();
+            let edit = TextEdit {
+                end: start + 1,
+                replacement: replacement.to_owned(),
+                start,
+            };
+            let wiki_text = edit.apply(&wiki_text);
+            let (output, region) = configuration.reparse_region(previous, &edit, wiki_text.clone()).await;
+            let expected = configuration.parse(wiki_text).await;
+            assert_eq!(output.nodes, expected.nodes, "{:?}", edit);
+            assert_eq!(output.warnings, expected.warnings, "{:?}", edit);
+            assert!(region.start < start && region.end > start && region.len() < 40, "{:?} {:?}", edit, region);
+        }
+    }
+}
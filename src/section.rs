@@ -0,0 +1,163 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use crate::{Node, Output, Positioned, Section, WikiText};
+
+impl Output {
+    /// Returns the sections of the wiki text, numbered the same way as the `section` parameter of Mediawiki.
+    ///
+    /// Section 0 is the text before the first heading and is always present. Every following section starts at a heading and includes its subsections. Headings inside templates, parameters and extension tags are not counted, since Mediawiki doesn't count them either. Headings inside `Node::Error` from `Configuration::parse_with_recovery` are counted.
+    #[must_use]
+    pub fn sections(&self) -> Vec<Section> {
+        let mut headings = vec![];
+        collect_headings(&self.nodes, &mut headings);
+        let end = self.nodes.last().map_or(0, Positioned::end);
+        let mut sections = Vec::with_capacity(headings.len() + 1);
+        sections.push(Section {
+            end: headings.first().map_or(end, |(start, _)| *start),
+            level: 0,
+            start: 0,
+        });
+        for (index, (start, level)) in headings.iter().cloned().enumerate() {
+            sections.push(Section {
+                end: headings[index + 1..]
+                    .iter()
+                    .find(|(_, next_level)| *next_level <= level)
+                    .map_or(end, |(next_start, _)| *next_start),
+                level,
+                start,
+            });
+        }
+        sections
+    }
+
+    /// Returns the text of the given section, with trailing whitespace removed, or `None` if there is no such section.
+    ///
+    /// The section is sliced out of the given wiki text by the positions in the output, so it must be the wiki text the output was parsed from.
+    #[must_use]
+    pub fn extract_section<'a>(&self, wiki_text: &'a WikiText, section: usize) -> Option<&'a str> {
+        let Section { end, start, .. } = self.sections().into_iter().nth(section)?;
+        Some(section_text(wiki_text, start, end).trim_end())
+    }
+
+    /// Returns the wiki text with the given section replaced by the new text, or `None` if there is no such section.
+    ///
+    /// As with `extract_section`, the wiki text must be the one the output was parsed from. The result is formatted the same way as when Mediawiki replaces a section.
+    #[must_use]
+    pub fn replace_section(
+        &self,
+        wiki_text: &WikiText,
+        section: usize,
+        new_text: &str,
+    ) -> Option<String> {
+        let Section { end, start, .. } = self.sections().into_iter().nth(section)?;
+        let text = wiki_text.as_ref();
+        let end = end.max(start).min(text.len());
+        let mut result = String::with_capacity(text.len() + new_text.len() + 2);
+        result += &text[..start];
+        result += new_text;
+        result += "\n\n";
+        result += &text[end..];
+        result.truncate(result.trim_end().len());
+        Some(result)
+    }
+}
+
+/// Returns the wiki text with a new section appended at the end, the same way as when Mediawiki adds a new section with the given heading and text.
+#[must_use]
+pub fn append_section(wiki_text: &WikiText, heading: &str, text: &str) -> String {
+    let section = if heading.is_empty() {
+        text.to_owned()
+    } else {
+        format!("== {} ==\n\n{}", heading, text)
+    };
+    if wiki_text.as_ref().trim().is_empty() {
+        section
+    } else {
+        format!("{}\n\n{}", wiki_text.as_ref(), section)
+    }
+}
+
+fn collect_headings(nodes: &[Node], headings: &mut Vec<(usize, u8)>) {
+    for node in nodes {
+        match node {
+            Node::Error { nodes, .. } => collect_headings(nodes, headings),
+            Node::Heading { level, start, .. } => headings.push((*start, *level)),
+            Node::Table { captions, rows, .. } => {
+                for caption in captions {
+                    collect_headings(&caption.content, headings);
+                }
+                for row in rows {
+                    for cell in &row.cells {
+                        collect_headings(&cell.content, headings);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn section_text(wiki_text: &WikiText, start: usize, end: usize) -> &str {
+    let text = wiki_text.as_ref();
+    &text[start.min(text.len())..end.max(start).min(text.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, WikiText};
+
+    #[tokio::test]
+    async fn it_works() {
+        let wiki_text = WikiText::new(concat!(
+            "Lead\n",
+            "==Alpha==\n",
+            "alpha\n",
+            "===Beta===\n",
+            "{{template|\n==Not a section==\n}}\n",
+            "<nowiki>\n==Not a section==\n</nowiki>\n",
+            "==Gamma==\n",
+            "gamma\n",
+        ));
+        let output = Configuration::default().parse(wiki_text.clone()).await;
+        assert_eq!(output.sections().len(), 4);
+        assert_eq!(output.extract_section(&wiki_text, 0), Some("Lead"));
+        assert_eq!(
+            output.extract_section(&wiki_text, 1),
+            Some(concat!(
+                "==Alpha==\n",
+                "alpha\n",
+                "===Beta===\n",
+                "{{template|\n==Not a section==\n}}\n",
+                "<nowiki>\n==Not a section==\n</nowiki>"
+            ))
+        );
+        assert_eq!(output.extract_section(&wiki_text, 3), Some("==Gamma==\ngamma"));
+        assert_eq!(output.extract_section(&wiki_text, 4), None);
+        assert_eq!(
+            output.replace_section(&wiki_text, 1, "==Delta==").as_deref(),
+            Some("Lead\n==Delta==\n\n==Gamma==\ngamma")
+        );
+        assert_eq!(
+            output.replace_section(&wiki_text, 3, "==Delta==").as_deref(),
+            Some(concat!(
+                "Lead\n",
+                "==Alpha==\n",
+                "alpha\n",
+                "===Beta===\n",
+                "{{template|\n==Not a section==\n}}\n",
+                "<nowiki>\n==Not a section==\n</nowiki>\n",
+                "==Delta=="
+            ))
+        );
+        assert_eq!(
+            super::append_section(&WikiText::new("Lead\n"), "Delta", "delta"),
+            "Lead\n\n\n== Delta ==\n\ndelta"
+        );
+        let wiki_text = WikiText::new("Lead {{a\n==Alpha==\nalpha\n==Beta==\nbeta");
+        let output = Configuration::default().parse_with_recovery(wiki_text.clone()).await;
+        assert_eq!(output.sections().len(), 3);
+        assert_eq!(output.extract_section(&wiki_text, 2), Some("==Beta==\nbeta"));
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use serde::{Deserialize, Serialize};
+use crate::{Configuration, ConfigurationError, ConfigurationSource, Namespace, NamespaceSource};
+
+/// Site specific configuration of a wiki, owning its data.
+///
+/// This holds the same information as `ConfigurationSource`, for configurations that are built at runtime or saved to and loaded from a file.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct SiteConfig {
+    /// Tag names of extension tags.
+    pub extension_tags: Vec<String>,
+
+    /// Whether all named character entities of HTML5 are recognized, rather than only those of HTML 4.
+    pub html5_entities: bool,
+
+    /// Prefixes of links to other wikis, not including language prefixes.
+    pub interwiki_prefixes: Vec<String>,
+
+    /// Prefixes of links to the same page in wikis in other languages.
+    pub language_prefixes: Vec<String>,
+
+    /// Characters that can appear in link prefixes, in the format of Mediawiki's `linkprefixcharset`, such as `a-zA-Z\x{80}-\x{10ffff}`, or empty if the wiki has no link prefixes.
+    pub link_prefix: String,
+
+    /// Rule for link trails, either a regular expression in the format of Mediawiki's `linktrail`, such as `/^([a-z]+)(.*)$/sD`, or the characters that can appear in link trails.
+    pub link_trail: String,
+
+    /// Magic words that can appear between `__` and `__`.
+    pub magic_words: Vec<String>,
+
+    /// Namespaces of the wiki.
+    pub namespaces: Vec<SiteNamespace>,
+
+    /// Names of parser functions that are called without `#`, such as `lc` or `DEFAULTSORT`, matched case insensitively.
+    pub parser_functions: Vec<String>,
+
+    /// Protocols that can be used for external links.
+    pub protocols: Vec<String>,
+
+    /// Magic words that can be used for redirects.
+    pub redirect_magic_words: Vec<String>,
+
+    /// Names of variables, such as `PAGENAME`, matched case sensitively.
+    pub variables: Vec<String>,
+}
+
+/// Site specific configuration of a namespace, owning its data.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct SiteNamespace {
+    /// Additional names of the namespace, besides its name and canonical name.
+    pub aliases: Vec<String>,
+
+    /// The name of the namespace that is the same in all wikis.
+    pub canonical_name: String,
+
+    /// Whether the first letter of page names in the namespace is case sensitive.
+    pub case_sensitive: bool,
+
+    /// The numeric identifier of the namespace.
+    pub id: i32,
+
+    /// The name of the namespace in the language of the wiki.
+    pub name: String,
+}
+
+/// Builder for a configuration assembled at runtime.
+///
+/// ```
+/// use async_parse_wiki_text::ConfigurationBuilder;
+/// let configuration = ConfigurationBuilder::new()
+///     .extension_tag("ref")
+///     .protocol("gemini://")
+///     .protocol("https://")
+///     .redirect_magic_word("REDIRECT")
+///     .build()
+///     .unwrap();
+/// assert_eq!(configuration.to_source().protocols, ["gemini://", "https://"]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ConfigurationBuilder {
+    site_config: SiteConfig,
+}
+
+impl ConfigurationBuilder {
+    /// Returns a builder for a configuration with nothing configured.
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Builds the configuration. Returns an error if the link prefix or link trail is invalid.
+    pub fn build(self) -> Result<Configuration, ConfigurationError> {
+        Configuration::from_site_config(self.site_config)
+    }
+
+    /// Adds an extension tag.
+    #[must_use]
+    pub fn extension_tag(mut self, name: impl Into<String>) -> Self {
+        self.site_config.extension_tags.push(name.into());
+        self
+    }
+
+    /// Sets whether all named character entities of HTML5 are recognized, rather than only those of HTML 4.
+    #[must_use]
+    pub fn html5_entities(mut self, html5_entities: bool) -> Self {
+        self.site_config.html5_entities = html5_entities;
+        self
+    }
+
+    /// Adds a prefix of links to another wiki.
+    #[must_use]
+    pub fn interwiki_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.site_config.interwiki_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Adds a prefix of links to the same page in a wiki in another language.
+    #[must_use]
+    pub fn language_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.site_config.language_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Sets the characters that can appear in link prefixes, in the format of Mediawiki's `linkprefixcharset`.
+    #[must_use]
+    pub fn link_prefix(mut self, charset: impl Into<String>) -> Self {
+        self.site_config.link_prefix = charset.into();
+        self
+    }
+
+    /// Sets the rule for link trails, either a regular expression in the format of Mediawiki's `linktrail` or the characters that can appear in link trails.
+    #[must_use]
+    pub fn link_trail(mut self, rule: impl Into<String>) -> Self {
+        self.site_config.link_trail = rule.into();
+        self
+    }
+
+    /// Adds a magic word that can appear between `__` and `__`.
+    #[must_use]
+    pub fn magic_word(mut self, magic_word: impl Into<String>) -> Self {
+        self.site_config.magic_words.push(magic_word.into());
+        self
+    }
+
+    /// Adds a namespace.
+    #[must_use]
+    pub fn namespace(mut self, namespace: SiteNamespace) -> Self {
+        self.site_config.namespaces.push(namespace);
+        self
+    }
+
+    /// Adds the name of a parser function that is called without `#`.
+    #[must_use]
+    pub fn parser_function(mut self, name: impl Into<String>) -> Self {
+        self.site_config.parser_functions.push(name.into());
+        self
+    }
+
+    /// Adds a protocol that can be used for external links.
+    #[must_use]
+    pub fn protocol(mut self, protocol: impl Into<String>) -> Self {
+        self.site_config.protocols.push(protocol.into());
+        self
+    }
+
+    /// Adds a magic word that can be used for redirects.
+    #[must_use]
+    pub fn redirect_magic_word(mut self, magic_word: impl Into<String>) -> Self {
+        self.site_config.redirect_magic_words.push(magic_word.into());
+        self
+    }
+
+    /// Adds the name of a variable.
+    #[must_use]
+    pub fn variable(mut self, name: impl Into<String>) -> Self {
+        self.site_config.variables.push(name.into());
+        self
+    }
+}
+
+impl From<SiteConfig> for ConfigurationBuilder {
+    fn from(site_config: SiteConfig) -> Self {
+        ConfigurationBuilder { site_config }
+    }
+}
+
+impl<'a> From<&ConfigurationSource<'a>> for SiteConfig {
+    fn from(source: &ConfigurationSource<'a>) -> Self {
+        let mut namespaces: Vec<SiteNamespace> = source.namespaces.iter().map(SiteNamespace::from).collect();
+        for (namespace, canonical_name, aliases) in [
+            (Namespace::CATEGORY, "Category", source.category_namespaces),
+            (Namespace::FILE, "File", source.file_namespaces),
+        ] {
+            if aliases.is_empty() {
+                continue;
+            }
+            let index = match namespaces.iter().position(|definition| definition.id == namespace.id) {
+                Some(index) => index,
+                None => {
+                    namespaces.push(SiteNamespace {
+                        aliases: vec![],
+                        canonical_name: canonical_name.to_owned(),
+                        case_sensitive: false,
+                        id: namespace.id,
+                        name: canonical_name.to_owned(),
+                    });
+                    namespaces.len() - 1
+                }
+            };
+            for alias in aliases {
+                if !namespaces[index].aliases.iter().any(|existing| existing == alias) {
+                    namespaces[index].aliases.push((*alias).to_owned());
+                }
+            }
+        }
+        SiteConfig {
+            extension_tags: strings(source.extension_tags),
+            html5_entities: source.html5_entities,
+            interwiki_prefixes: strings(source.interwiki_prefixes),
+            language_prefixes: strings(source.language_prefixes),
+            link_prefix: source.link_prefix.to_owned(),
+            link_trail: source.link_trail.to_owned(),
+            magic_words: strings(source.magic_words),
+            namespaces,
+            parser_functions: strings(source.parser_functions),
+            protocols: strings(source.protocols),
+            redirect_magic_words: strings(source.redirect_magic_words),
+            variables: strings(source.variables),
+        }
+    }
+}
+
+impl<'a> From<&NamespaceSource<'a>> for SiteNamespace {
+    fn from(source: &NamespaceSource<'a>) -> Self {
+        SiteNamespace {
+            aliases: strings(source.aliases),
+            canonical_name: source.canonical_name.to_owned(),
+            case_sensitive: source.case_sensitive,
+            id: source.id,
+            name: source.name.to_owned(),
+        }
+    }
+}
+
+fn strings(source: &[&str]) -> Vec<String> {
+    source.iter().map(|item| (*item).to_owned()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Configuration, ConfigurationBuilder, ConfigurationError, ConfigurationSource, Namespace, NamespaceSource, SiteConfig,
+    };
+
+    #[test]
+    fn it_works() {
+        let site_config = Configuration::default().to_source();
+        let json = serde_json::to_string(&site_config).unwrap();
+        let loaded: SiteConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, site_config);
+        assert_eq!(Configuration::from_site_config(loaded).unwrap().to_source(), site_config);
+        let partial: SiteConfig = serde_json::from_str(r#"{"protocols":["https://"]}"#).unwrap();
+        assert_eq!(partial.protocols, ["https://"]);
+        assert!(partial.namespaces.is_empty());
+        assert_eq!(
+            ConfigurationBuilder::new().link_trail("/^(\\w+)(.*)$/sD").build().err(),
+            Some(ConfigurationError::InvalidLinkTrail)
+        );
+        assert_eq!(
+            ConfigurationBuilder::new().link_prefix("a-z\\w").build().err(),
+            Some(ConfigurationError::InvalidLinkPrefix)
+        );
+        let mut invalid = site_config.clone();
+        invalid.link_trail = "/^([a-z]+)$/".to_owned();
+        assert_eq!(Configuration::from_site_config(invalid).err(), Some(ConfigurationError::InvalidLinkTrail));
+        let source = ConfigurationSource {
+            category_namespaces: &["kategorie", "Category"],
+            extension_tags: &[],
+            file_namespaces: &["datei"],
+            html5_entities: false,
+            interwiki_prefixes: &[],
+            language_prefixes: &[],
+            link_prefix: "",
+            link_trail: "",
+            magic_words: &[],
+            namespaces: &[NamespaceSource {
+                aliases: &[],
+                canonical_name: "Category",
+                case_sensitive: false,
+                id: 14,
+                name: "Kategorie",
+            }],
+            parser_functions: &[],
+            protocols: &[],
+            redirect_magic_words: &[],
+            variables: &[],
+        };
+        let configuration = Configuration::new(&source);
+        let namespaces = configuration.to_source().namespaces;
+        assert_eq!(namespaces.len(), 2);
+        assert_eq!(namespaces[0].aliases, ["kategorie", "Category"]);
+        assert_eq!((namespaces[1].id, &namespaces[1].aliases[..]), (Namespace::FILE.id, &["datei".to_owned()][..]));
+        assert_eq!(configuration.parse_title("Datei:X").unwrap().namespace, Namespace::FILE);
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use serde_json::Value;
+use std::fmt;
+use crate::{Configuration, ConfigurationError, SiteConfig, SiteNamespace};
+
+/// Error from reading the site info of a wiki.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SiteinfoError {
+    /// The text is not valid JSON.
+    InvalidJson,
+
+    /// A property needed for parsing is missing or has an unexpected format.
+    InvalidProperty(&'static str),
+}
+
+impl fmt::Display for SiteinfoError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SiteinfoError::InvalidJson => formatter.write_str("The site info is not valid JSON."),
+            SiteinfoError::InvalidProperty(name) => write!(
+                formatter,
+                "The property {:?} of the site info is missing or invalid.",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SiteinfoError {}
+
+impl Configuration {
+    /// Allocates and returns a new configuration based on a saved site info response from the API of the wiki.
+    ///
+    /// The response must be from a request with `action=query&meta=siteinfo&siprop=general|namespaces|namespacealiases|magicwords|functionhooks|variables|extensiontags|protocols|interwikimap`, in either format version of the API. The properties `functionhooks` and `variables` are optional, and without them no template names are recognized as parser functions or variables.
+    pub fn from_siteinfo_json(json: &str) -> Result<Self, SiteinfoError> {
+        let response: Value = serde_json::from_str(json).map_err(|_| SiteinfoError::InvalidJson)?;
+        let query = response.get("query").unwrap_or(&response);
+        let general = query
+            .get("general")
+            .ok_or(SiteinfoError::InvalidProperty("general"))?;
+        let link_prefix = general
+            .get("linkprefixcharset")
+            .and_then(Value::as_str)
+            .unwrap_or("");
+        let link_trail = general.get("linktrail").and_then(Value::as_str).unwrap_or("");
+        let mut site_config = SiteConfig {
+            extension_tags: strings(query, "extensiontags")?
+                .into_iter()
+                .map(|tag| tag.trim_start_matches('<').trim_end_matches('>').to_owned())
+                .collect(),
+            html5_entities: true,
+            link_prefix: link_prefix.to_owned(),
+            link_trail: link_trail.to_owned(),
+            protocols: strings(query, "protocols")?
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            ..Default::default()
+        };
+        for entry in array(query, "interwikimap")? {
+            let prefix = entry
+                .get("prefix")
+                .and_then(Value::as_str)
+                .ok_or(SiteinfoError::InvalidProperty("interwikimap"))?
+                .to_owned();
+            if entry.get("language").is_some() {
+                site_config.language_prefixes.push(prefix);
+            } else {
+                site_config.interwiki_prefixes.push(prefix);
+            }
+        }
+        let function_hooks = optional_strings(query, "functionhooks")?;
+        let variables = optional_strings(query, "variables")?;
+        for entry in array(query, "magicwords")? {
+            let aliases = entry
+                .get("aliases")
+                .and_then(Value::as_array)
+                .ok_or(SiteinfoError::InvalidProperty("magicwords"))?
+                .iter()
+                .filter_map(Value::as_str);
+            let name = entry.get("name").and_then(Value::as_str);
+            if let Some(name) = name.filter(|name| function_hooks.contains(name)) {
+                site_config.parser_functions.extend(
+                    aliases
+                        .clone()
+                        .filter(|alias| !alias.starts_with('#'))
+                        .map(|alias| alias.trim_end_matches(':').to_owned()),
+                );
+                if variables.contains(&name) {
+                    site_config.variables.extend(aliases.map(str::to_owned));
+                }
+            } else if name.is_some_and(|name| variables.contains(&name)) {
+                site_config.variables.extend(aliases.map(str::to_owned));
+            } else if name == Some("redirect") {
+                site_config
+                    .redirect_magic_words
+                    .extend(aliases.map(|alias| alias.trim_start_matches('#').to_owned()));
+            } else {
+                site_config.magic_words.extend(aliases.filter_map(|alias| {
+                    alias
+                        .strip_prefix("__")
+                        .and_then(|alias| alias.strip_suffix("__"))
+                        .filter(|alias| !alias.is_empty())
+                        .map(str::to_owned)
+                }));
+            }
+        }
+        let namespace_aliases = array(query, "namespacealiases")?
+            .iter()
+            .map(|entry| {
+                Ok((
+                    entry
+                        .get("id")
+                        .and_then(Value::as_i64)
+                        .ok_or(SiteinfoError::InvalidProperty("namespacealiases"))?,
+                    string_value(entry, "alias")
+                        .ok_or(SiteinfoError::InvalidProperty("namespacealiases"))?,
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for entry in query
+            .get("namespaces")
+            .and_then(Value::as_object)
+            .ok_or(SiteinfoError::InvalidProperty("namespaces"))?
+            .values()
+        {
+            let id = entry
+                .get("id")
+                .and_then(Value::as_i64)
+                .ok_or(SiteinfoError::InvalidProperty("namespaces"))?;
+            site_config.namespaces.push(SiteNamespace {
+                aliases: namespace_aliases
+                    .iter()
+                    .filter(|(alias_id, _)| *alias_id == id)
+                    .map(|(_, alias)| (*alias).to_owned())
+                    .collect(),
+                canonical_name: entry
+                    .get("canonical")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_owned(),
+                case_sensitive: entry.get("case").and_then(Value::as_str) == Some("case-sensitive"),
+                id: id as _,
+                name: string_value(entry, "name")
+                    .ok_or(SiteinfoError::InvalidProperty("namespaces"))?
+                    .to_owned(),
+            });
+        }
+        Configuration::from_site_config(site_config).map_err(|error| {
+            SiteinfoError::InvalidProperty(match error {
+                ConfigurationError::InvalidLinkPrefix => "linkprefixcharset",
+                ConfigurationError::InvalidLinkTrail => "linktrail",
+            })
+        })
+    }
+}
+
+fn array<'a>(query: &'a Value, name: &'static str) -> Result<&'a [Value], SiteinfoError> {
+    query
+        .get(name)
+        .and_then(Value::as_array)
+        .map(|array| array as _)
+        .ok_or(SiteinfoError::InvalidProperty(name))
+}
+
+fn optional_strings<'a>(query: &'a Value, name: &'static str) -> Result<Vec<&'a str>, SiteinfoError> {
+    match query.get(name) {
+        None => Ok(vec![]),
+        Some(_) => strings(query, name),
+    }
+}
+
+fn string_value<'a>(entry: &'a Value, name: &str) -> Option<&'a str> {
+    entry
+        .get(name)
+        .or_else(|| entry.get("*"))
+        .and_then(Value::as_str)
+}
+
+fn strings<'a>(query: &'a Value, name: &'static str) -> Result<Vec<&'a str>, SiteinfoError> {
+    array(query, name)?
+        .iter()
+        .map(|value| value.as_str().ok_or(SiteinfoError::InvalidProperty(name)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, Namespace, Node, WikiText};
+
+    #[tokio::test]
+    async fn it_works() {
+        let configuration = Configuration::from_siteinfo_json(concat!(
+            r##"{"batchcomplete":true,"query":{"##,
+            r##""general":{"lang":"de","linktrail":"/^([a-zäöüß]+)(.*)$/sDu"},"##,
+            r##""namespaces":{"##,
+            r##""0":{"id":0,"case":"first-letter","name":"","content":true},"##,
+            r##""6":{"id":6,"case":"first-letter","name":"Datei","canonical":"File"},"##,
+            r##""14":{"id":14,"case":"first-letter","name":"Kategorie","canonical":"Category"}},"##,
+            r##""namespacealiases":[{"id":6,"alias":"Bild"}],"##,
+            r##""magicwords":["##,
+            r##"{"name":"redirect","aliases":["#WEITERLEITUNG","#REDIRECT"],"case-sensitive":false},"##,
+            r##"{"name":"notoc","aliases":["__KEIN_INHALTSVERZEICHNIS__","__NOTOC__"],"case-sensitive":true},"##,
+            r##"{"name":"lc","aliases":["KLEIN:","LC:"],"case-sensitive":false},"##,
+            r##"{"name":"pagename","aliases":["SEITENNAME","PAGENAME"],"case-sensitive":true}],"##,
+            r##""functionhooks":["lc","pagename"],"variables":["pagename"],"##,
+            r##""extensiontags":["<nowiki>","<ref>"],"##,
+            r##""protocols":["http://","https://"],"##,
+            r##""interwikimap":["##,
+            r##"{"prefix":"en","local":true,"language":"English","url":"https://en.wikipedia.org/wiki/$1"},"##,
+            r##"{"prefix":"wikt","local":true,"url":"https://de.wiktionary.org/wiki/$1"}]}}"##
+        ))
+        .unwrap();
+        assert_eq!(configuration.namespace_name(Namespace::FILE), Some("Datei"));
+        assert_eq!(
+            configuration.parse_title("Bild:Zentrosom.png").map(|title| title.namespace),
+            Some(Namespace::FILE)
+        );
+        let output = configuration
+            .parse(WikiText::new("#WEITERLEITUNG [[Zentrosom]]"))
+            .await;
+        assert!(matches!(output.nodes.as_slice(), [Node::Redirect { .. }]));
+        let output = configuration
+            .parse(WikiText::new(
+                "__KEIN_INHALTSVERZEICHNIS__[[Zentrosom]]ös [[Kategorie:Zellbiologie]][[en:Centrosome]]",
+            ))
+            .await;
+        assert!(matches!(
+            output.nodes.as_slice(),
+            [
+                Node::MagicWord { .. },
+                Node::Link { end: 43, .. },
+                Node::Text { start: 43, .. },
+                Node::Category { .. },
+                Node::InterlanguageLink { .. },
+            ]
+        ));
+        assert_eq!(configuration.parse_template_name("klein: X"), None);
+        assert_eq!(configuration.parse_template_name("SEITENNAME"), None);
+        assert_eq!(configuration.parse_template_name("SEITENNAME:X"), None);
+        assert!(configuration.parse_template_name("Seitenname").is_some());
+        assert_eq!(
+            Configuration::from_siteinfo_json("{").err(),
+            Some(super::SiteinfoError::InvalidJson)
+        );
+    }
+}
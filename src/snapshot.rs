@@ -0,0 +1,384 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use std::collections::HashMap;
+use std::fmt;
+use crate::configuration::{namespace_definitions, parser_functions, variables};
+use crate::trie::{Character, State};
+use crate::{Configuration, InterwikiClass, LinkAffix, Namespace, SiteConfig, SiteNamespace, TagClass, Text, Trie};
+
+const MAGIC: &[u8] = b"PWTS";
+
+const VERSION: u8 = 4;
+
+/// Error from loading a configuration snapshot.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SnapshotError {
+    /// The data is not a configuration snapshot or is truncated or corrupt.
+    InvalidFormat,
+
+    /// The snapshot was written by an incompatible version of this library.
+    UnsupportedVersion(u8),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::InvalidFormat => formatter.write_str("The configuration snapshot is invalid."),
+            SnapshotError::UnsupportedVersion(version) => write!(
+                formatter,
+                "The configuration snapshot has the unsupported version {}.",
+                version
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl Configuration {
+    /// Loads a configuration from a snapshot made with `to_snapshot`.
+    ///
+    /// Only the tries and the tag map are stored in their built form, which is where nearly all the time of building a configuration goes. The site configuration is stored as strings and copied into an owned `SiteConfig`, and the namespace definitions, the sets of parser functions and variables and the link prefix and link trail rules are built from it again on every load. Loading a snapshot therefore still allocates, but is much faster than building the configuration from its site configuration. A snapshot can only be loaded by the same version of this library that made it.
+    ///
+    /// Returns `SnapshotError::InvalidFormat` if the data is truncated or corrupt, without trusting any count in the data to allocate memory.
+    pub fn from_snapshot(snapshot: &[u8]) -> Result<Self, SnapshotError> {
+        let mut reader = Reader { bytes: snapshot };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::InvalidFormat);
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let site_config = read_site_config(&mut reader)?;
+        let character_entities = read_trie(&mut reader)?;
+        let interwiki_prefixes = read_trie(&mut reader)?;
+        let magic_words = read_trie(&mut reader)?;
+        let namespaces = read_trie(&mut reader)?;
+        let protocols = read_trie(&mut reader)?;
+        let redirect_magic_words = read_trie(&mut reader)?;
+        let tag_count = reader.u32()?;
+        // Each tag takes at least five bytes, so a corrupt count can't make the map allocate more memory than the snapshot is large.
+        let mut tag_name_map = HashMap::with_capacity((tag_count as usize).min(reader.bytes.len() / 5));
+        for _ in 0..tag_count {
+            let name = Text::new(reader.str()?);
+            let class = match reader.u8()? {
+                0 => TagClass::ExtensionTag,
+                1 => TagClass::Tag,
+                _ => return Err(SnapshotError::InvalidFormat),
+            };
+            tag_name_map.insert(name, class);
+        }
+        if !reader.bytes.is_empty() {
+            return Err(SnapshotError::InvalidFormat);
+        }
+        Ok(Configuration {
+            character_entities,
+            interwiki_prefixes,
+            link_prefix: LinkAffix::prefix(&site_config.link_prefix).ok_or(SnapshotError::InvalidFormat)?,
+            link_trail: LinkAffix::trail(&site_config.link_trail).ok_or(SnapshotError::InvalidFormat)?,
+            magic_words,
+            namespace_definitions: namespace_definitions(&site_config),
+            namespaces,
+            parser_functions: parser_functions(&site_config),
+            protocols,
+            redirect_magic_words,
+            tag_name_map,
+            variables: variables(&site_config),
+            site_config,
+        })
+    }
+
+    /// Returns a compact binary snapshot of the configuration, for loading with `from_snapshot`.
+    #[must_use]
+    pub fn to_snapshot(&self) -> Vec<u8> {
+        let mut snapshot = MAGIC.to_vec();
+        snapshot.push(VERSION);
+        write_site_config(&mut snapshot, &self.site_config);
+        write_trie(&mut snapshot, &self.character_entities);
+        write_trie(&mut snapshot, &self.interwiki_prefixes);
+        write_trie(&mut snapshot, &self.magic_words);
+        write_trie(&mut snapshot, &self.namespaces);
+        write_trie(&mut snapshot, &self.protocols);
+        write_trie(&mut snapshot, &self.redirect_magic_words);
+        let mut tags: Vec<_> = self.tag_name_map.iter().collect();
+        tags.sort_by(|(name1, _), (name2, _)| name1.as_ref().cmp(name2.as_ref()));
+        write_u32(&mut snapshot, tags.len());
+        for (name, class) in tags {
+            write_str(&mut snapshot, name.as_ref());
+            snapshot.push(match class {
+                TagClass::ExtensionTag => 0,
+                TagClass::Tag => 1,
+            });
+        }
+        snapshot
+    }
+}
+
+trait Payload: Copy + Sized {
+    fn read(reader: &mut Reader) -> Result<Self, SnapshotError>;
+
+    fn write(self, snapshot: &mut Vec<u8>);
+}
+
+impl Payload for () {
+    fn read(_: &mut Reader) -> Result<Self, SnapshotError> {
+        Ok(())
+    }
+
+    fn write(self, _: &mut Vec<u8>) {}
+}
+
+impl Payload for (char, Option<char>) {
+    fn read(reader: &mut Reader) -> Result<Self, SnapshotError> {
+        let character = char::from_u32(reader.u32()?).ok_or(SnapshotError::InvalidFormat)?;
+        let second_character = match reader.u32()? {
+            u32::MAX => None,
+            code_point => Some(char::from_u32(code_point).ok_or(SnapshotError::InvalidFormat)?),
+        };
+        Ok((character, second_character))
+    }
+
+    fn write(self, snapshot: &mut Vec<u8>) {
+        write_u32(snapshot, self.0 as _);
+        write_u32(snapshot, self.1.map_or(u32::MAX, |character| character as _) as _);
+    }
+}
+
+impl Payload for InterwikiClass {
+    fn read(reader: &mut Reader) -> Result<Self, SnapshotError> {
+        match reader.u8()? {
+            0 => Ok(InterwikiClass::Interwiki),
+            1 => Ok(InterwikiClass::Language),
+            _ => Err(SnapshotError::InvalidFormat),
+        }
+    }
+
+    fn write(self, snapshot: &mut Vec<u8>) {
+        snapshot.push(match self {
+            InterwikiClass::Interwiki => 0,
+            InterwikiClass::Language => 1,
+        });
+    }
+}
+
+impl Payload for Namespace {
+    fn read(reader: &mut Reader) -> Result<Self, SnapshotError> {
+        Ok(Namespace {
+            id: reader.u32()? as _,
+        })
+    }
+
+    fn write(self, snapshot: &mut Vec<u8>) {
+        write_u32(snapshot, self.id as u32 as _);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn str(&mut self) -> Result<&'a str, SnapshotError> {
+        let length = self.u32()?;
+        std::str::from_utf8(self.take(length as _)?).map_err(|_| SnapshotError::InvalidFormat)
+    }
+
+    fn string(&mut self) -> Result<String, SnapshotError> {
+        self.str().map(str::to_owned)
+    }
+
+    fn strings(&mut self) -> Result<Vec<String>, SnapshotError> {
+        let count = self.u32()?;
+        (0..count).map(|_| self.string()).collect()
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], SnapshotError> {
+        if length > self.bytes.len() {
+            return Err(SnapshotError::InvalidFormat);
+        }
+        let (bytes, remainder) = self.bytes.split_at(length);
+        self.bytes = remainder;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+fn read_site_config(reader: &mut Reader) -> Result<SiteConfig, SnapshotError> {
+    let extension_tags = reader.strings()?;
+    let html5_entities = reader.u8()? != 0;
+    let interwiki_prefixes = reader.strings()?;
+    let language_prefixes = reader.strings()?;
+    let link_prefix = reader.string()?;
+    let link_trail = reader.string()?;
+    let magic_words = reader.strings()?;
+    let namespace_count = reader.u32()?;
+    let namespaces = (0..namespace_count)
+        .map(|_| {
+            Ok(SiteNamespace {
+                aliases: reader.strings()?,
+                canonical_name: reader.string()?,
+                case_sensitive: reader.u8()? != 0,
+                id: reader.u32()? as _,
+                name: reader.string()?,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(SiteConfig {
+        extension_tags,
+        html5_entities,
+        interwiki_prefixes,
+        language_prefixes,
+        link_prefix,
+        link_trail,
+        magic_words,
+        namespaces,
+        parser_functions: reader.strings()?,
+        protocols: reader.strings()?,
+        redirect_magic_words: reader.strings()?,
+        variables: reader.strings()?,
+    })
+}
+
+fn read_trie<T: Payload>(reader: &mut Reader) -> Result<Trie<T>, SnapshotError> {
+    let offset_count = reader.u32()?;
+    let offsets = (0..offset_count)
+        .map(|_| reader.u32())
+        .collect::<Result<_, _>>()?;
+    let character_count = reader.u32()?;
+    let characters = (0..character_count)
+        .map(|_| {
+            let character = reader.u8()?;
+            let next_state = match reader.u8()? {
+                0 => State::Continue(reader.u32()?),
+                1 => State::Final(T::read(reader)?),
+                _ => return Err(SnapshotError::InvalidFormat),
+            };
+            Ok(Character {
+                character,
+                next_state,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Trie::from_raw_parts(characters, offsets).ok_or(SnapshotError::InvalidFormat)
+}
+
+fn write_site_config(snapshot: &mut Vec<u8>, site_config: &SiteConfig) {
+    write_strings(snapshot, &site_config.extension_tags);
+    snapshot.push(site_config.html5_entities as _);
+    write_strings(snapshot, &site_config.interwiki_prefixes);
+    write_strings(snapshot, &site_config.language_prefixes);
+    write_str(snapshot, &site_config.link_prefix);
+    write_str(snapshot, &site_config.link_trail);
+    write_strings(snapshot, &site_config.magic_words);
+    write_u32(snapshot, site_config.namespaces.len());
+    for namespace in &site_config.namespaces {
+        write_strings(snapshot, &namespace.aliases);
+        write_str(snapshot, &namespace.canonical_name);
+        snapshot.push(namespace.case_sensitive as _);
+        write_u32(snapshot, namespace.id as u32 as _);
+        write_str(snapshot, &namespace.name);
+    }
+    write_strings(snapshot, &site_config.parser_functions);
+    write_strings(snapshot, &site_config.protocols);
+    write_strings(snapshot, &site_config.redirect_magic_words);
+    write_strings(snapshot, &site_config.variables);
+}
+
+fn write_str(snapshot: &mut Vec<u8>, value: &str) {
+    write_u32(snapshot, value.len());
+    snapshot.extend_from_slice(value.as_bytes());
+}
+
+fn write_strings(snapshot: &mut Vec<u8>, values: &[String]) {
+    write_u32(snapshot, values.len());
+    for value in values {
+        write_str(snapshot, value);
+    }
+}
+
+fn write_trie<T: Payload>(snapshot: &mut Vec<u8>, trie: &Trie<T>) {
+    let (characters, offsets) = trie.raw_parts();
+    write_u32(snapshot, offsets.len());
+    for offset in offsets {
+        write_u32(snapshot, *offset as _);
+    }
+    write_u32(snapshot, characters.len());
+    for character in characters {
+        snapshot.push(character.character);
+        match character.next_state {
+            State::Continue(next_state) => {
+                snapshot.push(0);
+                write_u32(snapshot, next_state as _);
+            }
+            State::Final(payload) => {
+                snapshot.push(1);
+                payload.write(snapshot);
+            }
+        }
+    }
+}
+
+fn write_u32(snapshot: &mut Vec<u8>, value: usize) {
+    snapshot.extend_from_slice(&(value as u32).to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SnapshotError, MAGIC};
+    use crate::{Configuration, WikiText};
+
+    include!("../examples/test/test_cases.rs");
+
+    #[tokio::test]
+    async fn it_works() {
+        let built = Configuration::default();
+        let snapshot = built.to_snapshot();
+        let loaded = Configuration::from_snapshot(&snapshot).unwrap();
+        assert_eq!(loaded.to_source(), built.to_source());
+        assert_eq!(loaded.to_snapshot(), snapshot);
+        for (_, wiki_texts) in TEST_CASES {
+            for wiki_text in *wiki_texts {
+                assert_eq!(
+                    format!("{:?}", loaded.parse(WikiText::new(wiki_text)).await),
+                    format!("{:?}", built.parse(WikiText::new(wiki_text)).await)
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn truncated() {
+        let snapshot = Configuration::default().to_snapshot();
+        assert_eq!(
+            Configuration::from_snapshot(&snapshot[..snapshot.len() - 1]).err(),
+            Some(SnapshotError::InvalidFormat)
+        );
+    }
+
+    #[tokio::test]
+    async fn corrupt_count() {
+        let configuration = Configuration::default();
+        let snapshot = configuration.to_snapshot();
+        // The tags come last, each as the length and bytes of its name and one byte for its class, after their count.
+        let count_position = snapshot.len() - 4 - configuration.tag_name_map.keys().map(|name| name.as_ref().len() + 5).sum::<usize>();
+        for count in [0xFFFF_FFF0_u32, u32::MAX] {
+            let mut corrupt = snapshot.clone();
+            corrupt[count_position..count_position + 4].copy_from_slice(&count.to_le_bytes());
+            assert_eq!(Configuration::from_snapshot(&corrupt).err(), Some(SnapshotError::InvalidFormat));
+        }
+        let mut corrupt = snapshot.clone();
+        corrupt[MAGIC.len() + 1..MAGIC.len() + 5].copy_from_slice(&0xFFFF_FFF0_u32.to_le_bytes());
+        assert_eq!(Configuration::from_snapshot(&corrupt).err(), Some(SnapshotError::InvalidFormat));
+    }
+}
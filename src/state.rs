@@ -3,8 +3,9 @@
 // the file LICENSE at the top-level directory of this distribution.
 
 use async_recursion::async_recursion;
+use std::collections::VecDeque;
 use tokio::task::yield_now;
-use crate::{Node, TableCaption, TableRow, ListItem, Parameter, Warning, DefinitionListItem, configuration::Namespace, WikiText, Text};
+use crate::{Event, Node, NodeKind, TableCaption, TableRow, ListItem, Parameter, RelatedSpan, RelatedSpanKind, Warning, WarningContext, WarningMessage, DefinitionListItem, configuration::Namespace, InterwikiClass, WikiText, Text};
 
 pub struct OpenNode {
     pub nodes: Vec<Node>,
@@ -20,10 +21,7 @@ pub enum OpenNodeType {
     Heading {
         level: u8,
     },
-    Link {
-        namespace: Option<Namespace>,
-        target: Text,
-    },
+    Link(Link),
     OrderedList {
         items: Vec<ListItem>,
     },
@@ -45,9 +43,25 @@ pub enum OpenNodeType {
     },
 }
 
+pub struct Link {
+    pub interwiki: Option<InterwikiClass>,
+    pub leading_colon: bool,
+    pub namespace: Namespace,
+    pub target: Text,
+}
+
+pub struct Recovery {
+    pub attempted: NodeKind,
+    pub end: usize,
+    pub kind: WarningMessage,
+    pub start: usize,
+}
+
 pub struct State {
+    pub events: Option<VecDeque<Event>>,
     pub flushed_position: usize,
     pub nodes: Vec<Node>,
+    pub recoveries: Option<Vec<Recovery>>,
     pub scan_position: usize,
     pub stack: Vec<OpenNode>,
     pub warnings: Vec<Warning>,
@@ -77,6 +91,16 @@ pub enum TableState {
 }
 
 impl State {
+    /// Emits the given number of nodes from the start of the nodes at the top level as events, followed by the warnings so far, if events are being emitted.
+    pub fn emit(&mut self, count: usize) {
+        if let Some(events) = &mut self.events {
+            for node in self.nodes.drain(..count) {
+                crate::event::push_node(events, node);
+            }
+            events.extend(self.warnings.drain(..).map(Event::Warning));
+        }
+    }
+
     pub async fn flush(&mut self, end_position: usize) {
         flush(
             &mut self.nodes,
@@ -90,6 +114,16 @@ impl State {
         self.wiki_text.as_ref().as_bytes().get(position).cloned()
     }
 
+    /// Pushes a node after the nodes parsed so far, emitting the nodes at the top level as events if no node is open.
+    ///
+    /// A node pushed at the top level is never changed afterwards, unlike a text node flushed at the top level, which a rewind or a link prefix may still take back until the next node is pushed.
+    pub fn push_node(&mut self, node: Node) {
+        self.nodes.push(node);
+        if self.stack.is_empty() {
+            self.emit(self.nodes.len());
+        }
+    }
+
     pub async fn push_open_node(&mut self, type_: OpenNodeType, inner_start_position: usize) {
         let scan_position = self.scan_position;
         self.flush(scan_position).await;
@@ -102,7 +136,64 @@ impl State {
         self.flushed_position = inner_start_position;
     }
 
-    pub fn rewind(&mut self, nodes: Vec<Node>, position: usize) {
+    /// Returns the code closing the open nodes from the given index in the stack, innermost first, or `None` if any of them can't be closed by inserting code.
+    pub fn closing_code(&self, from: usize) -> Option<String> {
+        self.stack[from..]
+            .iter()
+            .rev()
+            .map(|open_node| match &open_node.type_ {
+                OpenNodeType::ExternalLink => Some("]".to_owned()),
+                OpenNodeType::Link(..) => Some("]]".to_owned()),
+                OpenNodeType::Parameter { .. } => Some("}}}".to_owned()),
+                OpenNodeType::Tag { name } => Some(format!("</{}>", name.as_ref())),
+                OpenNodeType::Template { .. } => Some("}}".to_owned()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn open_node_context(&self, open_node: &OpenNode) -> Option<WarningContext> {
+        match &open_node.type_ {
+            OpenNodeType::Tag { name } => Some(WarningContext::TagName {
+                name: name.as_ref().to_owned(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn open_node_start_span(&self, open_node: &OpenNode) -> RelatedSpan {
+        let length = match &open_node.type_ {
+            OpenNodeType::Heading { level } => *level as usize,
+            OpenNodeType::Link(..) | OpenNodeType::Table(..) | OpenNodeType::Template { .. } => 2,
+            OpenNodeType::Parameter { .. } => 3,
+            OpenNodeType::Tag { .. } => self.wiki_text.as_ref().as_bytes()[open_node.start..]
+                .iter()
+                .position(|byte| *byte == b'>')
+                .map_or(1, |position| position + 1),
+            _ => 1,
+        };
+        RelatedSpan {
+            end: open_node.start + length,
+            kind: RelatedSpanKind::Start,
+            start: open_node.start,
+        }
+    }
+
+    pub fn recover(&mut self, start: usize, attempted: impl FnOnce() -> NodeKind) {
+        if let (Some(recoveries), Some(warning)) = (&mut self.recoveries, self.warnings.last()) {
+            recoveries.retain(|recovery| recovery.start < start);
+            recoveries.push(Recovery {
+                attempted: attempted(),
+                end: warning.end,
+                kind: warning.message,
+                start,
+            });
+        }
+    }
+
+    pub fn rewind(&mut self, open_node: OpenNode) {
+        let OpenNode { nodes, start: position, type_ } = open_node;
+        self.recover(position, || node_kind(&type_));
         self.scan_position = position + 1;
         self.nodes = nodes;
         if let Some(position_before_text) = match self.nodes.last() {
@@ -141,7 +232,32 @@ impl State {
     }
 }
 
-pub async fn flush<'a>(
+fn node_kind(type_: &OpenNodeType) -> NodeKind {
+    match type_ {
+        OpenNodeType::DefinitionList { .. } => NodeKind::DefinitionList,
+        OpenNodeType::ExternalLink => NodeKind::ExternalLink,
+        OpenNodeType::Heading { level } => NodeKind::Heading { level: *level },
+        OpenNodeType::Link(Link {
+            interwiki,
+            namespace,
+            target,
+            ..
+        }) => NodeKind::Link {
+            namespace: *namespace,
+            target: target.clone(),
+            type_: crate::link::link_type(*interwiki),
+        },
+        OpenNodeType::OrderedList { .. } => NodeKind::OrderedList,
+        OpenNodeType::Parameter { .. } => NodeKind::Parameter,
+        OpenNodeType::Preformatted => NodeKind::Preformatted,
+        OpenNodeType::Table(..) => NodeKind::Table,
+        OpenNodeType::Tag { name } => NodeKind::Tag { name: name.clone() },
+        OpenNodeType::Template { .. } => NodeKind::Template,
+        OpenNodeType::UnorderedList { .. } => NodeKind::UnorderedList,
+    }
+}
+
+pub async fn flush(
     nodes: &mut Vec<Node>,
     flushed_position: usize,
     end_position: usize,
@@ -150,8 +150,11 @@ async fn change_state(
     match table.state {
         TableState::Before => {
             state.warnings.push(Warning {
+                context: None,
                 end: position_before_line_break,
+                fix: None,
                 message: WarningMessage::StrayTextInTable,
+                related: vec![],
                 start: table.start,
             });
             table
@@ -248,8 +251,11 @@ async fn parse_end(
             match table_state {
                 TableState::Before => {
                     state.warnings.push(Warning {
+                        context: None,
                         end: position_before_line_break,
+                        fix: None,
                         message: WarningMessage::StrayTextInTable,
+                        related: vec![],
                         start,
                     });
                     before.append(&mut std::mem::replace(&mut state.nodes, open_node.nodes));
@@ -314,7 +320,7 @@ async fn parse_end(
             }
             state.scan_position = position_after_token;
             state.nodes.append(&mut before);
-            state.nodes.push(Node::Table {
+            state.push_node(Node::Table {
                 attributes,
                 captions,
                 end: state.scan_position,
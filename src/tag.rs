@@ -2,7 +2,7 @@
 // This is free software distributed under the terms specified in
 // the file LICENSE at the top-level directory of this distribution.
 
-use crate::{Warning, Configuration, Node, WarningMessage, TagClass, Text};
+use crate::{fix, Warning, WarningContext, Configuration, Node, RelatedSpan, RelatedSpanKind, WarningMessage, TagClass, Text};
 use crate::state::State;
 use crate::state::OpenNodeType;
 
@@ -20,13 +20,18 @@ pub async fn parse_end_tag(state: &mut State, configuration: &Configuration) {
             _ => tag_name_end_position += 1,
         }
     }
-    let tag_name = Text::new(&state.wiki_text.as_ref()[tag_name_start_position..tag_name_end_position].to_ascii_lowercase());
+    let tag_name = Text::new(state.wiki_text.as_ref()[tag_name_start_position..tag_name_end_position].to_ascii_lowercase());
     match configuration.tag_name_map.get(&tag_name) {
         None => {
             state.scan_position = tag_name_start_position;
             state.warnings.push(Warning {
+                context: Some(WarningContext::TagName {
+                    name: tag_name.as_ref().to_owned(),
+                }),
                 end: tag_name_end_position,
+                fix: None,
                 message: WarningMessage::UnrecognizedTagName,
+                related: vec![],
                 start: tag_name_start_position,
             });
         }
@@ -39,8 +44,11 @@ pub async fn parse_end_tag(state: &mut State, configuration: &Configuration) {
                     _ => {
                         state.scan_position = tag_name_start_position;
                         state.warnings.push(Warning {
+                            context: None,
                             end: tag_end_position,
+                            fix: None,
                             message: WarningMessage::InvalidTagSyntax,
+                            related: vec![],
                             start: start_position,
                         });
                         return;
@@ -60,21 +68,32 @@ pub async fn parse_end_tag(state: &mut State, configuration: &Configuration) {
                 None => {
                     state.scan_position = tag_name_start_position;
                     state.warnings.push(Warning {
+                        context: Some(WarningContext::TagName {
+                            name: tag_name.as_ref().to_owned(),
+                        }),
                         end: tag_name_end_position,
+                        fix: Some(fix::deletion(start_position, tag_end_position + 1)),
                         message: WarningMessage::UnexpectedEndTag,
+                        related: vec![],
                         start: tag_name_start_position,
                     });
                 }
                 Some(open_node_index) => {
                     if open_node_index < state.stack.len() - 1 {
+                        let fix = state
+                            .closing_code(open_node_index + 1)
+                            .map(|code| fix::insertion(start_position, code));
+                        state.stack.truncate(open_node_index + 2);
+                        let open_node = state.stack.pop().unwrap();
                         state.warnings.push(Warning {
+                            context: state.open_node_context(&open_node),
                             end: tag_end_position,
+                            fix,
                             message: WarningMessage::MissingEndTagRewinding,
+                            related: vec![state.open_node_start_span(&open_node)],
                             start: start_position,
                         });
-                        state.stack.truncate(open_node_index + 2);
-                        let open_node = state.stack.pop().unwrap();
-                        state.rewind(open_node.nodes, open_node.start);
+                        state.rewind(open_node);
                     } else {
                         state.flush(start_position).await;
                         let open_node = state.stack.pop().unwrap();
@@ -82,7 +101,7 @@ pub async fn parse_end_tag(state: &mut State, configuration: &Configuration) {
                         state.flushed_position = tag_end_position;
                         state.scan_position = state.flushed_position;
                         let nodes = std::mem::replace(&mut state.nodes, open_node.nodes);
-                        state.nodes.push(Node::Tag {
+                        state.push_node(Node::Tag {
                             end: state.scan_position,
                             name: tag_name,
                             nodes,
@@ -99,8 +118,11 @@ pub async fn parse_end_tag(state: &mut State, configuration: &Configuration) {
                     None => {
                         state.scan_position = tag_name_start_position;
                         state.warnings.push(Warning {
+                            context: None,
                             end: tag_name_end_position,
+                            fix: None,
                             message: WarningMessage::InvalidTagSyntax,
+                            related: vec![],
                             start: tag_name_start_position,
                         });
                         return;
@@ -112,7 +134,7 @@ pub async fn parse_end_tag(state: &mut State, configuration: &Configuration) {
             state.flush(start_position).await;
             state.flushed_position = tag_end_position + 1;
             state.scan_position = state.flushed_position;
-            state.nodes.push(Node::EndTag {
+            state.push_node(Node::EndTag {
                 end: state.scan_position,
                 name: tag_name,
                 start: start_position,
@@ -136,8 +158,13 @@ pub async fn parse_start_tag(state: &mut State, configuration: &Configuration) {
         None => {
             state.scan_position = tag_name_start_position;
             state.warnings.push(Warning {
+                context: Some(WarningContext::TagName {
+                    name: tag_name.as_ref().to_owned(),
+                }),
                 end: tag_name_end_position,
+                fix: None,
                 message: WarningMessage::UnrecognizedTagName,
+                related: vec![],
                 start: tag_name_start_position,
             });
         }
@@ -149,8 +176,11 @@ pub async fn parse_start_tag(state: &mut State, configuration: &Configuration) {
             None => {
                 state.scan_position = tag_name_start_position;
                 state.warnings.push(Warning {
+                    context: None,
                     end: tag_name_end_position,
+                    fix: None,
                     message: WarningMessage::InvalidTagSyntax,
+                    related: vec![],
                     start: state.scan_position,
                 });
             }
@@ -162,7 +192,7 @@ pub async fn parse_start_tag(state: &mut State, configuration: &Configuration) {
                             state.flush(start_position).await;
                             state.flushed_position = tag_end_position;
                             state.scan_position = state.flushed_position;
-                            state.nodes.push(Node::Tag {
+                            state.push_node(Node::Tag {
                                 end: tag_end_position,
                                 name: tag_name,
                                 nodes: vec![],
@@ -191,7 +221,7 @@ pub async fn parse_start_tag(state: &mut State, configuration: &Configuration) {
                         state.flush(start_position).await;
                         state.flushed_position = tag_end_position;
                         state.scan_position = state.flushed_position;
-                        state.nodes.push(Node::StartTag {
+                        state.push_node(Node::StartTag {
                             end: tag_end_position,
                             name: tag_name,
                             start: start_position,
@@ -203,7 +233,7 @@ pub async fn parse_start_tag(state: &mut State, configuration: &Configuration) {
     }
 }
 
-async fn parse_plain_text_tag<'a>(
+async fn parse_plain_text_tag(
     state: &mut State,
     position_before_start_tag: usize,
     position_after_start_tag: usize,
@@ -213,29 +243,42 @@ async fn parse_plain_text_tag<'a>(
         match state.get_byte(state.scan_position).await {
             None => {
                 state.scan_position = position_before_start_tag + 1;
+                let fix = state.closing_code(0).map(|code| {
+                    fix::insertion(
+                        state.wiki_text.len(),
+                        format!("</{}>{}", start_tag_name.as_ref(), code),
+                    )
+                });
                 state.warnings.push(Warning {
+                    context: Some(WarningContext::TagName {
+                        name: start_tag_name.as_ref().to_owned(),
+                    }),
                     end: position_after_start_tag,
+                    fix,
                     message: WarningMessage::MissingEndTagRewinding,
+                    related: vec![],
                     start: position_before_start_tag,
                 });
                 break;
             }
-            Some(b'<') => if state.get_byte(state.scan_position + 1).await == Some(b'/')
-                && parse_plain_text_end_tag(
-                    state,
-                    position_before_start_tag,
-                    position_after_start_tag,
-                    start_tag_name.clone(),
-                ).await {
+            Some(b'<')
+                if state.get_byte(state.scan_position + 1).await == Some(b'/')
+                    && parse_plain_text_end_tag(
+                        state,
+                        position_before_start_tag,
+                        position_after_start_tag,
+                        start_tag_name.clone(),
+                    ).await =>
+            {
                 break;
-            },
+            }
             _ => {}
         }
         state.scan_position += 1;
     }
 }
 
-async fn parse_plain_text_end_tag<'a>(
+async fn parse_plain_text_end_tag(
     state: &mut State,
     position_before_start_tag: usize,
     position_after_start_tag: usize,
@@ -259,7 +302,7 @@ async fn parse_plain_text_end_tag<'a>(
             _ => position_after_end_tag_name += 1,
         }
     } + 1;
-    let end_tag_name = Text::new(&state.wiki_text.as_ref()[position_before_end_tag_name..position_after_end_tag_name].to_ascii_lowercase());
+    let end_tag_name = Text::new(state.wiki_text.as_ref()[position_before_end_tag_name..position_after_end_tag_name].to_ascii_lowercase());
     if start_tag_name == end_tag_name {
         let nodes = if position_after_start_tag < position_before_end_tag {
             vec![Node::Text {
@@ -272,7 +315,7 @@ async fn parse_plain_text_end_tag<'a>(
         };
         state.flushed_position = position_after_end_tag;
         state.scan_position = position_after_end_tag;
-        state.nodes.push(Node::Tag {
+        state.push_node(Node::Tag {
             end: position_after_end_tag,
             name: end_tag_name,
             nodes,
@@ -280,22 +323,37 @@ async fn parse_plain_text_end_tag<'a>(
         });
         return true;
     }
-    let mut found = false;
-    for open_node in &state.stack {
+    let mut found = None;
+    for (open_node_index, open_node) in state.stack.iter().enumerate() {
         if let OpenNodeType::Tag { name, .. } = &open_node.type_ {
             if name == &end_tag_name {
-                found = true;
+                found = Some(open_node_index);
                 break;
             }
         }
     }
-    if found {
+    if let Some(open_node_index) = found {
+        let fix = state.closing_code(open_node_index + 1).map(|code| {
+            fix::insertion(
+                position_before_end_tag,
+                format!("</{}>{}", start_tag_name.as_ref(), code),
+            )
+        });
         state.warnings.push(Warning {
+            context: Some(WarningContext::TagName {
+                name: start_tag_name.as_ref().to_owned(),
+            }),
             end: position_before_end_tag,
+            fix,
             message: WarningMessage::MissingEndTagRewinding,
+            related: vec![RelatedSpan {
+                end: position_after_start_tag,
+                kind: RelatedSpanKind::Start,
+                start: position_before_start_tag,
+            }],
             start: position_before_start_tag,
         });
         state.scan_position = position_before_start_tag + 1;
     }
-    found
+    found.is_some()
 }
@@ -6,7 +6,7 @@ use tokio::task::yield_now;
 
 use crate::state::{State, OpenNode};
 use crate::state::OpenNodeType;
-use crate::{Parameter, Warning, WarningMessage, Node};
+use crate::{fix, Parameter, Warning, WarningMessage, Node};
 
 
 pub async fn parse_parameter_name_end(state: &mut State) {
@@ -67,8 +67,11 @@ pub async fn parse_parameter_separator(state: &mut State) {
                 ).await;
                 *default = Some(std::mem::take(&mut state.nodes));
                 state.warnings.push(Warning {
+                    context: None,
                     end: state.scan_position + 1,
+                    fix: None,
                     message: WarningMessage::UselessTextInParameter,
+                    related: vec![],
                     start: state.scan_position,
                 });
             }
@@ -90,7 +93,7 @@ pub async fn parse_template_end(state: &mut State) {
                 let start_position = state.scan_position;
                 state.flush(start_position).await;
                 let nodes = std::mem::replace(&mut state.nodes, nodes);
-                state.nodes.push(Node::Parameter {
+                state.push_node(Node::Parameter {
                     default: Some(default.unwrap_or(nodes)),
                     end: state.scan_position,
                     name,
@@ -100,7 +103,7 @@ pub async fn parse_template_end(state: &mut State) {
                 let start_position = state.skip_whitespace_backwards(state.scan_position).await;
                 state.flush(start_position).await;
                 let nodes = std::mem::replace(&mut state.nodes, nodes);
-                state.nodes.push(Node::Parameter {
+                state.push_node(Node::Parameter {
                     default: None,
                     end: state.scan_position,
                     name: nodes,
@@ -110,12 +113,20 @@ pub async fn parse_template_end(state: &mut State) {
             state.scan_position += 3;
             state.flushed_position = state.scan_position;
         } else {
+            let open_node = OpenNode {
+                nodes,
+                start,
+                type_: OpenNodeType::Parameter { default, name },
+            };
             state.warnings.push(Warning {
+                context: None,
                 end: state.scan_position + 2,
+                fix: None,
                 message: WarningMessage::UnexpectedEndTagRewinding,
+                related: vec![state.open_node_start_span(&open_node)],
                 start: state.scan_position,
             });
-            state.rewind(nodes, start);
+            state.rewind(open_node);
         },
         Some(OpenNode {
             nodes,
@@ -140,25 +151,31 @@ pub async fn parse_template_end(state: &mut State) {
                     name
                 }
             };
-            state.nodes.push(Node::Template {
+            state.push_node(Node::Template {
                 end: state.scan_position,
                 name,
                 parameters,
                 start,
             });
         }
-        Some(OpenNode { nodes, start, .. }) => {
+        Some(open_node) => {
             state.warnings.push(Warning {
+                context: state.open_node_context(&open_node),
                 end: state.scan_position + 2,
+                fix: None,
                 message: WarningMessage::UnexpectedEndTagRewinding,
+                related: vec![state.open_node_start_span(&open_node)],
                 start: state.scan_position,
             });
-            state.rewind(nodes, start);
+            state.rewind(open_node);
         }
         _ => {
             state.warnings.push(Warning {
+                context: None,
                 end: state.scan_position + 2,
+                fix: Some(fix::deletion(state.scan_position, state.scan_position + 2)),
                 message: WarningMessage::UnexpectedEndTag,
+                related: vec![],
                 start: state.scan_position,
             });
             state.scan_position += 2;
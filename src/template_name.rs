@@ -0,0 +1,232 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use std::collections::HashMap;
+use crate::{Configuration, Namespace, Node, Output, Title};
+
+const MODIFIERS: [&str; 5] = ["msg", "msgnw", "raw", "safesubst", "subst"];
+
+/// Redirects between templates, used to resolve the different names of a template to the same canonical template.
+#[derive(Clone, Debug, Default)]
+pub struct TemplateAliases {
+    aliases: HashMap<Title, Title>,
+}
+
+/// Name of a template, normalized the same way as Mediawiki resolves the page to transclude.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TemplateName {
+    /// The modifiers before the name, such as `subst` or `msgnw`, in lowercase and in the order they are given.
+    pub modifiers: Vec<String>,
+
+    /// The title of the page to transclude, without fragment. The page is in the template namespace unless another namespace is given, or the name starts with `:` for the main namespace.
+    pub title: Title,
+}
+
+impl TemplateName {
+    /// Returns the kind of the template, which is the name of the page it transcludes with the first letter in lowercase, or `None` if the page is not in the template namespace.
+    ///
+    /// Infoboxes and citation templates are told apart by their kind, so that for example `{{Cite web}}`, `{{cite_web}}` and `{{Template:cite web}}` are all of the kind `cite web`.
+    #[must_use]
+    pub fn kind(&self) -> Option<String> {
+        if self.title.namespace != Namespace::TEMPLATE {
+            return None;
+        }
+        let mut characters = self.title.name.chars();
+        let first_character = characters.next()?;
+        Some(first_character.to_lowercase().chain(characters).collect())
+    }
+}
+
+impl Configuration {
+    /// Parses the name of a template into the title of the page to transclude, the same way as Mediawiki.
+    ///
+    /// Modifiers such as `subst:`, `safesubst:` and `msgnw:` are separated from the name, and the name is normalized like the target of a link. Returns `None` if the name is not a valid title, such as for a parser function with `#`, or if the name is a configured variable or has a configured parser function before `:`.
+    #[must_use]
+    pub fn parse_template_name(&self, name: &str) -> Option<TemplateName> {
+        let mut modifiers = vec![];
+        let mut name = name.trim_start();
+        while let Some(position) = name.find(':') {
+            let modifier = name[..position].trim().to_lowercase();
+            if !MODIFIERS.contains(&(&modifier as &str)) {
+                break;
+            }
+            modifiers.push(modifier);
+            name = name[position + 1..].trim_start();
+        }
+        if self.variables.contains(name.trim_end()) {
+            return None;
+        }
+        if let Some(position) = name.find(':') {
+            if self.parser_functions.contains(&name[..position].trim_end().to_lowercase()) {
+                return None;
+            }
+        }
+        let mut title = self.parse_title(name)?;
+        if !name.starts_with(':') && title.namespace == Namespace::MAIN {
+            title = match self.namespace_name(Namespace::TEMPLATE) {
+                None => Title {
+                    namespace: Namespace::TEMPLATE,
+                    ..title
+                },
+                Some(namespace_name) => self.parse_title(&format!("{}:{}", namespace_name, name))?,
+            };
+        }
+        if title.name.is_empty() {
+            return None;
+        }
+        title.fragment = None;
+        Some(TemplateName { modifiers, title })
+    }
+
+    /// Returns the normalized name of the given template, or `None` if the node is not a template or its name contains anything but text, such as another template.
+    #[must_use]
+    pub fn template_name(&self, node: &Node) -> Option<TemplateName> {
+        match node {
+            Node::Template { name, .. } => self.parse_template_name(&plain_text(name)?),
+            _ => None,
+        }
+    }
+}
+
+impl TemplateAliases {
+    /// Creates an empty set of aliases.
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the page with the given title as an alias of the target of its redirect. Returns `false` and adds nothing if the page is not a redirect or either title is not valid.
+    ///
+    /// The output must be the result of parsing the page with the given configuration.
+    pub fn add_page(&mut self, configuration: &Configuration, title: &str, output: &Output) -> bool {
+        let Some(target) = output.nodes.iter().find_map(|node| match node {
+            Node::Redirect { target, .. } => Some(target),
+            _ => None,
+        }) else {
+            return false;
+        };
+        match (
+            configuration.parse_title(title),
+            configuration.parse_title(target.as_ref()),
+        ) {
+            (Some(alias), Some(target)) => {
+                self.insert(alias, target);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Adds the given title as an alias of the given target.
+    pub fn insert(&mut self, alias: Title, mut target: Title) {
+        target.fragment = None;
+        self.aliases.insert(Title { fragment: None, ..alias }, target);
+    }
+
+    /// Returns the canonical title of the given title, following one redirect the same way as Mediawiki follows a redirect when transcluding a template.
+    #[must_use]
+    pub fn resolve<'a>(&'a self, title: &'a Title) -> &'a Title {
+        self.aliases.get(title).unwrap_or(title)
+    }
+}
+
+pub(crate) fn plain_text(nodes: &[Node]) -> Option<String> {
+    let mut result = String::new();
+    for node in nodes {
+        match node {
+            Node::CharacterEntity {
+                character,
+                second_character,
+                ..
+            } => {
+                result.push(*character);
+                result.extend(second_character);
+            }
+            Node::Comment { .. } => {}
+            Node::Text { value, .. } => result += value.as_ref(),
+            _ => return None,
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, Namespace, TemplateAliases, WikiText};
+
+    #[tokio::test]
+    async fn modifiers_and_namespaces() {
+        let configuration = Configuration::default();
+        let output = configuration
+            .parse(WikiText::new("{{ subst: Msgnw:infobox_<!-- c -->person|a}}{{:foo}}{{User:bar}}{{#if:x|y}}{{{{x}}}}"))
+            .await;
+        let names = output
+            .nodes
+            .iter()
+            .map(|node| configuration.template_name(node))
+            .collect::<Vec<_>>();
+        let name = names[0].as_ref().unwrap();
+        assert_eq!(name.modifiers, ["subst", "msgnw"]);
+        assert_eq!(name.title.name, "Infobox person");
+        assert_eq!(name.title.namespace, Namespace::TEMPLATE);
+        assert_eq!(names[1].as_ref().unwrap().title.namespace, Namespace::MAIN);
+        assert_eq!(names[2].as_ref().unwrap().title.namespace, Namespace::USER);
+        assert_eq!(names[3], None);
+        assert_eq!(names[4], None);
+    }
+
+    #[tokio::test]
+    async fn normalization() {
+        let configuration = Configuration::default();
+        assert_eq!(
+            configuration.parse_template_name("Template:Infobox_person#x"),
+            configuration.parse_template_name("infobox person")
+        );
+    }
+
+    #[tokio::test]
+    async fn variables_and_parser_functions() {
+        let configuration = Configuration::default();
+        assert_eq!(configuration.parse_template_name("DEFAULTSORT:X"), None);
+        assert_eq!(configuration.parse_template_name(" PAGENAME "), None);
+        assert_eq!(configuration.parse_template_name("lc:x"), None);
+        assert_eq!(configuration.parse_template_name("subst:LC: x"), None);
+        assert_eq!(configuration.parse_template_name("PAGENAME:Foo"), None);
+        assert_eq!(
+            configuration.parse_template_name("Pagename").unwrap().title.name,
+            "Pagename"
+        );
+        assert_eq!(
+            configuration.parse_template_name("Lcx:y").unwrap().title.namespace,
+            Namespace::TEMPLATE
+        );
+    }
+
+    #[tokio::test]
+    async fn aliases() {
+        let configuration = Configuration::default();
+        let name = configuration.parse_template_name("Infobox person").unwrap();
+        let mut aliases = TemplateAliases::new();
+        let redirect = configuration
+            .parse(WikiText::new("#REDIRECT [[Template:Infobox person]]"))
+            .await;
+        let page = configuration.parse(WikiText::new("{{Infobox person}}")).await;
+        assert!(aliases.add_page(&configuration, "Template:Infobox Person", &redirect));
+        assert!(!aliases.add_page(&configuration, "Template:Infobox person", &page));
+        let alias = configuration.parse_template_name("Infobox Person").unwrap();
+        assert_eq!(aliases.resolve(&alias.title), &name.title);
+        assert_eq!(aliases.resolve(&name.title), &name.title);
+    }
+
+    #[tokio::test]
+    async fn kind() {
+        let configuration = Configuration::default();
+        let kind = |name| configuration.parse_template_name(name).and_then(|name| name.kind());
+        assert_eq!(kind("Cite web").as_deref(), Some("cite web"));
+        assert_eq!(kind("subst:cite_web").as_deref(), Some("cite web"));
+        assert_eq!(kind("Template:Cite web").as_deref(), Some("cite web"));
+        assert_eq!(kind("User:Cite web"), None);
+        assert_eq!(kind(":Cite web"), None);
+    }
+}
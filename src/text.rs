@@ -4,7 +4,7 @@ use std::sync::Arc;
 /// This is the text wrapper struct for the parse input
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Text {
-    text: Arc<String>,
+    text: Arc<str>,
 }
 
 impl Text
@@ -13,8 +13,7 @@ impl Text
     /// In general just a &String or &str
     /// This struct can then be shared among threads
     pub fn new<T: AsRef<str> + Sync + Send>(value: T) -> Self {
-        let s = value.as_ref().to_owned(); // essentially a clone
-        Self { text: Arc::new(s) }
+        Self { text: Arc::from(value.as_ref()) }
     }
     /// The length of the text data
     pub fn len(&self) -> usize {
@@ -27,11 +26,11 @@ impl Text
 }
 impl AsRef<str> for Text {
     fn as_ref(&self) -> &str {
-        self.text.as_str()
+        &self.text
     }
 }
 impl TextSlice for Text {
-    fn as_str(&self, pos: usize) -> TextSliceResult {
+    fn as_str(&self, pos: usize) -> TextSliceResult<'_> {
         if pos >= self.text.len() {
             TextSliceResult::None
         }
@@ -39,7 +38,7 @@ impl TextSlice for Text {
             TextSliceResult::Some(&self.text[pos..=pos])
         }
     }
-    fn as_slice(&self, start: usize, end: usize) -> TextSliceResult {
+    fn as_slice(&self, start: usize, end: usize) -> TextSliceResult<'_> {
         if end < start || start >= self.text.len() {
             TextSliceResult::None
         }
@@ -52,10 +51,12 @@ impl TextSlice for Text {
     }
 }
 
+#[allow(dead_code)]
 pub trait TextSlice {
-    fn as_str(&self, pos: usize) -> TextSliceResult;
-    fn as_slice(&self, start: usize, end: usize) -> TextSliceResult;
+    fn as_str(&self, pos: usize) -> TextSliceResult<'_>;
+    fn as_slice(&self, start: usize, end: usize) -> TextSliceResult<'_>;
 }
+#[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum TextSliceResult<'a> {
     Some(&'a str),
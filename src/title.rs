@@ -0,0 +1,241 @@
+// Copyright 2018 Fredrik Portström <https://portstrom.com>
+// This is free software distributed under the terms specified in
+// the file LICENSE at the top-level directory of this distribution.
+
+use crate::character_entity::parse_numeric_character_reference;
+use crate::{Configuration, Namespace, Title};
+
+impl Configuration {
+    /// Parses the target of a link into a title, the same way as Mediawiki resolves link targets.
+    ///
+    /// Comments and nowiki tags are removed first, since they aren't part of the target. Percent escapes and character entities are decoded, underscores and runs of whitespace are collapsed to single spaces, and the first letter of the page name is uppercased unless the namespace is case sensitive or the title refers to another wiki. Returns `None` if the target is not a valid title.
+    #[must_use]
+    pub fn parse_title(&self, target: &str) -> Option<Title> {
+        let stripped;
+        let target = if target.contains('<') {
+            stripped = strip_comments_and_nowiki(target);
+            &stripped
+        } else {
+            target
+        };
+        let decoded;
+        let target = if target.contains('%') {
+            decoded = decode_percent_escapes(target)?;
+            &decoded
+        } else {
+            target
+        };
+        let target = normalize_whitespace(&self.decode_character_entities(target));
+        let (target, fragment) = match target.find('#') {
+            None => (&target as &str, None),
+            Some(position) => (
+                &target[..position],
+                Some(target[position + 1..].trim_end().to_owned()),
+            ),
+        };
+        let mut name = target.trim();
+        if let Some(remainder) = name.strip_prefix(':') {
+            name = remainder.trim_start();
+        }
+        let mut interwiki = None;
+        let mut namespace = Namespace::MAIN;
+        if let Ok((match_length, found_namespace)) = self.namespaces.find(name) {
+            namespace = found_namespace;
+            name = name[match_length..].trim_start();
+        } else if let Ok((match_length, _)) = self.interwiki_prefixes.find(name) {
+            interwiki = Some(name[..match_length - 1].trim_end().to_lowercase());
+            name = name[match_length..].trim_start();
+        }
+        if !is_valid_name(name)
+            || name.is_empty()
+                && interwiki.is_none()
+                && (fragment.is_none() || namespace != Namespace::MAIN)
+        {
+            return None;
+        }
+        let name = match (&interwiki, name.chars().next()) {
+            (None, Some(first_character)) if !self.is_case_sensitive(namespace) => {
+                first_character.to_uppercase().collect::<String>() + &name[first_character.len_utf8()..]
+            }
+            _ => name.to_owned(),
+        };
+        Some(Title {
+            fragment,
+            interwiki,
+            name,
+            namespace,
+        })
+    }
+
+    fn decode_character_entities(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut position = 0;
+        while let Some(offset) = text[position..].find('&') {
+            let entity_start_position = position + offset;
+            result += &text[position..entity_start_position];
+            position = entity_start_position + 1;
+            if let Ok((match_length, (character, second_character))) =
+                self.character_entities.find(&text[position..])
+            {
+                result.push(character);
+                result.extend(second_character);
+                position += match_length;
+            } else if let Some((match_length, character)) =
+                parse_numeric_character_reference(&text[position..])
+            {
+                result.push(character.unwrap_or('\u{fffd}'));
+                position += match_length;
+            } else {
+                result.push('&');
+            }
+        }
+        result + &text[position..]
+    }
+}
+
+fn decode_percent_escapes(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut position = 0;
+    while position < bytes.len() {
+        if bytes[position] == b'%' {
+            if let Some(byte) = text
+                .get(position + 1..position + 3)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            {
+                result.push(byte);
+                position += 3;
+                continue;
+            }
+        }
+        result.push(bytes[position]);
+        position += 1;
+    }
+    String::from_utf8(result).ok()
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.chars().any(|character| {
+        matches!(character, '<' | '>' | '[' | ']' | '{' | '}' | '|' | '\u{fffd}')
+            || character.is_control()
+    }) && !name.contains("~~~")
+        && name != "."
+        && name != ".."
+        && !name.starts_with("./")
+        && !name.starts_with("../")
+        && !name.contains("/./")
+        && !name.contains("/../")
+        && !name.ends_with("/.")
+        && !name.ends_with("/..")
+        && !name.starts_with(':')
+        && name.len() <= 255
+}
+
+fn nowiki_tag_length(text: &str) -> Option<usize> {
+    let name = text.strip_prefix('<')?;
+    let name = name.strip_prefix('/').unwrap_or(name);
+    if !name.get(..6)?.eq_ignore_ascii_case("nowiki") {
+        return None;
+    }
+    let remainder = name[6..].trim_start_matches([' ', '\t', '\n']);
+    let remainder = remainder.strip_prefix('/').unwrap_or(remainder);
+    remainder.strip_prefix('>').map(|after| text.len() - after.len())
+}
+
+fn strip_comments_and_nowiki(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut position = 0;
+    while let Some(offset) = text[position..].find('<') {
+        let tag_start_position = position + offset;
+        result += &text[position..tag_start_position];
+        let remainder = &text[tag_start_position..];
+        position = if let Some(comment) = remainder.strip_prefix("<!--") {
+            comment.find("-->").map_or(text.len(), |end| tag_start_position + 4 + end + 3)
+        } else if let Some(length) = nowiki_tag_length(remainder) {
+            tag_start_position + length
+        } else {
+            result.push('<');
+            tag_start_position + 1
+        };
+    }
+    result + &text[position..]
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut after_space = false;
+    for character in text.chars() {
+        match character {
+            ' ' | '_' | '\u{a0}' | '\u{1680}' | '\u{180e}' | '\u{2000}'..='\u{200a}'
+            | '\u{2028}' | '\u{2029}' | '\u{202f}' | '\u{205f}' | '\u{3000}' => {
+                if !after_space {
+                    result.push(' ');
+                }
+                after_space = true;
+            }
+            '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' => {}
+            _ => {
+                result.push(character);
+                after_space = false;
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Configuration, Namespace, Title};
+
+    #[test]
+    fn it_works() {
+        let configuration = Configuration::default();
+        assert_eq!(
+            configuration.parse_title(" foo__bar &amp;%20baz#Some_section"),
+            Some(Title {
+                fragment: Some("Some section".to_owned()),
+                interwiki: None,
+                name: "Foo bar & baz".to_owned(),
+                namespace: Namespace::MAIN,
+            })
+        );
+        assert_eq!(
+            configuration.parse_title(":category: élan"),
+            Some(Title {
+                fragment: None,
+                interwiki: None,
+                name: "Élan".to_owned(),
+                namespace: Namespace::CATEGORY,
+            })
+        );
+        assert_eq!(
+            configuration.parse_title("Wikt:centrosome"),
+            Some(Title {
+                fragment: None,
+                interwiki: Some("wikt".to_owned()),
+                name: "centrosome".to_owned(),
+                namespace: Namespace::MAIN,
+            })
+        );
+        assert_eq!(configuration.parse_title("#Section").map(|title| title.name), Some(String::new()));
+        assert_eq!(configuration.parse_title(""), None);
+        assert_eq!(
+            configuration.parse_title("User_talk:foo").map(|title| title.namespace),
+            Some(Namespace::USER_TALK)
+        );
+        assert_eq!(configuration.parse_title("File:"), None);
+        assert_eq!(configuration.parse_title("a&lt;b"), None);
+        assert_eq!(configuration.parse_title("a%7Cb"), None);
+        assert_eq!(configuration.parse_title("../a"), None);
+    }
+
+    #[test]
+    fn comments_and_nowiki() {
+        let configuration = Configuration::default();
+        for target in ["Foo<!-- c -->", "Fo<!--c-->o", "<nowiki/>Foo", "F<nowiki>o</NOWIKI >o", "Foo<!-- unclosed"] {
+            assert_eq!(configuration.parse_title(target).map(|title| title.name), Some("Foo".to_owned()), "{}", target);
+        }
+        assert_eq!(configuration.parse_title("Foo<b>"), None);
+        assert_eq!(configuration.parse_title("<!-- c -->"), None);
+    }
+}
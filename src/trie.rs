@@ -4,22 +4,71 @@
 
 use crate::case_folding_simple::CASE_FOLDING_SIMPLE;
 
-struct Character<T> {
-    character: u8,
-    next_state: State<T>,
+#[derive(Clone, Copy)]
+pub struct Character<T> {
+    pub character: u8,
+    pub next_state: State<T>,
 }
 
 #[derive(Clone, Copy)]
-enum State<T> {
+pub enum State<T> {
     Continue(u32),
     Final(T),
 }
 
 pub struct Trie<T> {
+    characters: Vec<Character<T>>,
+    offsets: Vec<u32>,
+}
+
+pub struct TrieBuilder<T> {
     states: Vec<Vec<Character<T>>>,
 }
 
 impl<T: Copy> Trie<T> {
+    pub fn find(&self, text: &str) -> Result<(usize, T), usize> {
+        let mut state = 0;
+        'outer: for (position, character1) in text.as_bytes().iter().cloned().enumerate() {
+            for character2 in self.state(state) {
+                if character1 == character2.character {
+                    match character2.next_state {
+                        State::Continue(next_state) => {
+                            state = next_state;
+                            continue 'outer;
+                        }
+                        State::Final(payload) => return Ok((position + 1, payload)),
+                    }
+                }
+            }
+            return Err(position);
+        }
+        Err(0)
+    }
+
+    pub fn from_raw_parts(characters: Vec<Character<T>>, offsets: Vec<u32>) -> Option<Self> {
+        if offsets.first() != Some(&0)
+            || offsets.last() != Some(&(characters.len() as u32))
+            || offsets.windows(2).any(|pair| pair[0] > pair[1])
+            || characters.iter().any(|character| match character.next_state {
+                State::Continue(next_state) => next_state as usize + 1 >= offsets.len(),
+                State::Final(_) => false,
+            })
+        {
+            return None;
+        }
+        Some(Trie { characters, offsets })
+    }
+
+    pub fn raw_parts(&self) -> (&[Character<T>], &[u32]) {
+        (&self.characters, &self.offsets)
+    }
+
+    fn state(&self, state: u32) -> &[Character<T>] {
+        &self.characters[self.offsets[state as usize] as usize..self.offsets[state as usize + 1] as usize]
+    }
+}
+
+impl<T: Copy> TrieBuilder<T> {
     pub fn add_case_sensitive_term(&mut self, term: &str, payload: T) -> bool {
         self.add_term_internal(term, payload, false)
     }
@@ -133,27 +182,19 @@ impl<T: Copy> Trie<T> {
         false
     }
 
-    pub fn find(&self, text: &str) -> Result<(usize, T), usize> {
-        let mut state = 0;
-        'outer: for (position, character1) in text.as_bytes().iter().cloned().enumerate() {
-            for character2 in &self.states[state as usize] {
-                if character1 == character2.character {
-                    match character2.next_state {
-                        State::Continue(next_state) => {
-                            state = next_state;
-                            continue 'outer;
-                        }
-                        State::Final(payload) => return Ok((position + 1, payload)),
-                    }
-                }
-            }
-            return Err(position);
+    pub fn build(self) -> Trie<T> {
+        let mut offsets = Vec::with_capacity(self.states.len() + 1);
+        offsets.push(0);
+        let mut characters = Vec::with_capacity(self.states.iter().map(Vec::len).sum());
+        for state in self.states {
+            characters.extend(state);
+            offsets.push(characters.len() as _);
         }
-        Err(0)
+        Trie { characters, offsets }
     }
 
     pub fn new() -> Self {
-        Trie {
+        TrieBuilder {
             states: vec![vec![]],
         }
     }
@@ -3,44 +3,174 @@
 // the file LICENSE at the top-level directory of this distribution.
 
 use std::fmt;
+use crate::Fix;
+
+/// Kind of warning from a lint rule, made by `lint::Linter`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LintMessage {
+    /// Human-readable description of what the rule checks, such as `A heading is more than one level below the previous heading.`.
+    pub description: &'static str,
+
+    /// The identifier of the rule, such as `heading-level-skip`.
+    pub rule: &'static str,
+
+    /// The severity the rule is configured with.
+    pub severity: Severity,
+}
+
+/// Place in the wiki text related to a warning, other than where the warning is.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RelatedSpan {
+    /// The byte position in the wiki text where the span ends.
+    pub end: usize,
+
+    /// How the span is related to the warning.
+    pub kind: RelatedSpanKind,
+
+    /// The byte position in the wiki text where the span starts.
+    pub start: usize,
+}
+
+/// Identifier for how a span is related to a warning.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RelatedSpanKind {
+    /// The first definition of something that is defined again where the warning is.
+    FirstDefinition,
+
+    /// The start tag or other code starting the element that the warning is about.
+    Start,
+}
+
+/// Severity of a warning. Ordered from the least severe to the most severe.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    /// Something that is well-formed but likely unintended or useless, such as a repeated empty line.
+    Info,
+
+    /// Something that is not well-formed but is rendered the way it's most likely intended.
+    Warning,
+
+    /// Something that is not well-formed and is rendered differently than it's most likely intended, such as an element that is never closed.
+    Error,
+}
 
 /// Warning from the parser telling that something is not well-formed.
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Warning {
+    /// Structured information about the warning, such as the tag name of an unrecognized tag, if any.
+    pub context: Option<WarningContext>,
+
     /// The byte position in the wiki text where the warning ends.
     pub end: usize,
 
+    /// Suggested change of the wiki text resolving the warning, if there is an obvious one.
+    pub fix: Option<Fix>,
+
     /// An identifier for the kind of warning.
     pub message: WarningMessage,
 
+    /// Other places in the wiki text related to the warning, such as the start tag of an element that is missing its end tag.
+    pub related: Vec<RelatedSpan>,
+
     /// The byte position in the wiki text where the warning starts.
     pub start: usize,
 }
 
+/// Structured information about a warning.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum WarningContext {
+    /// The name of a character entity, without `&` and `;`.
+    CharacterEntity {
+        /// The name of the character entity.
+        name: String,
+    },
+
+    /// The name of a reference.
+    ReferenceName {
+        /// The name of the reference.
+        name: String,
+    },
+
+    /// The name of a tag, in lowercase.
+    TagName {
+        /// The name of the tag.
+        name: String,
+    },
+
+    /// The name of a template parameter, or the position of a positional parameter counted from 1.
+    TemplateParameter {
+        /// The name of the parameter.
+        name: String,
+    },
+}
+
+pub(crate) const ALL: [WarningMessage; 25] = [
+    WarningMessage::DefinitionTermContinuation,
+    WarningMessage::DuplicateReferenceName,
+    WarningMessage::DuplicateTemplateParameter,
+    WarningMessage::EndTagInComment,
+    WarningMessage::InvalidCharacter,
+    WarningMessage::InvalidCharacterReference,
+    WarningMessage::InvalidHeadingSyntaxRewinding,
+    WarningMessage::InvalidLinkSyntax,
+    WarningMessage::InvalidLinkTarget,
+    WarningMessage::InvalidParameterSyntax,
+    WarningMessage::InvalidTagSyntax,
+    WarningMessage::MissingEndTagRewinding,
+    WarningMessage::RepeatedEmptyLine,
+    WarningMessage::StrayTextInTable,
+    WarningMessage::TextAfterRedirect,
+    WarningMessage::UnbalancedQuotes,
+    WarningMessage::UndefinedReferenceName,
+    WarningMessage::UnexpectedEndTagRewinding,
+    WarningMessage::UnexpectedEndTag,
+    WarningMessage::UnexpectedHeadingLevelCorrecting,
+    WarningMessage::UnrecognizedCharacterEntity,
+    WarningMessage::UnrecognizedTagName,
+    WarningMessage::UnusedReferenceName,
+    WarningMessage::UselessTextInParameter,
+    WarningMessage::UselessTextInRedirect,
+];
+
 /// Identifier for a kind of warning from the parser.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum WarningMessage {
     /// List broken by definition term.
     DefinitionTermContinuation,
 
+    /// A reference name is defined more than once with different content.
+    DuplicateReferenceName,
+
+    /// A template parameter is given more than once. The last value is used.
+    DuplicateTemplateParameter,
+
     /// End tag in comment.
     EndTagInComment,
 
     /// Invalid character.
     InvalidCharacter,
 
+    /// A numeric character reference refers to a code point that is not allowed.
+    InvalidCharacterReference,
+
     /// Invalid heading syntax. Rewinding.
     InvalidHeadingSyntaxRewinding,
 
     /// Invalid link syntax.
     InvalidLinkSyntax,
 
+    /// The target of a link is not a valid title.
+    InvalidLinkTarget,
+
     /// Invalid parameter syntax.
     InvalidParameterSyntax,
 
     /// Invalid tag syntax.
     InvalidTagSyntax,
 
+    /// Warning from a lint rule rather than from the parser.
+    Lint(LintMessage),
+
     /// Missing end tag. Rewinding.
     MissingEndTagRewinding,
 
@@ -53,6 +183,12 @@ pub enum WarningMessage {
     /// Wiki text comes after a redirect.
     TextAfterRedirect,
 
+    /// Bold or italic formatting is not balanced within the line.
+    UnbalancedQuotes,
+
+    /// A reference name is used but never defined.
+    UndefinedReferenceName,
+
     /// The end tag does not match the last start tag. Rewinding.
     UnexpectedEndTagRewinding,
 
@@ -62,9 +198,15 @@ pub enum WarningMessage {
     /// Expected heading of higher level. Correcting start of heading.
     UnexpectedHeadingLevelCorrecting,
 
+    /// A character entity with an unrecognized name was found.
+    UnrecognizedCharacterEntity,
+
     /// A tag with an unrecognized tag name was found.
     UnrecognizedTagName,
 
+    /// A reference is defined inside `<references>` but never used.
+    UnusedReferenceName,
+
     /// Useless text in parameter.
     UselessTextInParameter,
 
@@ -72,21 +214,120 @@ pub enum WarningMessage {
     UselessTextInRedirect,
 }
 
+impl Severity {
+    /// Lowercase name of the severity, such as `error`.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+impl Warning {
+    /// Returns the severity of the warning.
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        self.message.severity()
+    }
+}
+
 impl WarningMessage {
+    /// Stable code identifying the kind of warning, such as `PWT0007`. The code of a kind of warning never changes, and codes are never reused. A new kind of warning always takes the next free number, wherever it goes in the alphabetical order of the kinds, so the codes are not in the same order as the kinds. The code of a warning from a lint rule is the identifier of the rule.
+    #[must_use]
+    pub fn code(self) -> &'static str {
+        match self {
+            WarningMessage::DefinitionTermContinuation => "PWT0001",
+            WarningMessage::DuplicateReferenceName => "PWT0003",
+            WarningMessage::DuplicateTemplateParameter => "PWT0004",
+            WarningMessage::EndTagInComment => "PWT0002",
+            WarningMessage::InvalidCharacter => "PWT0005",
+            WarningMessage::InvalidCharacterReference => "PWT0006",
+            WarningMessage::InvalidHeadingSyntaxRewinding => "PWT0007",
+            WarningMessage::InvalidLinkSyntax => "PWT0008",
+            WarningMessage::InvalidLinkTarget => "PWT0009",
+            WarningMessage::InvalidParameterSyntax => "PWT0010",
+            WarningMessage::InvalidTagSyntax => "PWT0011",
+            WarningMessage::Lint(lint) => lint.rule,
+            WarningMessage::MissingEndTagRewinding => "PWT0012",
+            WarningMessage::RepeatedEmptyLine => "PWT0013",
+            WarningMessage::StrayTextInTable => "PWT0014",
+            WarningMessage::TextAfterRedirect => "PWT0015",
+            WarningMessage::UnbalancedQuotes => "PWT0016",
+            WarningMessage::UndefinedReferenceName => "PWT0020",
+            WarningMessage::UnexpectedEndTagRewinding => "PWT0017",
+            WarningMessage::UnexpectedEndTag => "PWT0018",
+            WarningMessage::UnexpectedHeadingLevelCorrecting => "PWT0019",
+            WarningMessage::UnrecognizedCharacterEntity => "PWT0021",
+            WarningMessage::UnrecognizedTagName => "PWT0022",
+            WarningMessage::UnusedReferenceName => "PWT0025",
+            WarningMessage::UselessTextInParameter => "PWT0023",
+            WarningMessage::UselessTextInRedirect => "PWT0024",
+        }
+    }
+
+    /// Returns the kind of warning with the given code, or `None` if the code is not the code of any kind of warning from the parser.
+    #[must_use]
+    pub fn from_code(code: &str) -> Option<Self> {
+        ALL.iter().copied().find(|message| message.code() == code)
+    }
+
+    /// Severity of the warning.
+    #[must_use]
+    pub fn severity(self) -> Severity {
+        match self {
+            WarningMessage::InvalidHeadingSyntaxRewinding
+            | WarningMessage::InvalidLinkSyntax
+            | WarningMessage::InvalidLinkTarget
+            | WarningMessage::InvalidParameterSyntax
+            | WarningMessage::InvalidTagSyntax
+            | WarningMessage::MissingEndTagRewinding
+            | WarningMessage::UndefinedReferenceName
+            | WarningMessage::UnexpectedEndTag
+            | WarningMessage::UnexpectedEndTagRewinding => Severity::Error,
+            WarningMessage::DefinitionTermContinuation
+            | WarningMessage::RepeatedEmptyLine
+            | WarningMessage::UselessTextInParameter
+            | WarningMessage::UselessTextInRedirect => Severity::Info,
+            WarningMessage::DuplicateReferenceName
+            | WarningMessage::DuplicateTemplateParameter
+            | WarningMessage::EndTagInComment
+            | WarningMessage::InvalidCharacter
+            | WarningMessage::InvalidCharacterReference
+            | WarningMessage::StrayTextInTable
+            | WarningMessage::TextAfterRedirect
+            | WarningMessage::UnbalancedQuotes
+            | WarningMessage::UnexpectedHeadingLevelCorrecting
+            | WarningMessage::UnrecognizedCharacterEntity
+            | WarningMessage::UnrecognizedTagName
+            | WarningMessage::UnusedReferenceName => Severity::Warning,
+            WarningMessage::Lint(lint) => lint.severity,
+        }
+    }
+
     /// Human-readable description of the warning.
     pub fn message(self) -> &'static str {
         match self {
             WarningMessage::DefinitionTermContinuation => "List broken by definition term.",
+            WarningMessage::DuplicateReferenceName => "A reference name is defined more than once with different content.",
+            WarningMessage::DuplicateTemplateParameter => "A template parameter is given more than once. The last value is used.",
             WarningMessage::EndTagInComment => "End tag in comment.",
             WarningMessage::InvalidCharacter => "Invalid character.",
+            WarningMessage::InvalidCharacterReference => "A numeric character reference refers to a code point that is not allowed.",
             WarningMessage::InvalidHeadingSyntaxRewinding => "Invalid heading syntax. Rewinding.",
             WarningMessage::InvalidLinkSyntax => "Invalid link syntax.",
+            WarningMessage::InvalidLinkTarget => "The target of a link is not a valid title.",
             WarningMessage::InvalidParameterSyntax => "Invalid parameter syntax.",
             WarningMessage::InvalidTagSyntax => "Invalid tag syntax.",
+            WarningMessage::Lint(lint) => lint.description,
             WarningMessage::MissingEndTagRewinding => "Missing end tag. Rewinding.",
             WarningMessage::RepeatedEmptyLine => "Repeated empty line.",
             WarningMessage::StrayTextInTable => "Stray text in table.",
             WarningMessage::TextAfterRedirect => "Wiki text comes after a redirect.",
+            WarningMessage::UnbalancedQuotes => "Bold or italic formatting is not balanced within the line.",
+            WarningMessage::UndefinedReferenceName => "A reference name is used but never defined.",
             WarningMessage::UnexpectedEndTagRewinding => {
                 "The end tag does not match the last start tag. Rewinding."
             }
@@ -96,15 +337,120 @@ impl WarningMessage {
             WarningMessage::UnexpectedHeadingLevelCorrecting => {
                 "Expected heading of higher level. Correcting start of heading."
             }
+            WarningMessage::UnrecognizedCharacterEntity => "A character entity with an unrecognized name was found.",
             WarningMessage::UnrecognizedTagName => "A tag with an unrecognized tag name was found.",
+            WarningMessage::UnusedReferenceName => "A reference is defined inside references but never used.",
             WarningMessage::UselessTextInParameter => "Useless text in parameter.",
             WarningMessage::UselessTextInRedirect => "Useless text in redirect.",
         }
     }
 }
 
+impl fmt::Display for Severity {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.name())
+    }
+}
+
 impl fmt::Display for WarningMessage {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         formatter.write_str(self.message())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ALL;
+    use crate::{
+        Configuration, RelatedSpan, RelatedSpanKind, Severity, WarningContext, WarningMessage,
+        WikiText,
+    };
+
+    // The match has no wildcard, so a kind of warning added to `WarningMessage` fails to compile here until it's given its position in `ALL`.
+    fn position_in_all(message: WarningMessage) -> Option<usize> {
+        match message {
+            WarningMessage::DefinitionTermContinuation => Some(0),
+            WarningMessage::DuplicateReferenceName => Some(1),
+            WarningMessage::DuplicateTemplateParameter => Some(2),
+            WarningMessage::EndTagInComment => Some(3),
+            WarningMessage::InvalidCharacter => Some(4),
+            WarningMessage::InvalidCharacterReference => Some(5),
+            WarningMessage::InvalidHeadingSyntaxRewinding => Some(6),
+            WarningMessage::InvalidLinkSyntax => Some(7),
+            WarningMessage::InvalidLinkTarget => Some(8),
+            WarningMessage::InvalidParameterSyntax => Some(9),
+            WarningMessage::InvalidTagSyntax => Some(10),
+            WarningMessage::MissingEndTagRewinding => Some(11),
+            WarningMessage::RepeatedEmptyLine => Some(12),
+            WarningMessage::StrayTextInTable => Some(13),
+            WarningMessage::TextAfterRedirect => Some(14),
+            WarningMessage::UnbalancedQuotes => Some(15),
+            WarningMessage::UndefinedReferenceName => Some(16),
+            WarningMessage::UnexpectedEndTagRewinding => Some(17),
+            WarningMessage::UnexpectedEndTag => Some(18),
+            WarningMessage::UnexpectedHeadingLevelCorrecting => Some(19),
+            WarningMessage::UnrecognizedCharacterEntity => Some(20),
+            WarningMessage::UnrecognizedTagName => Some(21),
+            WarningMessage::UnusedReferenceName => Some(22),
+            WarningMessage::UselessTextInParameter => Some(23),
+            WarningMessage::UselessTextInRedirect => Some(24),
+            WarningMessage::Lint(_) => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn it_works() {
+        for (position, message) in ALL.into_iter().enumerate() {
+            assert_eq!(position_in_all(message), Some(position));
+        }
+        let codes = ALL
+            .iter()
+            .map(|message| message.code())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(codes.len(), ALL.len());
+        for message in ALL {
+            assert_eq!(WarningMessage::from_code(message.code()), Some(message));
+        }
+        assert_eq!(
+            WarningMessage::InvalidHeadingSyntaxRewinding.code(),
+            "PWT0007"
+        );
+        assert_eq!(WarningMessage::from_code("PWT9999"), None);
+        assert!(Severity::Error > Severity::Warning && Severity::Warning > Severity::Info);
+
+        let output = Configuration::default()
+            .parse(WikiText::new("a<ref>b &bogus; <blink>"))
+            .await;
+        let warning = output
+            .warnings
+            .iter()
+            .find(|warning| warning.message == WarningMessage::MissingEndTagRewinding)
+            .unwrap();
+        assert_eq!(warning.severity(), Severity::Error);
+        assert_eq!(
+            warning.context,
+            Some(WarningContext::TagName {
+                name: "ref".to_owned()
+            })
+        );
+        assert_eq!(
+            warning.related,
+            [RelatedSpan {
+                end: 6,
+                kind: RelatedSpanKind::Start,
+                start: 1,
+            }]
+        );
+        let contexts = output
+            .warnings
+            .iter()
+            .filter_map(|warning| warning.context.clone())
+            .collect::<Vec<_>>();
+        assert!(contexts.contains(&WarningContext::CharacterEntity {
+            name: "bogus".to_owned()
+        }));
+        assert!(contexts.contains(&WarningContext::TagName {
+            name: "blink".to_owned()
+        }));
+    }
+}